@@ -1,50 +1,82 @@
 // 一个简单的命令行计算器，展示控制流的使用
+// 表达式的解析和求值来自 calculator 库，与 projects/calculator 共用同一套运算逻辑
 use std::io;
+use std::path::Path;
+
+use calculator::plot;
+use calculator::{Calculator, FormatOptions, History, HistoryEntry, Interpreter};
 
 fn main() {
+    let calculator = Calculator::new();
+    // 解释器保存赋值过的变量，支持 while 和 for 循环
+    let mut interpreter = Interpreter::new(&calculator);
+    // 本次会话的输出格式，可以用 format 命令修改
+    let mut session_format = FormatOptions::default();
+    // 历史记录保存在用户数据目录下，打不开时只记录在内存中
+    let mut history = History::open_default().unwrap_or_else(|e| {
+        println!("警告: {}，历史记录不会保存", e);
+        History::in_memory()
+    });
+    
     println!("简易计算器");
-    println!("支持的操作: +, -, *, /, %");
-    println!("输入 'q' 退出");
+    println!("支持的操作: {}", calculator.registry().symbols().join(", "));
+    println!("每行输入一个表达式，例如 (1 + 2) * 3，也可以赋值和使用循环");
+    println!("输入 'help' 查看格式选项和历史命令，输入 'q' 退出");
     
-    loop {
-        // 获取第一个数字
-        let first_number = match get_number("请输入第一个数字: ") {
-            Some(num) => num,
-            None => break,  // 退出程序
-        };
-        
-        // 获取操作符
-        let operator = match get_operator() {
-            Some(op) => op,
-            None => break,  // 退出程序
-        };
-        
-        // 获取第二个数字
-        let second_number = match get_number("请输入第二个数字: ") {
-            Some(num) => num,
-            None => break,  // 退出程序
-        };
-        
-        // 执行计算
-        match calculate(first_number, operator, second_number) {
-            Ok(result) => println!("结果: {}", result),
-            Err(e) => println!("错误: {}", e),
+    // 读取一行输入，输入 q 时退出程序
+    while let Some(input) = get_line("> ") {
+        // 根据第一个单词选择要执行的操作
+        match input.split_whitespace().next() {
+            None => continue,
+            Some("help") => print_help(),
+            Some("format") => {
+                let args: Vec<String> = input.split_whitespace().skip(1).map(String::from).collect();
+                match session_format.apply_args(&args) {
+                    Ok(rest) if rest.is_empty() => println!("当前格式: {}", session_format),
+                    Ok(rest) => println!("无法识别的参数: {}", rest.join(" ")),
+                    Err(e) => println!("错误: {}", e),
+                }
+            }
+            Some("plot") => match plot::run(&calculator, &input["plot".len()..], interpreter.variables()) {
+                Ok(chart) => print!("{}", chart),
+                Err(e) => println!("错误: {}", e),
+            },
+            Some("history") => show_history(&history, &input["history".len()..]),
+            Some("export") => match input.split_whitespace().nth(1) {
+                Some(file) => match history.export_session(Path::new(file)) {
+                    Ok(()) => println!("已导出本次会话到 {}", file),
+                    Err(e) => println!("错误: {}", e),
+                },
+                None => println!("用法: export <文件.md>"),
+            },
+            Some(word) if word.starts_with('!') => {
+                // !n 重新执行第 n 条历史记录
+                let entry = word[1..].parse().ok().and_then(|n| history.get(n)).cloned();
+                match entry {
+                    Some(entry) => {
+                        println!("> {}", entry.input);
+                        run_line(&mut interpreter, &session_format, &mut history, &entry.input);
+                    }
+                    None => println!("没有这条历史记录: {}", word),
+                }
+            }
+            Some(_) => run_line(&mut interpreter, &session_format, &mut history, &input),
         }
-        
-        println!("\n----------------------------\n");
     }
     
     println!("感谢使用计算器!");
 }
 
-// 获取用户输入的数字
-fn get_number(prompt: &str) -> Option<f64> {
+// 读取一行输入，输入 q 或读到输入末尾时返回 None
+fn get_line(prompt: &str) -> Option<String> {
     loop {
-        println!("{}", prompt);
+        print!("{}", prompt);
+        io::Write::flush(&mut io::stdout()).expect("刷新输出失败");
         
         let mut input = String::new();
         
         match io::stdin().read_line(&mut input) {
+            Ok(0) => return None,
             Ok(_) => {
                 // 去除两端空白
                 let input = input.trim();
@@ -54,11 +86,7 @@ fn get_number(prompt: &str) -> Option<f64> {
                     return None;
                 }
                 
-                // 尝试将输入解析为数字
-                match input.parse::<f64>() {
-                    Ok(num) => return Some(num),
-                    Err(_) => println!("无效的数字，请重新输入"),
-                }
+                return Some(input.to_string());
             }
             Err(e) => {
                 println!("无法读取输入: {}", e);
@@ -68,73 +96,96 @@ fn get_number(prompt: &str) -> Option<f64> {
     }
 }
 
-// 获取用户输入的操作符
-fn get_operator() -> Option<char> {
-    loop {
-        println!("请输入操作符 (+, -, *, /, %): ");
-        
-        let mut input = String::new();
-        
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => {
-                // 去除两端空白
-                let input = input.trim();
-                
-                // 检查是否要退出
-                if input.to_lowercase() == "q" {
-                    return None;
-                }
-                
-                // 检查操作符是否有效
-                if input.len() != 1 {
-                    println!("请输入单个字符作为操作符");
-                    continue;
-                }
-                
-                let op = input.chars().next().unwrap();
-                
-                match op {
-                    '+' | '-' | '*' | '/' | '%' => return Some(op),
-                    _ => println!("无效的操作符，请重新输入"),
-                }
-            }
-            Err(e) => {
-                println!("无法读取输入: {}", e);
-                continue;
-            }
-        }
+// 计算一行并把输入和输出一起记入历史
+fn run_line(interpreter: &mut Interpreter, session_format: &FormatOptions, history: &mut History, input: &str) {
+    let output = evaluate_line(interpreter, session_format, input);
+    // 只有赋值的行没有输出
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+    
+    if let Err(e) = history.record(input, &output) {
+        println!("警告: {}", e);
     }
 }
 
-// 执行计算操作
-fn calculate(a: f64, op: char, b: f64) -> Result<f64, String> {
-    match op {
-        '+' => Ok(a + b),
-        '-' => Ok(a - b),
-        '*' => Ok(a * b),
-        '/' => {
-            if b == 0.0 {
-                Err(String::from("除数不能为零"))
-            } else {
-                Ok(a / b)
-            }
-        },
-        '%' => {
-            if b == 0.0 {
-                Err(String::from("模数不能为零"))
-            } else {
-                Ok(a % b)
-            }
-        },
-        _ => Err(String::from("不支持的操作符")),
+// 执行一行脚本，行尾的格式选项只对这一行生效，返回要显示的结果
+fn evaluate_line(interpreter: &mut Interpreter, session_format: &FormatOptions, input: &str) -> String {
+    let mut line_format = *session_format;
+    let words: Vec<String> = input.split_whitespace().map(String::from).collect();
+    
+    let expression = match line_format.apply_args(&words) {
+        Ok(rest) => rest.join(" "),
+        Err(e) => return format!("错误: {}", e),
+    };
+    
+    match interpreter.run(&expression) {
+        Ok(results) => results
+            .iter()
+            .map(|result| format!("结果: {}", line_format.format(result)))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Err(e) => format!("错误: {}", e),
+    }
+}
+
+// history 显示最近的记录，history search <文本> 搜索所有记录
+fn show_history(history: &History, args: &str) {
+    let args = args.trim();
+    
+    let entries = if args.is_empty() {
+        history.recent(20)
+    } else if let Some(text) = args.strip_prefix("search") {
+        history.search(text.trim())
+    } else {
+        println!("用法: history 或 history search <文本>");
+        return;
+    };
+    
+    if entries.is_empty() {
+        println!("没有历史记录");
+    }
+    for (number, entry) in entries {
+        print_entry(number, entry);
     }
 }
 
-// 要运行此程序:
-// 1. 保存为 simple_calculator.rs
-// 2. 编译: rustc simple_calculator.rs
-// 3. 运行: ./simple_calculator
+fn print_entry(number: usize, entry: &HistoryEntry) {
+    println!("{:>4}  {}", number, entry.input);
+    println!("      {}", entry.result);
+}
+
+// 显示格式选项的说明
+fn print_help() {
+    println!("输出格式选项:");
+    println!("  --auto                  自动（默认）");
+    println!("  --fixed N               固定 N 位小数");
+    println!("  --sig N                 N 位有效数字");
+    println!("  --sci [N]               科学计数法，可以指定小数位数");
+    println!("  --eng [N]               工程计数法，可以指定有效数字位数");
+    println!("  --frac [最大分母]       最佳分数近似");
+    println!("  --base N                N 进制（2 到 36）");
+    println!("  --group comma|space|locale|none  数字分组");
+    println!("用法:");
+    println!("  format --fixed 2        设置本次会话的格式");
+    println!("  format                  查看当前格式");
+    println!("  1 / 3 --frac            只对这一次计算生效");
+    println!("变量和循环:");
+    println!("  x = 1 / 3               给变量赋值");
+    println!("  x > 0 and x < 1         比较和逻辑运算（and, or, not）");
+    println!("  if x > 0 then x else -x 条件表达式");
+    println!("  for i in 1..=5 do i * i end");
+    println!("  while x < 100 do x = x * 2 end");
+    println!("绘图:");
+    println!("  plot sin(x), cos(x) from -pi to pi");
+    println!("  plot x * x from 0 to 3 --svg out.svg   同时保存为 SVG");
+    println!("  --width N / --height N  图表的宽度和高度（字符数）");
+    println!("历史记录:");
+    println!("  history                 显示最近 20 条记录");
+    println!("  history search <文本>   搜索输入或结果中包含文本的记录");
+    println!("  !n                      重新执行第 n 条记录");
+    println!("  export <文件.md>        把本次会话导出为 Markdown 笔记");
+}
 
-// 或者使用 Cargo:
-// 1. 在 Cargo.toml 中添加此文件为 bin 目标
-// 2. 运行: cargo run --bin simple_calculator
+// 要运行此程序（依赖 projects/calculator 库，需要使用 Cargo）:
+// cargo run --bin calculator
@@ -1,136 +1,103 @@
 // 学生管理系统示例
 // 展示结构体、函数、方法和所有权的实际应用
-
+// 学生和成绩的数据结构来自 student_management 库，菜单流程只通过 StudentRepository 读写数据，
+// 数据保存在哪里由启动参数 --storage memory|json|sqlite 决定（默认 json）
+// 除了交互式菜单，也支持 add、grade、list、remove 等非交互式子命令，方便脚本使用
+// 文件后端的每次修改都追加到带哈希链的审计日志中，verify-log 子命令检查日志是否被修改
+// serve 子命令在本机启动 REST API，供网页前端读写同一份数据
+// tui 子命令打开全屏终端界面，和菜单一样通过 StudentRepository 读写数据
+// GPA 换算表从 STUDENT_GRADING_FILE 或数据文件旁边的 grading_scales.json 读取，没有时使用内置换算表
+use std::env;
 use std::io;
-use std::collections::HashMap;
-
-// 定义学生结构体
-struct Student {
-    id: u32,
-    name: String,
-    age: u8,
-    grades: HashMap<String, f32>,
-}
+use std::path::PathBuf;
+use std::process;
 
-// 为 Student 实现方法
-impl Student {
-    // 关联函数（静态方法）：创建新学生
-    fn new(id: u32, name: String, age: u8) -> Student {
-        Student {
-            id,
-            name,
-            age,
-            grades: HashMap::new(),
+use student_management::cli::{self, Command, Context, ReportTarget};
+use student_management::grading;
+use student_management::import;
+use student_management::course::DEFAULT_MAX_SCORE;
+use student_management::filter::Filter;
+use student_management::gradebook::{Assessment, Category};
+use student_management::ranking::{Limit, RankStyle};
+use student_management::report::ReportFormat;
+use student_management::server::ApiServer;
+use student_management::student::print_student_list;
+use student_management::tui;
+use student_management::audit::{self, AuditedRepository};
+use student_management::history::{Clock, SystemClock};
+use student_management::undo::Undoable;
+use student_management::{Course, GradingConfig, StudentRepository};
+
+// 主函数 - 程序入口
+fn main() {
+    // 启动时自动加载上次保存的数据，文件损坏时提示如何恢复并退出，不会覆盖原文件
+    let args: Vec<String> = env::args().skip(1).collect();
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            eprintln!("{}", cli::USAGE);
+            process::exit(2);
         }
-    }
-    
-    // 添加课程成绩
-    fn add_grade(&mut self, subject: String, score: f32) {
-        self.grades.insert(subject, score);
-    }
-    
-    // 计算平均分
-    fn average_grade(&self) -> f32 {
-        if self.grades.is_empty() {
-            return 0.0;
+    };
+    let mut context = match GradingConfig::load_or_builtin(&grading::default_path()) {
+        Ok(grading) => Context::new(grading),
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            process::exit(1);
         }
-        
-        let sum: f32 = self.grades.values().sum();
-        sum / (self.grades.len() as f32)
-    }
-    
-    // 显示学生信息
-    fn display(&self) {
-        println!("学生ID: {}", self.id);
-        println!("姓名: {}", self.name);
-        println!("年龄: {}", self.age);
-        println!("课程成绩:");
-        
-        if self.grades.is_empty() {
-            println!("  尚无课程成绩");
-        } else {
-            for (subject, score) in &self.grades {
-                println!("  {}: {:.1}", subject, score);
-            }
-            println!("平均分: {:.2}", self.average_grade());
+    };
+    // 文件后端的每次修改都写入数据文件旁边的审计日志
+    let opened = cli.backend.open_default().and_then(|repository| match cli.backend.default_path() {
+        Some(path) => {
+            let log = audit::default_path(&path);
+            let audited = AuditedRepository::open(repository, &log, &audit::current_actor(), Box::new(SystemClock))?;
+            context.audit_log = Some(log);
+            Ok(Box::new(audited) as Box<dyn StudentRepository>)
         }
-    }
-}
-
-// 学生管理系统结构体
-struct StudentManagement {
-    students: HashMap<u32, Student>,
-    next_id: u32,
-}
-
-// 为 StudentManagement 实现方法
-impl StudentManagement {
-    // 创建新的学生管理系统
-    fn new() -> StudentManagement {
-        StudentManagement {
-            students: HashMap::new(),
-            next_id: 1,
+        None => Ok(repository),
+    });
+    let mut repository = match opened {
+        Ok(repository) => repository,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            process::exit(1);
         }
-    }
-    
-    // 添加学生
-    fn add_student(&mut self, name: String, age: u8) -> u32 {
-        let id = self.next_id;
-        let student = Student::new(id, name, age);
-        
-        self.students.insert(id, student);
-        self.next_id += 1;
-        
-        id  // 返回新添加学生的ID
-    }
-    
-    // 获取学生，返回可变引用
-    fn get_student_mut(&mut self, id: u32) -> Option<&mut Student> {
-        self.students.get_mut(&id)
-    }
-    
-    // 获取学生，返回不可变引用
-    fn get_student(&self, id: u32) -> Option<&Student> {
-        self.students.get(&id)
-    }
-    
-    // 删除学生
-    fn remove_student(&mut self, id: u32) -> bool {
-        if self.students.contains_key(&id) {
-            self.students.remove(&id);
-            true
-        } else {
-            false
-        }
-    }
-    
-    // 显示所有学生的基本信息
-    fn list_all_students(&self) {
-        if self.students.is_empty() {
-            println!("系统中尚无学生记录");
-            return;
+    };
+
+    if cli.command == Command::Interactive {
+        match cli.backend.default_path() {
+            Some(path) => println!("数据文件: {}", path.display()),
+            None => println!("数据只保存在内存中，退出后不会保留"),
         }
-        
-        println!("{:-^50}", " 学生列表 ");
-        println!("{:<5} | {:<15} | {:<5} | {:<10}", "ID", "姓名", "年龄", "平均分");
-        println!("{:-^50}", "");
-        
-        for student in self.students.values() {
-            println!(
-                "{:<5} | {:<15} | {:<5} | {:<10.2}",
-                student.id,
-                student.name,
-                student.age,
-                student.average_grade()
-            );
+        interactive(repository, &context);
+    } else if let Command::Serve { port } = cli.command {
+        let server = match ApiServer::bind(port, repository, context) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("错误: {}", e);
+                process::exit(1);
+            }
+        };
+        println!("学生管理 API 已启动: http://127.0.0.1:{}/students", server.port());
+        println!("接口说明: http://127.0.0.1:{}/openapi.json，按 Ctrl+C 停止", server.port());
+        server.run();
+    } else if cli.command == Command::Tui {
+        if let Err(e) = tui::run(repository, &context) {
+            eprintln!("错误: {}", e);
+            process::exit(1);
         }
+    } else if let Err(e) = cli::run(&cli.command, repository.as_mut(), &context, &mut io::stdout()) {
+        eprintln!("错误: {}", e);
+        process::exit(1);
     }
 }
 
-// 主函数 - 程序入口
-fn main() {
-    let mut system = StudentManagement::new();
-    
+// 交互式菜单，每次操作后等待回车；本次运行中的修改可以撤销和重做
+fn interactive(repository: Box<dyn StudentRepository>, context: &Context) {
+    let grading = &context.grading;
+    let clock = context.clock.as_ref();
+    let mut undoable = Undoable::new(repository);
     loop {
         // 显示菜单
         display_menu();
@@ -139,11 +106,21 @@ fn main() {
         let choice = get_user_choice();
         
         match choice {
-            1 => add_student_flow(&mut system),
-            2 => view_student_flow(&system),
-            3 => add_grade_flow(&mut system),
-            4 => system.list_all_students(),
-            5 => remove_student_flow(&mut system),
+            1 => add_student_flow(&mut undoable),
+            2 => view_student_flow(&undoable, grading),
+            3 => add_grade_flow(&mut undoable, clock),
+            4 => list_students_flow(&undoable, grading),
+            5 => remove_student_flow(&mut undoable),
+            6 => add_course_flow(&mut undoable),
+            7 => list_courses_flow(&undoable),
+            8 => enroll_flow(&mut undoable),
+            9 => assess_flow(&mut undoable, clock),
+            10 => grade_history_flow(&mut undoable, context),
+            11 => undo_flow(&mut undoable),
+            12 => redo_flow(&mut undoable),
+            13 => course_stats_flow(&mut undoable, context),
+            14 => rank_flow(&mut undoable, context),
+            15 => report_flow(&mut undoable, context),
             0 => {
                 println!("感谢使用学生管理系统！再见！");
                 break;
@@ -165,6 +142,16 @@ fn display_menu() {
     println!("3. 添加课程成绩");
     println!("4. 列出所有学生");
     println!("5. 删除学生");
+    println!("6. 添加课程");
+    println!("7. 列出所有课程");
+    println!("8. 选课");
+    println!("9. 记录作业或考试成绩");
+    println!("10. 查看成绩修改历史");
+    println!("11. 撤销");
+    println!("12. 重做");
+    println!("13. 课程统计");
+    println!("14. 排名");
+    println!("15. 生成成绩单");
     println!("0. 退出系统");
     println!("{:=^50}", "");
     print!("请选择操作: ");
@@ -193,25 +180,44 @@ fn get_user_input(prompt: &str) -> String {
 }
 
 // 辅助函数 - 添加学生流程
-fn add_student_flow(system: &mut StudentManagement) {
+fn add_student_flow(system: &mut dyn StudentRepository) {
     println!("\n{:-^50}", " 添加新学生 ");
     
     let name = get_user_input("请输入学生姓名: ");
     
     let age: u8 = loop {
         let age_str = get_user_input("请输入学生年龄: ");
-        match age_str.parse() {
+        match import::parse_age(&age_str) {
             Ok(age) => break age,
             Err(_) => println!("无效年龄，请输入1-150之间的数字"),
         }
     };
     
-    let id = system.add_student(name.clone(), age);
+    let class = get_user_input("请输入班级（没有分班直接回车）: ");
+
+    let id = match system.insert(name.clone(), age) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("添加失败: {}", e);
+            return;
+        }
+    };
+    if !class.is_empty() {
+        let result = system.get(id).and_then(|student| {
+            let mut student = student.ok_or_else(|| format!("未找到ID为 {} 的学生", id))?;
+            student.class = Some(class.clone());
+            system.update(student)
+        });
+        if let Err(e) = result {
+            println!("学生已添加，ID为: {}，但设置班级失败: {}", id, e);
+            return;
+        }
+    }
     println!("学生 {} 已添加，ID为: {}", name, id);
 }
 
 // 辅助函数 - 查看学生信息流程
-fn view_student_flow(system: &StudentManagement) {
+fn view_student_flow(system: &dyn StudentRepository, grading: &GradingConfig) {
     println!("\n{:-^50}", " 查看学生信息 ");
     
     let id_str = get_user_input("请输入学生ID: ");
@@ -223,14 +229,17 @@ fn view_student_flow(system: &StudentManagement) {
         }
     };
     
-    match system.get_student(id) {
-        Some(student) => student.display(),
-        None => println!("未找到ID为 {} 的学生", id),
+    // 课程名称和学分来自课程登记表
+    let courses = system.list_courses().unwrap_or_default();
+    match system.get(id) {
+        Ok(Some(student)) => student.display(&courses, grading),
+        Ok(None) => println!("未找到ID为 {} 的学生", id),
+        Err(e) => println!("查询失败: {}", e),
     }
 }
 
 // 辅助函数 - 添加成绩流程
-fn add_grade_flow(system: &mut StudentManagement) {
+fn add_grade_flow(system: &mut dyn StudentRepository, clock: &dyn Clock) {
     println!("\n{:-^50}", " 添加课程成绩 ");
     
     let id_str = get_user_input("请输入学生ID: ");
@@ -242,33 +251,402 @@ fn add_grade_flow(system: &mut StudentManagement) {
         }
     };
     
-    let subject = get_user_input("请输入课程名称: ");
+    let mut student = match system.get(id) {
+        Ok(Some(student)) => student,
+        Ok(None) => {
+            println!("未找到ID为 {} 的学生", id);
+            return;
+        }
+        Err(e) => {
+            println!("查询失败: {}", e);
+            return;
+        }
+    };
+
+    // 课程必须在登记表中，并且学生已经选修
+    let course = match find_course(system, &get_user_input("请输入课程代码或名称: ")) {
+        Some(course) => course,
+        None => return,
+    };
+    if !student.is_enrolled(&course.code) {
+        println!("学生 {} 没有选修课程 {}，请先选课", student.name, course.name);
+        return;
+    }
+    if let Err(e) = course.check_direct_grade() {
+        println!("{}", e);
+        return;
+    }
     
     let score: f32 = loop {
-        let score_str = get_user_input("请输入分数 (0-100): ");
-        match score_str.parse() {
-            Ok(score) => {
-                if score >= 0.0 && score <= 100.0 {
-                    break score;
-                } else {
-                    println!("分数必须在0到100之间");
-                }
-            }
+        let score_str = get_user_input(format!("请输入分数 (0-{}): ", course.max_score).as_str());
+        match import::parse_score(&score_str) {
+            Ok(score) => match course.check_score(score) {
+                Ok(()) => break score,
+                Err(e) => println!("{}", e),
+            },
             Err(_) => println!("无效分数，请输入数字"),
         }
     };
-    
-    match system.get_student_mut(id) {
-        Some(student) => {
-            student.add_grade(subject.clone(), score);
-            println!("已为学生ID {} 添加课程 {} 的成绩: {}", id, subject, score);
+
+    // 修改已有成绩时询问原因，记入成绩历史
+    let reason = match student.current_grade(&course.code) {
+        Some(old) => get_user_input(&format!("原成绩为 {}，请输入修改原因 (可直接回车): ", old)),
+        None => String::new(),
+    };
+
+    if let Err(e) = student.record_grade(&course, score, &reason, clock) {
+        println!("{}", e);
+        return;
+    }
+    match system.update(student) {
+        Ok(()) => println!("已为学生ID {} 添加课程 {} 的成绩: {}", id, course.name, score),
+        Err(e) => println!("保存失败: {}", e),
+    }
+}
+
+// 辅助函数 - 按代码或名称查找课程，找不到时提示并返回 None
+fn find_course(system: &dyn StudentRepository, text: &str) -> Option<Course> {
+    match system.find_course(text) {
+        Ok(Some(course)) => Some(course),
+        Ok(None) => {
+            println!("课程 {} 没有登记，请先添加课程", text);
+            None
+        }
+        Err(e) => {
+            println!("查询失败: {}", e);
+            None
         }
-        None => println!("未找到ID为 {} 的学生", id),
+    }
+}
+
+// 辅助函数 - 添加课程流程
+fn add_course_flow(system: &mut dyn StudentRepository) {
+    println!("\n{:-^50}", " 添加课程 ");
+
+    let code = get_user_input("请输入课程代码 (例如 MATH101): ");
+    let name = get_user_input("请输入课程名称: ");
+    let credits: f32 = match get_user_input("请输入学分: ").parse() {
+        Ok(credits) => credits,
+        Err(_) => {
+            println!("无效学分，请输入数字");
+            return;
+        }
+    };
+    let max_score_str = get_user_input("请输入满分 (直接回车为 100): ");
+    let max_score: f32 = if max_score_str.is_empty() {
+        DEFAULT_MAX_SCORE
+    } else {
+        match max_score_str.parse() {
+            Ok(max_score) => max_score,
+            Err(_) => {
+                println!("无效满分，请输入数字");
+                return;
+            }
+        }
+    };
+
+    let pass_fail = get_user_input("是否为通过制课程，不计入平均分和 GPA (y/N): ");
+    let pass_fail = pass_fail.eq_ignore_ascii_case("y");
+
+    // 评分类别用分号分隔，例如 作业=30,drop=1,late=10;期中=30;期末=40
+    let categories_str = get_user_input("请输入评分类别 (例如 作业=30,drop=1;期末=70，直接回车表示直接记录课程成绩): ");
+    let categories: Result<Vec<Category>, String> = categories_str
+        .split(';')
+        .filter(|text| !text.trim().is_empty())
+        .map(Category::parse)
+        .collect();
+    let categories = match categories {
+        Ok(categories) => categories,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let result = Course::new(&code, &name, credits, max_score)
+        .and_then(|course| course.with_categories(categories))
+        .and_then(|mut course| {
+            course.pass_fail = pass_fail;
+            let message = format!("课程 {} {} 已添加", course.code, course.name);
+            system.insert_course(course).map(|_| message)
+        });
+    match result {
+        Ok(message) => println!("{}", message),
+        Err(e) => println!("添加失败: {}", e),
+    }
+}
+
+// 辅助函数 - 列出所有课程
+fn list_courses_flow(system: &dyn StudentRepository) {
+    let courses = match system.list_courses() {
+        Ok(courses) => courses,
+        Err(e) => {
+            println!("查询失败: {}", e);
+            return;
+        }
+    };
+    if courses.is_empty() {
+        println!("尚未登记课程");
+        return;
+    }
+
+    println!("{:-^60}", " 课程列表 ");
+    println!("{:<10} | {:<15} | {:<5} | {:<5} | 计分方式", "代码", "名称", "学分", "满分");
+    println!("{:-^60}", "");
+    for course in courses {
+        println!(
+            "{:<10} | {:<15} | {:<5} | {:<5} | {}",
+            course.code,
+            course.name,
+            course.credits,
+            course.max_score,
+            if course.pass_fail { "通过制" } else { "分数" }
+        );
+        for category in &course.categories {
+            println!("  {}", category);
+        }
+    }
+}
+
+// 辅助函数 - 选课流程
+fn enroll_flow(system: &mut dyn StudentRepository) {
+    println!("\n{:-^50}", " 选课 ");
+
+    let id_str = get_user_input("请输入学生ID: ");
+    let id: u32 = match id_str.parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("无效ID，请输入数字");
+            return;
+        }
+    };
+    let mut student = match system.get(id) {
+        Ok(Some(student)) => student,
+        Ok(None) => {
+            println!("未找到ID为 {} 的学生", id);
+            return;
+        }
+        Err(e) => {
+            println!("查询失败: {}", e);
+            return;
+        }
+    };
+
+    let course = match find_course(system, &get_user_input("请输入课程代码或名称: ")) {
+        Some(course) => course,
+        None => return,
+    };
+    if !student.enroll(course.code.clone()) {
+        println!("学生 {} 已经选修了课程 {}", student.name, course.name);
+        return;
+    }
+    match system.update(student) {
+        Ok(()) => println!("学生ID {} 已选修课程 {} {}", id, course.code, course.name),
+        Err(e) => println!("保存失败: {}", e),
+    }
+}
+
+// 辅助函数 - 记录作业或考试成绩流程，课程成绩按课程的评分类别重新计算
+fn assess_flow(system: &mut dyn StudentRepository, clock: &dyn Clock) {
+    println!("\n{:-^50}", " 记录作业或考试成绩 ");
+
+    let id: u32 = match get_user_input("请输入学生ID: ").parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("无效ID，请输入数字");
+            return;
+        }
+    };
+    let mut student = match system.get(id) {
+        Ok(Some(student)) => student,
+        Ok(None) => {
+            println!("未找到ID为 {} 的学生", id);
+            return;
+        }
+        Err(e) => {
+            println!("查询失败: {}", e);
+            return;
+        }
+    };
+    let course = match find_course(system, &get_user_input("请输入课程代码或名称: ")) {
+        Some(course) => course,
+        None => return,
+    };
+    if course.categories.is_empty() {
+        println!("课程 {} 没有评分类别，请使用 \"添加课程成绩\"", course.name);
+        return;
+    }
+    for category in &course.categories {
+        println!("  {}", category);
+    }
+
+    let category = get_user_input("请输入类别: ");
+    let name = get_user_input("请输入名称 (例如 作业1，同名会替换原来的成绩): ");
+    let max_score_str = get_user_input("请输入满分 (直接回车为 100): ");
+    let max_score: f32 = if max_score_str.is_empty() {
+        DEFAULT_MAX_SCORE
+    } else {
+        match max_score_str.parse() {
+            Ok(max_score) => max_score,
+            Err(_) => {
+                println!("无效满分，请输入数字");
+                return;
+            }
+        }
+    };
+    let score = match import::parse_score(&get_user_input("请输入分数: ")) {
+        Ok(score) => score,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let days_late_str = get_user_input("迟交天数 (直接回车为 0): ");
+    let days_late: u32 = if days_late_str.is_empty() {
+        0
+    } else {
+        match days_late_str.parse() {
+            Ok(days) => days,
+            Err(_) => {
+                println!("无效天数，请输入整数");
+                return;
+            }
+        }
+    };
+
+    let result = Assessment::new(&name, &category, score, max_score, days_late)
+        .and_then(|assessment| student.record_assessment(&course, assessment, clock));
+    if let Err(e) = result {
+        println!("{}", e);
+        return;
+    }
+    let grade = student.grades.get(&course.code).copied().unwrap_or_default();
+    match system.update(student) {
+        Ok(()) => println!("已记录 {}，课程 {} 的成绩现在是 {:.2}", name.trim(), course.name, grade),
+        Err(e) => println!("保存失败: {}", e),
+    }
+}
+
+// 辅助函数 - 查看成绩修改历史，输出格式与 grade-history 子命令相同
+fn grade_history_flow(system: &mut dyn StudentRepository, context: &Context) {
+    println!("\n{:-^50}", " 成绩修改历史 ");
+
+    let id: u32 = match get_user_input("请输入学生ID: ").parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("无效ID，请输入数字");
+            return;
+        }
+    };
+    let course = get_user_input("请输入课程代码或名称: ");
+    if let Err(e) = cli::run(&Command::GradeHistory { id, course }, system, context, &mut io::stdout()) {
+        println!("{}", e);
+    }
+}
+
+// 辅助函数 - 课程统计，输出格式与 stats 子命令相同
+fn course_stats_flow(system: &mut dyn StudentRepository, context: &Context) {
+    println!("\n{:-^50}", " 课程统计 ");
+
+    let text = get_user_input("请输入课程代码或名称（直接回车统计所有课程）: ");
+    let course = if text.is_empty() { None } else { Some(text) };
+    if let Err(e) = cli::run(&Command::Stats { course }, system, context, &mut io::stdout()) {
+        println!("{}", e);
+    }
+}
+
+// 辅助函数 - 排名，输出格式与 rank 子命令相同
+fn rank_flow(system: &mut dyn StudentRepository, context: &Context) {
+    println!("\n{:-^50}", " 排名 ");
+
+    let optional = |text: String| if text.is_empty() { None } else { Some(text) };
+    let course = optional(get_user_input("请输入课程代码或名称（直接回车按平均分排名）: "));
+    let class = optional(get_user_input("请输入班级（直接回车在全部学生中排名）: "));
+    let style = if get_user_input("同分后是否跳过名次？(y/n，默认 y): ").eq_ignore_ascii_case("n") {
+        RankStyle::Dense
+    } else {
+        RankStyle::Competition
+    };
+    let limit = match get_user_input("只显示前几名（直接回车显示全部）: ").as_str() {
+        "" => None,
+        text => match text.parse() {
+            Ok(count) if count > 0 => Some(Limit::Top(count)),
+            _ => {
+                println!("无效数量: {}", text);
+                return;
+            }
+        },
+    };
+    let command = Command::Rank { course, class, style, limit };
+    if let Err(e) = cli::run(&command, system, context, &mut io::stdout()) {
+        println!("{}", e);
+    }
+}
+
+// 辅助函数 - 生成成绩单，可以输出到屏幕或保存为文件
+fn report_flow(system: &mut dyn StudentRepository, context: &Context) {
+    println!("\n{:-^50}", " 生成成绩单 ");
+
+    let id: u32 = match get_user_input("请输入学生ID: ").parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("无效ID，请输入数字");
+            return;
+        }
+    };
+    let format = match get_user_input("格式（html 或 md，默认 html）: ").as_str() {
+        "" => ReportFormat::Html,
+        name => match ReportFormat::parse(name) {
+            Ok(format) => format,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+    };
+    let output = get_user_input("保存到文件（直接回车显示在屏幕上）: ");
+    let output = if output.is_empty() { None } else { Some(PathBuf::from(output)) };
+    let command = Command::Report {
+        target: ReportTarget::Student { id, output },
+        format,
+        template: None,
+    };
+    if let Err(e) = cli::run(&command, system, context, &mut io::stdout()) {
+        println!("{}", e);
+    }
+}
+
+// 辅助函数 - 列出所有学生，可以输入筛选条件
+fn list_students_flow(system: &dyn StudentRepository, grading: &GradingConfig) {
+    let courses = system.list_courses().unwrap_or_default();
+    let text = get_user_input("筛选条件（例如 age >= 18 and has(数学)，直接回车显示全部）: ");
+    let filter = if text.is_empty() {
+        None
+    } else {
+        match Filter::parse(&text).and_then(|filter| filter.resolve(&courses)) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        }
+    };
+    match system.list() {
+        Ok(mut students) => {
+            if let Some(filter) = filter {
+                students.retain(|student| {
+                    let average = grading::weighted_average(student, &courses);
+                    filter.matches(student, average, grading.gpa(student, &courses))
+                });
+            }
+            print_student_list(&students, &courses, grading)
+        }
+        Err(e) => println!("查询失败: {}", e),
     }
 }
 
 // 辅助函数 - 删除学生流程
-fn remove_student_flow(system: &mut StudentManagement) {
+fn remove_student_flow(system: &mut dyn StudentRepository) {
     println!("\n{:-^50}", " 删除学生 ");
     
     let id_str = get_user_input("请输入要删除的学生ID: ");
@@ -283,16 +661,43 @@ fn remove_student_flow(system: &mut StudentManagement) {
     let confirm = get_user_input(format!("确认删除ID为 {} 的学生记录? (y/n): ", id).as_str());
     
     if confirm.to_lowercase() == "y" {
-        if system.remove_student(id) {
-            println!("学生ID {} 已成功删除", id);
-        } else {
-            println!("未找到ID为 {} 的学生", id);
+        match system.remove(id) {
+            Ok(true) => println!("学生ID {} 已成功删除（可以用菜单 11 撤销）", id),
+            Ok(false) => println!("未找到ID为 {} 的学生", id),
+            Err(e) => println!("删除失败: {}", e),
         }
     } else {
         println!("已取消删除操作");
     }
 }
 
+// 辅助函数 - 撤销上一次修改
+fn undo_flow(undoable: &mut Undoable) {
+    match undoable.undo() {
+        Ok(Some(description)) => println!("已撤销: {}", description),
+        Ok(None) => println!("没有可以撤销的操作"),
+        Err(e) => println!("撤销失败: {}", e),
+    }
+}
+
+// 辅助函数 - 重做上一次撤销的修改
+fn redo_flow(undoable: &mut Undoable) {
+    match undoable.redo() {
+        Ok(Some(description)) => println!("已重做: {}", description),
+        Ok(None) => println!("没有可以重做的操作"),
+        Err(e) => println!("重做失败: {}", e),
+    }
+}
+
 // 运行此程序:
-// 1. 保存为 examples/student_management.rs
-// 2. cargo run --bin student_management
\ No newline at end of file
+// cargo run --bin student_management
+// cargo run --bin student_management -- --storage sqlite
+// cargo run --bin student_management -- tui
+// cargo run --bin student_management -- add --name 张三 --age 20
+// cargo run --bin student_management -- course add --code MATH101 --name 数学 --credits 4
+// cargo run --bin student_management -- enroll 1 --course MATH101
+// cargo run --bin student_management -- list --sort avg --format csv
+// cargo run --bin student_management -- list --where "age >= 18 and avg < 60" --sort -avg,name --limit 20
+// cargo run --bin student_management -- stats 数学
+// cargo run --bin student_management -- verify-log
+// 设置环境变量 STUDENT_DATA_FILE 可以指定数据文件的位置
\ No newline at end of file
@@ -2,7 +2,7 @@
 // 演示所有权、引用、借用和切片等概念的实际应用
 
 use std::collections::HashMap;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 
 fn main() {
     println!("{:-^60}", " 文本分析器 ");
@@ -0,0 +1,284 @@
+// 求值器：在语法树上应用注册表中的运算符
+
+use std::collections::HashMap;
+
+use crate::functions;
+use crate::operator::{Arity, OperatorRegistry};
+use crate::parser::{self, Expr};
+use crate::value::Value;
+
+// 求值环境：提供变量和自定义函数，找不到时再回退到内置常量和函数
+// 返回 None 表示没有这个名字，返回 Some(Err) 表示名字存在但取值失败
+pub trait Environment {
+    fn variable(&self, name: &str) -> Option<Result<Value, String>>;
+
+    // 自定义函数，默认没有
+    fn call(&self, _name: &str, _args: &[Value]) -> Option<Result<Value, String>> {
+        None
+    }
+
+    // 区域引用展开后的所有值，默认不支持区域引用
+    fn range(&self, _from: &str, _to: &str) -> Option<Result<Vec<Value>, String>> {
+        None
+    }
+}
+
+// 只有内置常量和函数的空环境
+pub struct EmptyEnvironment;
+
+impl Environment for EmptyEnvironment {
+    fn variable(&self, _name: &str) -> Option<Result<Value, String>> {
+        None
+    }
+}
+
+impl Environment for HashMap<String, Value> {
+    fn variable(&self, name: &str) -> Option<Result<Value, String>> {
+        self.get(name).cloned().map(Ok)
+    }
+}
+
+// 对语法树求值
+pub fn evaluate(expr: &Expr, registry: &OperatorRegistry, env: &dyn Environment) -> Result<Value, String> {
+    match expr {
+        Expr::Number(value) => Ok(value.clone()),
+        Expr::Variable(name) => env
+            .variable(name)
+            .or_else(|| functions::constant(name).map(Ok))
+            .unwrap_or_else(|| Err(format!("未定义的变量: {}", name))),
+        Expr::Unary { op, operand } => {
+            let operand = evaluate(operand, registry, env)?;
+            apply(registry, op, Arity::Unary, &[operand])
+        }
+        Expr::Binary { op, left, right } => {
+            let left = evaluate(left, registry, env)?;
+            // 短路求值（例如 and / or）：左边已经能决定结果时不再计算右边，
+            // 因此 x != 0 and 1 / x > 1 在 x 为 0 时不会报错
+            let decisive = registry.binary(op).and_then(|operator| operator.short_circuit());
+            if let (Some(decisive), Value::Bool(b)) = (decisive, &left) {
+                if *b == decisive {
+                    return Ok(left);
+                }
+            }
+            let right = evaluate(right, registry, env)?;
+            apply(registry, op, Arity::Binary, &[left, right])
+        }
+        Expr::Call { name, args } => {
+            // 区域和列表参数展开成多个值，因此 sum(A1:A3, 4) 收到 4 个参数
+            let mut values = Vec::new();
+            for arg in args {
+                match arg {
+                    Expr::Range { from, to } => values.extend(evaluate_range(from, to, env)?),
+                    Expr::List(items) => {
+                        for item in items {
+                            values.push(evaluate(item, registry, env)?);
+                        }
+                    }
+                    _ => values.push(evaluate(arg, registry, env)?),
+                }
+            }
+            env.call(name, &values)
+                .or_else(|| functions::call(name, &values))
+                .or_else(|| call_polynomial_variable(name, &values, env))
+                .unwrap_or_else(|| Err(format!("未定义的函数: {}", name)))
+        }
+        Expr::Range { from, to } => Err(format!("区域引用 {}:{} 只能作为函数参数", from, to)),
+        Expr::List(_) => Err(String::from("列表只能作为函数参数，例如 poly([1, -3, 2])")),
+        Expr::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            if evaluate(condition, registry, env)?.to_bool()? {
+                evaluate(then, registry, env)
+            } else {
+                evaluate(otherwise, registry, env)
+            }
+        }
+    }
+}
+
+// 保存多项式的变量可以像函数一样求值：p = poly([1, -3, 2]) 之后 p(5) = 12
+fn call_polynomial_variable(name: &str, args: &[Value], env: &dyn Environment) -> Option<Result<Value, String>> {
+    match env.variable(name)? {
+        Ok(Value::Poly(p)) if args.len() == 1 => Some(p.evaluate(&args[0])),
+        Ok(Value::Poly(_)) => Some(Err(format!("多项式 {} 需要 1 个参数", name))),
+        _ => None,
+    }
+}
+
+fn evaluate_range(from: &str, to: &str, env: &dyn Environment) -> Result<Vec<Value>, String> {
+    env.range(from, to)
+        .unwrap_or_else(|| Err(format!("不支持区域引用: {}:{}", from, to)))
+}
+
+fn apply(registry: &OperatorRegistry, symbol: &str, arity: Arity, args: &[Value]) -> Result<Value, String> {
+    match registry.get(symbol, arity) {
+        Some(operator) => operator.eval(args),
+        None => Err(format!("不支持的操作符: {}", symbol)),
+    }
+}
+
+// 计算器：持有运算符注册表，负责把输入文本解析并求值
+pub struct Calculator {
+    registry: OperatorRegistry,
+}
+
+impl Calculator {
+    pub fn new() -> Calculator {
+        Calculator::with_registry(OperatorRegistry::default())
+    }
+
+    pub fn with_registry(registry: OperatorRegistry) -> Calculator {
+        Calculator { registry }
+    }
+
+    pub fn registry(&self) -> &OperatorRegistry {
+        &self.registry
+    }
+
+    // 注册自定义运算符等修改都通过这里进行
+    pub fn registry_mut(&mut self) -> &mut OperatorRegistry {
+        &mut self.registry
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Expr, String> {
+        parser::parse(input, &self.registry)
+    }
+
+    pub fn evaluate(&self, input: &str) -> Result<Value, String> {
+        self.evaluate_with(input, &EmptyEnvironment)
+    }
+
+    pub fn evaluate_with(&self, input: &str, env: &dyn Environment) -> Result<Value, String> {
+        let expr = self.parse(input)?;
+        evaluate(&expr, &self.registry, env)
+    }
+}
+
+impl Default for Calculator {
+    fn default() -> Calculator {
+        Calculator::new()
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::{Associativity, FnOperator, PRECEDENCE_AND, PRECEDENCE_MULTIPLICATIVE, PRECEDENCE_PREFIX};
+
+    #[test]
+    fn test_deepest_accepted_expressions() {
+        // 解析器接受的最深的表达式在测试线程的栈上也能求值
+        let calculator = Calculator::new();
+        let chain = format!("1{}", " + 1".repeat(254));
+        assert_eq!(calculator.evaluate(&chain), Ok(Value::from(255)));
+        let nested = format!("{}1{}", "(-".repeat(127), ")".repeat(127));
+        assert_eq!(calculator.evaluate(&nested), Ok(Value::from(-1)));
+        assert!(calculator.evaluate(&format!("{} + 1", chain)).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_expression() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("1 + 2 * 3"), Ok(Value::from(7)));
+        assert_eq!(calculator.evaluate("(1 + 2) * 3"), Ok(Value::from(9)));
+        assert_eq!(calculator.evaluate("-2 * 3"), Ok(Value::from(-6)));
+        assert_eq!(calculator.evaluate("7 / 2"), Ok(Value::from(3.5)));
+        assert!(calculator.evaluate("1 / 0").is_err());
+    }
+
+    #[test]
+    fn test_big_integers_stay_exact() {
+        let calculator = Calculator::new();
+        assert_eq!(
+            calculator.evaluate("9007199254740992 + 1").unwrap().to_string(),
+            "9007199254740993"
+        );
+    }
+
+    #[test]
+    fn test_variables_and_functions() {
+        let calculator = Calculator::new();
+        let mut variables = HashMap::new();
+        variables.insert(String::from("x"), Value::from(4));
+
+        assert_eq!(calculator.evaluate_with("sqrt(x) + x", &variables), Ok(Value::from(6.0)));
+        assert!(calculator.evaluate("y + 1").is_err());
+        assert!(calculator.evaluate("nope(1)").is_err());
+    }
+
+    #[test]
+    fn test_booleans_and_conditionals() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("1 + 1 == 2"), Ok(Value::from(true)));
+        assert_eq!(calculator.evaluate("not 1 > 2 and true"), Ok(Value::from(true)));
+        assert_eq!(calculator.evaluate("false or 3 <= 2"), Ok(Value::from(false)));
+        assert_eq!(calculator.evaluate("if 2 > 1 then 10 else 20"), Ok(Value::from(10)));
+        // 未选中的分支不会求值
+        assert_eq!(calculator.evaluate("if false then 1 / 0 else 5"), Ok(Value::from(5)));
+        assert_eq!(calculator.evaluate("0 != 0 and 1 / 0 > 1"), Ok(Value::from(false)));
+        assert!(calculator.evaluate("if 1 then 2 else 3").is_err());
+        assert!(calculator.evaluate("true + 1").is_err());
+    }
+
+    #[test]
+    fn test_polynomials() {
+        let calculator = Calculator::new();
+        let mut variables = HashMap::new();
+        variables.insert(String::from("p"), calculator.evaluate("poly([1, -3, 2])").unwrap());
+
+        assert_eq!(calculator.evaluate_with("p(5)", &variables), Ok(Value::from(12)));
+        assert_eq!(
+            calculator.evaluate_with("p * poly([1, 1])", &variables).unwrap().to_string(),
+            "x^3 - 2x^2 - x + 2 = (x+1)(x-1)(x-2)"
+        );
+        assert_eq!(calculator.evaluate_with("p / poly([1, -1])", &variables).unwrap().to_string(), "x - 2");
+        assert_eq!(calculator.evaluate_with("p % poly([1, 0])", &variables).unwrap().to_string(), "2");
+        assert_eq!(calculator.evaluate_with("p - 2", &variables).unwrap().to_string(), "x^2 - 3x = x(x-3)");
+        assert!(calculator.evaluate("[1, 2]").is_err());
+        assert!(calculator.evaluate_with("p(1, 2)", &variables).is_err());
+    }
+
+    #[test]
+    fn test_custom_operators() {
+        let mut calculator = Calculator::new();
+        calculator
+            .registry_mut()
+            .register(FnOperator::binary("//", PRECEDENCE_MULTIPLICATIVE, Associativity::Left, |a, b| {
+                Ok(Value::Float((a.to_f64() / b.to_f64()).floor()))
+            }))
+            .unwrap();
+        calculator
+            .registry_mut()
+            .register(FnOperator::binary("**", PRECEDENCE_PREFIX + 10, Associativity::Right, |a, b| {
+                Ok(Value::Float(a.to_f64().powf(b.to_f64())))
+            }))
+            .unwrap();
+
+        assert_eq!(calculator.evaluate("7 // 2"), Ok(Value::from(3.0)));
+        assert_eq!(calculator.evaluate("2 ** 3 ** 2"), Ok(Value::from(512.0)));
+        // 前缀负号的优先级低于 `**`，所以 -2 ** 2 = -(2 ** 2)
+        assert_eq!(calculator.evaluate("-2 ** 2"), Ok(Value::from(-4.0)));
+    }
+
+    #[test]
+    fn test_custom_short_circuit_operator() {
+        // 自定义的 && 和 and 相同：左边是 false 时直接得出 false，不再计算右边
+        let mut calculator = Calculator::new();
+        calculator
+            .registry_mut()
+            .register(
+                FnOperator::binary("&&", PRECEDENCE_AND, Associativity::Left, |a, b| {
+                    Ok(Value::Bool(a.to_bool()? && b.to_bool()?))
+                })
+                .short_circuit(false),
+            )
+            .unwrap();
+
+        assert_eq!(calculator.evaluate("1 > 2 && 1 / 0 > 1"), Ok(Value::from(false)));
+        assert_eq!(calculator.evaluate("1 < 2 && 2 > 1"), Ok(Value::from(true)));
+        assert!(calculator.evaluate("1 < 2 && 1 / 0 > 1").is_err());
+    }
+}
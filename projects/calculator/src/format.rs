@@ -0,0 +1,664 @@
+// 结果的输出格式：固定小数位、有效数字、科学计数法、工程计数法、分数近似和进制转换，
+// 以及按地区习惯的数字分组（1,234,567 或 1 234 567）
+
+use std::env;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::value::Value;
+
+// 数字的写法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notation {
+    // 整数完整打印，浮点数保留 15 位有效数字，过大或过小时改用科学计数法
+    Auto,
+    // 固定小数位数
+    Fixed(usize),
+    // 有效数字位数
+    Significant(usize),
+    // 科学计数法，可以指定小数位数
+    Scientific(Option<usize>),
+    // 工程计数法：指数总是 3 的倍数，可以指定有效数字位数
+    Engineering(Option<usize>),
+    // 用分母不超过给定值的最佳分数近似
+    Fraction(u64),
+    // 2 到 36 进制
+    Base(u32),
+}
+
+// 数字分组方式：分组符号和小数点符号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grouping {
+    pub separator: char,
+    pub decimal_point: char,
+}
+
+impl Grouping {
+    pub const COMMA: Grouping = Grouping {
+        separator: ',',
+        decimal_point: '.',
+    };
+    pub const SPACE: Grouping = Grouping {
+        separator: ' ',
+        decimal_point: '.',
+    };
+
+    // 根据地区名称（例如 en_US、fr_FR.UTF-8、de-DE）选择分组方式
+    pub fn for_locale(locale: &str) -> Grouping {
+        let language = locale
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        match language.as_str() {
+            // 法语、俄语、瑞典语等使用空格分组、逗号作小数点
+            "fr" | "ru" | "sv" | "fi" | "nb" | "cs" | "pl" | "uk" => Grouping {
+                separator: ' ',
+                decimal_point: ',',
+            },
+            // 德语、意大利语、西班牙语等使用点分组、逗号作小数点
+            "de" | "it" | "es" | "nl" | "pt" | "id" | "tr" | "da" => Grouping {
+                separator: '.',
+                decimal_point: ',',
+            },
+            _ => Grouping::COMMA,
+        }
+    }
+
+    // 从 LC_ALL、LC_NUMERIC、LANG 环境变量读取当前地区
+    pub fn from_environment() -> Grouping {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Grouping::for_locale(&locale)
+    }
+}
+
+// 完整的格式设置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatOptions {
+    pub notation: Notation,
+    pub grouping: Option<Grouping>,
+}
+
+const DEFAULT_FRACTION_DENOMINATOR: u64 = 10_000;
+const AUTO_SIGNIFICANT_DIGITS: usize = 15;
+const BASE_FRACTION_DIGITS: usize = 12;
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            notation: Notation::Auto,
+            grouping: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    pub fn format(&self, value: &Value) -> String {
+        match value {
+            Value::Float(x) if !x.is_finite() => x.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Poly(p) => p.to_string(),
+            // 超出 i64 范围的小数没有有意义的分数形式，按默认格式输出
+            Value::Float(x) if matches!(self.notation, Notation::Fraction(_)) && x.abs() > i64::MAX as f64 => {
+                FormatOptions {
+                    notation: Notation::Auto,
+                    ..*self
+                }
+                .format_decimal(value)
+            }
+            _ => match self.notation {
+                Notation::Fraction(max_denominator) => format_fraction(value, max_denominator),
+                Notation::Base(base) => format_base(value, base),
+                _ => self.format_decimal(value),
+            },
+        }
+    }
+
+    fn format_decimal(&self, value: &Value) -> String {
+        let number = Decimal::from_value(value);
+
+        let (mantissa, exponent) = match self.notation {
+            Notation::Fixed(places) => (number.round_to_places(places as i64).to_plain(places), None),
+            Notation::Significant(digits) => {
+                let rounded = number.round_to_significant(digits.max(1));
+                let places = (digits.max(1) as i64 - 1 - rounded.exponent).max(0) as usize;
+                (rounded.to_plain(places), None)
+            }
+            Notation::Scientific(places) => {
+                let rounded = match places {
+                    Some(places) => number.round_to_significant(places + 1),
+                    None => number,
+                };
+                let mantissa = Decimal {
+                    exponent: 0,
+                    ..rounded.clone()
+                };
+                (mantissa.to_plain(places.unwrap_or(0)), Some(rounded.exponent))
+            }
+            Notation::Engineering(digits) => {
+                let rounded = match digits {
+                    Some(digits) => number.round_to_significant(digits.max(1)),
+                    None => number,
+                };
+                let exponent = rounded.exponent.div_euclid(3) * 3;
+                let mantissa = Decimal {
+                    exponent: rounded.exponent - exponent,
+                    ..rounded.clone()
+                };
+                // 补足指定的有效数字位数，例如 3 位有效数字的 1.2e3 写成 1.20e3
+                let places = digits.map(|d| (d as i64 - 1 - mantissa.exponent).max(0) as usize);
+                (mantissa.to_plain(places.unwrap_or(0)), Some(exponent))
+            }
+            Notation::Auto => match value {
+                Value::Int(_) | Value::Bool(_) | Value::Poly(_) => (number.to_plain(0), None),
+                Value::Float(_) => {
+                    let rounded = number.round_to_significant(AUTO_SIGNIFICANT_DIGITS);
+                    if rounded.is_zero() || (-6..15).contains(&rounded.exponent) {
+                        (rounded.to_plain(0), None)
+                    } else {
+                        let mantissa = Decimal {
+                            exponent: 0,
+                            ..rounded.clone()
+                        };
+                        (mantissa.to_plain(0), Some(rounded.exponent))
+                    }
+                }
+            },
+            Notation::Fraction(_) | Notation::Base(_) => unreachable!(),
+        };
+
+        let text = apply_grouping(&mantissa, self.grouping);
+        match exponent {
+            Some(exponent) => format!("{}e{}", text, exponent),
+            None => text,
+        }
+    }
+
+    // 解析格式参数，返回没有被识别的其余参数
+    // 支持：--fixed N、--sig N、--sci [小数位数]、--eng [有效数字]、--frac [最大分母]、--base N、
+    //       --auto、--group comma|space|locale|none
+    pub fn apply_args(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        let mut rest = Vec::new();
+        let mut i = 0;
+        while i < args.len() {
+            let arg = args[i].as_str();
+            let name = match arg.strip_prefix("--") {
+                Some(name) if !name.is_empty() => name,
+                _ => {
+                    rest.push(args[i].clone());
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // 可选的数字参数
+            let number = args.get(i + 1).and_then(|next| next.parse::<u64>().ok());
+            let consumed = self.apply_setting(name, number, args.get(i + 1).map(|s| s.as_str()))?;
+            i += 1 + consumed;
+        }
+        Ok(rest)
+    }
+
+    // 应用一项设置，返回额外消耗的参数个数
+    fn apply_setting(&mut self, name: &str, number: Option<u64>, next: Option<&str>) -> Result<usize, String> {
+        let required = |what: &str| number.ok_or_else(|| format!("--{} 需要一个数字参数（{}）", name, what));
+
+        match name {
+            "auto" => {
+                self.notation = Notation::Auto;
+                Ok(0)
+            }
+            "fixed" => {
+                self.notation = Notation::Fixed(required("小数位数")? as usize);
+                Ok(1)
+            }
+            "sig" => {
+                let digits = required("有效数字位数")?;
+                if digits == 0 {
+                    return Err(String::from("有效数字位数至少为 1"));
+                }
+                self.notation = Notation::Significant(digits as usize);
+                Ok(1)
+            }
+            "sci" => {
+                self.notation = Notation::Scientific(number.map(|n| n as usize));
+                Ok(number.is_some() as usize)
+            }
+            "eng" => {
+                self.notation = Notation::Engineering(number.map(|n| n as usize));
+                Ok(number.is_some() as usize)
+            }
+            "frac" => {
+                let max_denominator = number.unwrap_or(DEFAULT_FRACTION_DENOMINATOR);
+                if max_denominator == 0 {
+                    return Err(String::from("最大分母至少为 1"));
+                }
+                self.notation = Notation::Fraction(max_denominator);
+                Ok(number.is_some() as usize)
+            }
+            "base" => {
+                let base = required("进制")?;
+                if !(2..=36).contains(&base) {
+                    return Err(String::from("进制必须在 2 到 36 之间"));
+                }
+                self.notation = Notation::Base(base as u32);
+                Ok(1)
+            }
+            "group" => {
+                self.grouping = match next {
+                    Some("comma") => Some(Grouping::COMMA),
+                    Some("space") => Some(Grouping::SPACE),
+                    Some("locale") => Some(Grouping::from_environment()),
+                    Some("none") => None,
+                    _ => return Err(String::from("--group 需要 comma、space、locale 或 none")),
+                };
+                Ok(1)
+            }
+            _ => Err(format!("未知的格式选项: --{}", name)),
+        }
+    }
+}
+
+impl fmt::Display for FormatOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.notation {
+            Notation::Auto => write!(f, "自动")?,
+            Notation::Fixed(places) => write!(f, "固定 {} 位小数", places)?,
+            Notation::Significant(digits) => write!(f, "{} 位有效数字", digits)?,
+            Notation::Scientific(None) => write!(f, "科学计数法")?,
+            Notation::Scientific(Some(places)) => write!(f, "科学计数法（{} 位小数）", places)?,
+            Notation::Engineering(None) => write!(f, "工程计数法")?,
+            Notation::Engineering(Some(digits)) => write!(f, "工程计数法（{} 位有效数字）", digits)?,
+            Notation::Fraction(max) => write!(f, "分数（分母不超过 {}）", max)?,
+            Notation::Base(base) => write!(f, "{} 进制", base)?,
+        }
+        match self.grouping {
+            Some(grouping) => write!(f, "，分组符号 '{}'，小数点 '{}'", grouping.separator, grouping.decimal_point),
+            None => write!(f, "，不分组"),
+        }
+    }
+}
+
+// 十进制数字串：值为 d0.d1d2... × 10^exponent，digits 不含前导零
+// 所有格式都在数字串上进行舍入，因此大整数也能精确处理
+#[derive(Debug, Clone, PartialEq)]
+struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl Decimal {
+    fn from_value(value: &Value) -> Decimal {
+        match value {
+            Value::Int(n) => {
+                let text = n.abs().to_string();
+                Decimal::new(n.is_negative(), &text, text.len() as i64 - 1)
+            }
+            Value::Bool(b) => Decimal::from_value(&Value::from(*b as i64)),
+            Value::Poly(_) => unreachable!("多项式在 format 中直接显示"),
+            // {:e} 给出能精确还原该浮点数的最短表示，例如 0.1 + 0.2 是 3.0000000000000004e-1
+            Value::Float(x) => {
+                let text = format!("{:e}", x.abs());
+                let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+                let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+                Decimal::new(x.is_sign_negative() && *x != 0.0, &digits, exponent.parse().unwrap_or(0))
+            }
+        }
+    }
+
+    fn new(negative: bool, digits: &str, exponent: i64) -> Decimal {
+        let mut decimal = Decimal {
+            negative,
+            digits: digits.bytes().map(|b| b - b'0').collect(),
+            exponent,
+        };
+        decimal.normalize();
+        decimal
+    }
+
+    // 去掉首尾多余的零，零统一表示为 [0] × 10^0
+    fn normalize(&mut self) {
+        while self.digits.len() > 1 && self.digits[0] == 0 {
+            self.digits.remove(0);
+            self.exponent -= 1;
+        }
+        while self.digits.len() > 1 && self.digits[self.digits.len() - 1] == 0 {
+            self.digits.pop();
+        }
+        if self.digits.is_empty() || self.digits == [0] {
+            self.digits = vec![0];
+            self.exponent = 0;
+            self.negative = false;
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits == [0]
+    }
+
+    // 只保留前 keep 位数字，按四舍五入处理
+    fn round_digits(&self, keep: i64) -> Decimal {
+        if keep >= self.digits.len() as i64 {
+            return self.clone();
+        }
+        if keep < 0 {
+            return Decimal::new(false, "0", 0);
+        }
+
+        let keep = keep as usize;
+        let mut digits = self.digits[..keep].to_vec();
+        let mut exponent = self.exponent;
+        if self.digits[keep] >= 5 {
+            // 从末位向前进位
+            let mut i = keep;
+            loop {
+                if i == 0 {
+                    digits.insert(0, 1);
+                    exponent += 1;
+                    break;
+                }
+                i -= 1;
+                if digits[i] == 9 {
+                    digits[i] = 0;
+                } else {
+                    digits[i] += 1;
+                    break;
+                }
+            }
+        }
+
+        let mut rounded = Decimal {
+            negative: self.negative,
+            digits,
+            exponent,
+        };
+        rounded.normalize();
+        rounded
+    }
+
+    fn round_to_significant(&self, digits: usize) -> Decimal {
+        self.round_digits(digits as i64)
+    }
+
+    fn round_to_places(&self, places: i64) -> Decimal {
+        self.round_digits(self.exponent + 1 + places)
+    }
+
+    // 写成普通小数，至少保留 min_places 位小数
+    fn to_plain(&self, min_places: usize) -> String {
+        let digit = |i: i64| -> char {
+            if i >= 0 && (i as usize) < self.digits.len() {
+                (b'0' + self.digits[i as usize]) as char
+            } else {
+                '0'
+            }
+        };
+
+        // 第 i 位数字对应 10^(exponent - i)
+        let integer: String = if self.exponent < 0 {
+            String::from("0")
+        } else {
+            (0..=self.exponent).map(digit).collect()
+        };
+        let fraction_len = (self.digits.len() as i64 - 1 - self.exponent).max(min_places as i64);
+        let fraction: String = (1..=fraction_len).map(|k| digit(self.exponent + k)).collect();
+
+        let sign = if self.negative { "-" } else { "" };
+        if fraction.is_empty() {
+            format!("{}{}", sign, integer)
+        } else {
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    }
+}
+
+// 在整数部分插入分组符号，并换成地区使用的小数点
+fn apply_grouping(text: &str, grouping: Option<Grouping>) -> String {
+    let grouping = match grouping {
+        Some(grouping) => grouping,
+        None => return text.to_string(),
+    };
+
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(grouping.separator);
+        }
+        grouped.push(c);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{}{}{}{}", sign, grouped, grouping.decimal_point, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+// 连分数求分母不超过 max_denominator 的最佳有理逼近，|x| 不能超过 i64::MAX
+pub fn best_fraction(x: f64, max_denominator: u64) -> (i64, u64) {
+    let negative = x < 0.0;
+    let mut remainder = x.abs();
+
+    // 两个相邻渐近分数 p0/q0 和 p1/q1
+    let (mut p0, mut q0, mut p1, mut q1): (u64, u64, u64, u64) = (0, 1, 1, 0);
+    loop {
+        let a = remainder.floor();
+        if a > u64::MAX as f64 {
+            break;
+        }
+        let a = a as u64;
+        let q2 = match a.checked_mul(q1).and_then(|v| v.checked_add(q0)) {
+            Some(q2) if q2 <= max_denominator => q2,
+            _ => {
+                // 超过分母上限时，再比较一下半渐近分数是否更接近
+                // 第一轮的 q2 总是 1，所以走到这里时 q1 至少为 1
+                let k = (max_denominator - q0) / q1;
+                let (pk, qk) = (p0 + k * p1, q0 + k * q1);
+                let error = |p: u64, q: u64| (p as f64 / q as f64 - x.abs()).abs();
+                if error(pk, qk) < error(p1, q1) {
+                    p1 = pk;
+                    q1 = qk;
+                }
+                break;
+            }
+        };
+        let p2 = a.saturating_mul(p1).saturating_add(p0);
+        p0 = p1;
+        q0 = q1;
+        p1 = p2;
+        q1 = q2;
+
+        let fraction = remainder - a as f64;
+        if fraction < 1e-12 {
+            break;
+        }
+        remainder = 1.0 / fraction;
+    }
+
+    let numerator = p1 as i64;
+    (if negative { -numerator } else { numerator }, q1.max(1))
+}
+
+fn format_fraction(value: &Value, max_denominator: u64) -> String {
+    let x = match value {
+        Value::Int(n) => return n.to_string(),
+        Value::Bool(b) => return b.to_string(),
+        Value::Poly(p) => return p.to_string(),
+        Value::Float(x) => *x,
+    };
+
+    let (numerator, denominator) = best_fraction(x, max_denominator);
+    let text = if denominator == 1 {
+        numerator.to_string()
+    } else {
+        format!("{}/{}", numerator, denominator)
+    };
+
+    // 不能精确表示时用 ≈ 提示
+    if (numerator as f64 / denominator as f64 - x).abs() <= f64::EPSILON * x.abs().max(1.0) {
+        text
+    } else {
+        format!("≈ {}", text)
+    }
+}
+
+fn format_base(value: &Value, base: u32) -> String {
+    let prefix = match base {
+        2 => "0b",
+        8 => "0o",
+        16 => "0x",
+        _ => "",
+    };
+    let suffix = if prefix.is_empty() && base != 10 {
+        format!(" ({}进制)", base)
+    } else {
+        String::new()
+    };
+
+    let (negative, integer, fraction) = match value {
+        Value::Int(n) => (n.is_negative(), n.abs(), 0.0),
+        Value::Bool(b) => (false, BigInt::from(*b as u8), 0.0),
+        Value::Poly(p) => return p.to_string(),
+        Value::Float(x) => {
+            let whole = x.abs().trunc();
+            let integer = num_traits::FromPrimitive::from_f64(whole).unwrap_or_else(BigInt::zero);
+            (*x < 0.0, integer, x.abs() - whole)
+        }
+    };
+
+    let mut text = integer.to_str_radix(base);
+    // 小数部分逐位乘以进制取整，最多保留 BASE_FRACTION_DIGITS 位
+    if fraction > 0.0 {
+        text.push('.');
+        let mut rest = fraction;
+        for _ in 0..BASE_FRACTION_DIGITS {
+            rest *= base as f64;
+            let digit = rest.trunc();
+            text.push(std::char::from_digit(digit.to_u32().unwrap_or(0), base).unwrap_or('0'));
+            rest -= digit;
+            if rest == 0.0 {
+                break;
+            }
+        }
+        if rest != 0.0 {
+            text.push('…');
+        }
+    }
+
+    let sign = if negative { "-" } else { "" };
+    format!("{}{}{}{}", sign, prefix, text, suffix)
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_with(args: &[&str], value: Value) -> String {
+        let mut options = FormatOptions::default();
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let rest = options.apply_args(&args).unwrap();
+        assert!(rest.is_empty());
+        options.format(&value)
+    }
+
+    #[test]
+    fn test_auto_hides_float_noise() {
+        assert_eq!(format_with(&[], Value::from(0.1 + 0.2)), "0.3");
+        assert_eq!(format_with(&[], Value::from(1e21)), "1e21");
+        assert_eq!(format_with(&[], Value::from(1.5e-7)), "1.5e-7");
+        assert_eq!(format_with(&[], Value::from(-2.5)), "-2.5");
+        assert_eq!(format_with(&[], Value::from(0.0)), "0");
+    }
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(format_with(&["--fixed", "2"], Value::from(1.23456)), "1.23");
+        assert_eq!(format_with(&["--fixed", "2"], Value::from(2.995)), "3.00");
+        assert_eq!(format_with(&["--fixed", "3"], Value::from(7)), "7.000");
+        assert_eq!(format_with(&["--fixed", "0"], Value::from(0.4)), "0");
+    }
+
+    #[test]
+    fn test_significant() {
+        assert_eq!(format_with(&["--sig", "3"], Value::from(1234.567)), "1230");
+        assert_eq!(format_with(&["--sig", "2"], Value::from(0.00012345)), "0.00012");
+        assert_eq!(format_with(&["--sig", "3"], Value::from(2.0)), "2.00");
+    }
+
+    #[test]
+    fn test_scientific_and_engineering() {
+        assert_eq!(format_with(&["--sci"], Value::from(12345)), "1.2345e4");
+        assert_eq!(format_with(&["--sci", "2"], Value::from(0.000123456)), "1.23e-4");
+        assert_eq!(format_with(&["--eng"], Value::from(12345)), "12.345e3");
+        assert_eq!(format_with(&["--eng", "3"], Value::from(0.00047)), "470e-6");
+        assert_eq!(format_with(&["--eng", "3"], Value::from(1200)), "1.20e3");
+    }
+
+    #[test]
+    fn test_grouping() {
+        let big = Value::from(1234567);
+        assert_eq!(format_with(&["--group", "comma"], big.clone()), "1,234,567");
+        assert_eq!(format_with(&["--group", "space"], big), "1 234 567");
+        assert_eq!(format_with(&["--group", "comma", "--fixed", "2"], Value::from(-1234.5)), "-1,234.50");
+
+        let options = FormatOptions {
+            notation: Notation::Fixed(2),
+            grouping: Some(Grouping::for_locale("de_DE.UTF-8")),
+        };
+        assert_eq!(options.format(&Value::from(1234567.891)), "1.234.567,89");
+    }
+
+    #[test]
+    fn test_fraction() {
+        assert_eq!(format_with(&["--frac"], Value::from(0.75)), "3/4");
+        assert_eq!(format_with(&["--frac"], Value::from(-1.0 / 3.0)), "-1/3");
+        assert_eq!(format_with(&["--frac", "1000"], Value::from(std::f64::consts::PI)), "≈ 355/113");
+        assert_eq!(best_fraction(0.1 + 0.2, 10_000), (3, 10));
+
+        // 超出 i64 范围时保持正负号和大小，按默认格式输出
+        assert_eq!(format_with(&["--frac"], Value::from(1e19)), "1e19");
+        assert_eq!(format_with(&["--frac"], Value::from(-1e19)), "-1e19");
+        assert_eq!(format_with(&["--frac"], Value::from(1e300)), "1e300");
+        assert_eq!(format_with(&["--frac"], Value::from(9.0e18)), "9000000000000000000");
+    }
+
+    #[test]
+    fn test_base() {
+        assert_eq!(format_with(&["--base", "16"], Value::from(255)), "0xff");
+        assert_eq!(format_with(&["--base", "2"], Value::from(-5)), "-0b101");
+        assert_eq!(format_with(&["--base", "2"], Value::from(2.5)), "0b10.1");
+        assert_eq!(format_with(&["--base", "36"], Value::from(35)), "z (36进制)");
+    }
+
+    #[test]
+    fn test_big_integers_stay_exact() {
+        let n = Value::parse("123456789012345678901234567890").unwrap();
+        assert_eq!(format_with(&["--group", "comma"], n.clone()), "123,456,789,012,345,678,901,234,567,890");
+        assert_eq!(format_with(&["--sci", "3"], n), "1.235e29");
+    }
+
+    #[test]
+    fn test_unknown_args_are_returned() {
+        let mut options = FormatOptions::default();
+        let args = vec![String::from("1/3"), String::from("--fixed"), String::from("2")];
+        assert_eq!(options.apply_args(&args), Ok(vec![String::from("1/3")]));
+        assert!(options.apply_args(&[String::from("--fixed")]).is_err());
+        assert!(options.apply_args(&[String::from("--bogus")]).is_err());
+    }
+}
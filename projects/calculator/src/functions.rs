@@ -0,0 +1,232 @@
+// 内置函数和常量
+
+use std::cmp::Ordering;
+use std::f64::consts;
+
+use num_bigint::BigInt;
+use num_traits::Signed;
+
+use crate::number_theory;
+use crate::polynomial::Polynomial;
+use crate::value::Value;
+
+// 内置常量
+pub fn constant(name: &str) -> Option<Value> {
+    match name {
+        "pi" => Some(Value::Float(consts::PI)),
+        "e" => Some(Value::Float(consts::E)),
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => None,
+    }
+}
+
+// 调用内置函数，函数不存在时返回 None
+pub fn call(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    if let Some(result) = polynomial_function(name, args) {
+        return Some(result);
+    }
+
+    let result = numeric_function(name, args)?;
+    // 其余函数只接受数字
+    if args.iter().any(|arg| matches!(arg, Value::Poly(_))) {
+        return Some(Err(format!("{} 不支持多项式参数", name)));
+    }
+    Some(result)
+}
+
+// 多项式函数：poly([1, -3, 2]) 构造 x^2 - 3x + 2，系数按次数从高到低给出
+fn polynomial_function(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let result = match name {
+        "poly" => Polynomial::from_descending(args).map(Value::Poly),
+        "deriv" => polynomial(name, args, 1).and_then(|p| p.derivative()).map(Value::Poly),
+        "degree" => polynomial(name, args, 1).map(|p| Value::from(p.degree() as i64)),
+        "polyval" => polynomial(name, args, 2).and_then(|p| p.evaluate(&args[1])),
+        _ => return None,
+    };
+    Some(result)
+}
+
+// 第一个参数必须是多项式
+fn polynomial(name: &str, args: &[Value], count: usize) -> Result<Polynomial, String> {
+    expect_args(name, args, count)?;
+    match &args[0] {
+        Value::Poly(p) => Ok(p.clone()),
+        other => Err(format!("{} 的第一个参数必须是多项式，实际是 {}", name, other)),
+    }
+}
+
+fn numeric_function(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let result = match name {
+        "sqrt" => float_function(name, args, f64::sqrt),
+        "abs" => unary(name, args).map(|x| match x {
+            Value::Int(n) => Value::Int(n.abs()),
+            Value::Float(x) => Value::Float(x.abs()),
+            Value::Bool(b) => Value::from(*b as i64),
+            Value::Poly(p) => Value::Poly(p.clone()),
+        }),
+        "sin" => float_function(name, args, f64::sin),
+        "cos" => float_function(name, args, f64::cos),
+        "tan" => float_function(name, args, f64::tan),
+        "asin" => float_function(name, args, f64::asin),
+        "acos" => float_function(name, args, f64::acos),
+        "atan" => float_function(name, args, f64::atan),
+        "exp" => float_function(name, args, f64::exp),
+        "ln" => float_function(name, args, f64::ln),
+        "log10" => float_function(name, args, f64::log10),
+        "floor" => rounding_function(name, args, f64::floor),
+        "ceil" => rounding_function(name, args, f64::ceil),
+        "round" => rounding_function(name, args, f64::round),
+        "sum" => sum(args),
+        "avg" => average(name, args),
+        "count" => Ok(Value::from(args.len() as i64)),
+        "min" => extremum(name, args, Ordering::Less),
+        "max" => extremum(name, args, Ordering::Greater),
+        "gcd" => integers(name, args, 2).map(|n| Value::Int(number_theory::gcd(&n[0], &n[1]))),
+        "lcm" => integers(name, args, 2).map(|n| Value::Int(number_theory::lcm(&n[0], &n[1]))),
+        "modpow" => integers(name, args, 3)
+            .and_then(|n| number_theory::mod_pow(&n[0], &n[1], &n[2]))
+            .map(Value::Int),
+        "modinv" => integers(name, args, 2)
+            .and_then(|n| number_theory::mod_inverse(&n[0], &n[1]))
+            .map(Value::Int),
+        "isprime" => integers(name, args, 1).map(|n| Value::Bool(number_theory::is_prime(&n[0]))),
+        "factorial" => integers(name, args, 1)
+            .and_then(|n| number_theory::to_u64(&n[0], "n"))
+            .map(|n| Value::Int(number_theory::factorial(n))),
+        "ncr" | "npr" => integers(name, args, 2).and_then(|n| {
+            let r = number_theory::to_u64(&n[1], "r")?;
+            let n = number_theory::to_u64(&n[0], "n")?;
+            if name == "ncr" {
+                number_theory::n_choose_r(n, r).map(Value::Int)
+            } else {
+                number_theory::n_permute_r(n, r).map(Value::Int)
+            }
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn expect_args(name: &str, args: &[Value], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(format!("{} 需要 {} 个参数，实际传入 {} 个", name, count, args.len()))
+    }
+}
+
+fn unary<'a>(name: &str, args: &'a [Value]) -> Result<&'a Value, String> {
+    expect_args(name, args, 1)?;
+    Ok(&args[0])
+}
+
+fn float_function(name: &str, args: &[Value], f: fn(f64) -> f64) -> Result<Value, String> {
+    let x = unary(name, args)?.to_f64();
+    Ok(Value::Float(f(x)))
+}
+
+// 取整函数对整数原样返回，对浮点数取整后转换为整数
+fn rounding_function(name: &str, args: &[Value], f: fn(f64) -> f64) -> Result<Value, String> {
+    match unary(name, args)? {
+        Value::Int(n) => Ok(Value::Int(n.clone())),
+        Value::Bool(b) => Ok(Value::from(*b as i64)),
+        Value::Poly(p) => Ok(Value::Poly(p.clone())),
+        Value::Float(x) => {
+            let rounded = Value::Float(f(*x));
+            Ok(rounded.to_integer().map(Value::Int).unwrap_or(rounded))
+        }
+    }
+}
+
+fn sum(args: &[Value]) -> Result<Value, String> {
+    let mut total = Value::from(0);
+    for arg in args {
+        total = match (&total, arg) {
+            (Value::Int(a), Value::Int(b)) => Value::Int(a + b),
+            _ => Value::Float(total.to_f64() + arg.to_f64()),
+        };
+    }
+    Ok(total)
+}
+
+fn average(name: &str, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() {
+        return Err(format!("{} 至少需要 1 个参数", name));
+    }
+    let total = sum(args)?.to_f64();
+    Ok(Value::Float(total / args.len() as f64))
+}
+
+fn extremum(name: &str, args: &[Value], wanted: Ordering) -> Result<Value, String> {
+    let mut best = match args.first() {
+        Some(first) => first,
+        None => return Err(format!("{} 至少需要 1 个参数", name)),
+    };
+    for arg in &args[1..] {
+        if arg.compare(best) == Some(wanted) {
+            best = arg;
+        }
+    }
+    Ok(best.clone())
+}
+
+fn integers(name: &str, args: &[Value], count: usize) -> Result<Vec<BigInt>, String> {
+    expect_args(name, args, count)?;
+    args.iter()
+        .map(|arg| {
+            arg.to_integer()
+                .ok_or_else(|| format!("{} 的参数必须是整数，实际是 {}", name, arg))
+        })
+        .collect()
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_functions() {
+        assert_eq!(call("sqrt", &[Value::from(9)]), Some(Ok(Value::from(3.0))));
+        assert!(call("sqrt", &[]).unwrap().is_err());
+        assert!(call("unknown", &[]).is_none());
+    }
+
+    #[test]
+    fn test_integer_functions() {
+        assert_eq!(call("gcd", &[Value::from(12), Value::from(18)]), Some(Ok(Value::from(6))));
+        assert_eq!(call("ncr", &[Value::from(5), Value::from(2)]), Some(Ok(Value::from(10))));
+        assert!(call("gcd", &[Value::from(1.5), Value::from(3)]).unwrap().is_err());
+        // isprime 和比较运算一样返回布尔值
+        assert_eq!(call("isprime", &[Value::from(7)]), Some(Ok(Value::from(true))));
+        assert_eq!(call("isprime", &[Value::from(8)]), Some(Ok(Value::from(false))));
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let args = [Value::from(1), Value::from(2), Value::from(3)];
+        assert_eq!(call("sum", &args), Some(Ok(Value::from(6))));
+        assert_eq!(call("avg", &args), Some(Ok(Value::from(2.0))));
+        assert_eq!(call("count", &args), Some(Ok(Value::from(3))));
+        assert!(call("avg", &[]).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_polynomial_functions() {
+        let p = call("poly", &[Value::from(1), Value::from(-3), Value::from(2)]).unwrap().unwrap();
+        assert_eq!(call("degree", std::slice::from_ref(&p)), Some(Ok(Value::from(2))));
+        assert_eq!(call("polyval", &[p.clone(), Value::from(3)]), Some(Ok(Value::from(2))));
+        let derivative = call("deriv", std::slice::from_ref(&p)).unwrap().unwrap();
+        assert_eq!(derivative.to_string(), "2x - 3 = 2(x-1.5)");
+        assert!(call("sqrt", &[p]).unwrap().is_err());
+        assert!(call("deriv", &[Value::from(1)]).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_min_max() {
+        let args = [Value::from(3), Value::from(-1), Value::from(2.5)];
+        assert_eq!(call("min", &args), Some(Ok(Value::from(-1))));
+        assert_eq!(call("max", &args), Some(Ok(Value::from(3))));
+    }
+}
@@ -0,0 +1,384 @@
+// 计算历史：每次计算的输入和结果追加写入用户数据目录下的历史文件，
+// 支持按编号重新执行、按文本搜索，以及把一次会话导出为 Markdown 笔记
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 设置这个环境变量可以把历史文件放到其他位置
+pub const HISTORY_PATH_VARIABLE: &str = "CALCULATOR_HISTORY";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    // Unix 时间戳（秒）
+    pub timestamp: u64,
+    // 所属会话的编号，见 new_session
+    pub session: u64,
+    pub input: String,
+    pub result: String,
+}
+
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    path: Option<PathBuf>,
+    session: u64,
+    // 会话开始时的 Unix 时间戳（秒）
+    started: u64,
+}
+
+impl History {
+    // 只保存在内存中的历史，不读写文件
+    pub fn in_memory() -> History {
+        History {
+            entries: Vec::new(),
+            path: None,
+            session: new_session(),
+            started: now(),
+        }
+    }
+
+    // 打开历史文件并开始一个新会话，文件不存在时会在第一次记录时创建
+    pub fn open(path: &Path) -> Result<History, String> {
+        let entries = if path.exists() {
+            let text = fs::read_to_string(path).map_err(|e| format!("无法读取历史文件 {}: {}", path.display(), e))?;
+            text.lines().filter_map(parse_line).collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(History {
+            entries,
+            path: Some(path.to_path_buf()),
+            session: new_session(),
+            started: now(),
+        })
+    }
+
+    // 打开默认位置的历史文件
+    pub fn open_default() -> Result<History, String> {
+        match default_path() {
+            Some(path) => History::open(&path),
+            None => Err(String::from("找不到用户数据目录")),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // 记录一次计算，并立即追加写入历史文件
+    pub fn record(&mut self, input: &str, result: &str) -> Result<usize, String> {
+        let entry = HistoryEntry {
+            timestamp: now(),
+            session: self.session,
+            input: input.to_string(),
+            result: result.to_string(),
+        };
+
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("无法创建目录 {}: {}", parent.display(), e))?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("无法打开历史文件 {}: {}", path.display(), e))?;
+            writeln!(file, "{}", format_line(&entry)).map_err(|e| format!("无法写入历史文件: {}", e))?;
+        }
+
+        self.entries.push(entry);
+        Ok(self.entries.len())
+    }
+
+    // 按编号取出记录，编号从 1 开始
+    pub fn get(&self, number: usize) -> Option<&HistoryEntry> {
+        number.checked_sub(1).and_then(|i| self.entries.get(i))
+    }
+
+    // 最近的 count 条记录，带编号
+    pub fn recent(&self, count: usize) -> Vec<(usize, &HistoryEntry)> {
+        let start = self.entries.len().saturating_sub(count);
+        self.numbered().skip(start).collect()
+    }
+
+    // 输入或结果中包含 text 的记录（不区分大小写），带编号
+    pub fn search(&self, text: &str) -> Vec<(usize, &HistoryEntry)> {
+        let text = text.to_lowercase();
+        self.numbered()
+            .filter(|(_, entry)| entry.input.to_lowercase().contains(&text) || entry.result.to_lowercase().contains(&text))
+            .collect()
+    }
+
+    // 当前会话的所有记录，带编号
+    pub fn session_entries(&self) -> Vec<(usize, &HistoryEntry)> {
+        self.numbered().filter(|(_, entry)| entry.session == self.session).collect()
+    }
+
+    fn numbered(&self) -> impl Iterator<Item = (usize, &HistoryEntry)> {
+        self.entries.iter().enumerate().map(|(i, entry)| (i + 1, entry))
+    }
+
+    // 把当前会话导出为 Markdown 笔记，每条记录包含输入和结果
+    pub fn session_markdown(&self) -> String {
+        let mut output = String::from("# 计算器会话\n\n");
+        output.push_str(&format!("开始时间: {}\n", format_timestamp(self.started)));
+
+        let entries = self.session_entries();
+        if entries.is_empty() {
+            output.push_str("\n（本次会话没有计算记录）\n");
+        }
+        for (number, entry) in entries {
+            output.push_str(&format!("\n## [{}] {}\n\n", number, format_timestamp(entry.timestamp)));
+            let fence = code_fence(&entry.input);
+            output.push_str(&format!("{}text\n{}\n{}\n\n", fence, entry.input, fence));
+            output.push_str(&format!("{}\n", entry.result));
+        }
+        output
+    }
+
+    pub fn export_session(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.session_markdown()).map_err(|e| format!("无法写入 {}: {}", path.display(), e))
+    }
+}
+
+// 历史文件的默认位置：
+// - 环境变量 CALCULATOR_HISTORY
+// - Linux: $XDG_DATA_HOME 或 ~/.local/share
+// - macOS: ~/Library/Application Support
+// - Windows: %APPDATA%
+// 下的 rust_learning/calculator/history.tsv
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(HISTORY_PATH_VARIABLE) {
+        return Some(PathBuf::from(path));
+    }
+
+    let data_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    }?;
+
+    Some(data_dir.join("rust_learning").join("calculator").join("history.tsv"))
+}
+
+// 进程号占用会话编号的低位
+const PID_BITS: u32 = 22;
+
+// 新会话的编号：高位是纳秒时间戳，低位是进程号，同时启动的两个计算器不会得到相同的编号；
+// 同一个进程中连续开始的会话编号递增
+fn new_session() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let pid_mask = (1 << PID_BITS) - 1;
+    let candidate = (nanos & !pid_mask) | (u64::from(process::id()) & pid_mask);
+    let mut session = candidate;
+    let _ = LAST.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+        session = if candidate > last { candidate } else { last + (1 << PID_BITS) };
+        Some(session)
+    });
+    session
+}
+
+// 代码块的围栏比内容中最长的一串反引号更长，内容中的 ``` 不会提前结束代码块
+fn code_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 历史文件每行一条记录：时间戳、会话、输入、结果，用制表符分隔
+fn format_line(entry: &HistoryEntry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        entry.timestamp,
+        entry.session,
+        escape(&entry.input),
+        escape(&entry.result)
+    )
+}
+
+// 格式不对的行直接跳过，不影响其他记录
+fn parse_line(line: &str) -> Option<HistoryEntry> {
+    let mut fields = line.split('\t');
+    let timestamp = fields.next()?.parse().ok()?;
+    let session = fields.next()?.parse().ok()?;
+    let input = unescape(fields.next()?);
+    let result = unescape(fields.next()?);
+    Some(HistoryEntry {
+        timestamp,
+        session,
+        input,
+        result,
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => output.push('\t'),
+            Some('n') => output.push('\n'),
+            Some(other) => output.push(other),
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+// 把 Unix 时间戳格式化为 UTC 时间，例如 2024-03-01 08:30:00 UTC
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // 从 1970-01-01 起的天数换算为公历日期（Howard Hinnant 的 civil_from_days 算法）
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("calculator_history_{}_{}.tsv", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let path = temp_path("reload");
+        let _ = fs::remove_file(&path);
+
+        let mut history = History::open(&path).unwrap();
+        history.record("1 + 2", "结果: 3").unwrap();
+        history.record("带\t制表符\\和换行\n", "错误: 无法识别的字符").unwrap();
+
+        let reloaded = History::open(&path).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get(1).unwrap().input, "1 + 2");
+        assert_eq!(reloaded.get(2).unwrap().input, "带\t制表符\\和换行\n");
+        assert!(reloaded.get(0).is_none());
+        assert!(reloaded.get(3).is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_search_and_recent() {
+        let mut history = History::in_memory();
+        history.record("sqrt(16)", "结果: 4").unwrap();
+        history.record("1 / 3", "结果: 0.333333333333333").unwrap();
+        history.record("SQRT(2)", "结果: 1.4142135623731").unwrap();
+
+        let found: Vec<usize> = history.search("sqrt").iter().map(|(n, _)| *n).collect();
+        assert_eq!(found, vec![1, 3]);
+
+        let recent: Vec<usize> = history.recent(2).iter().map(|(n, _)| *n).collect();
+        assert_eq!(recent, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_session_markdown_only_contains_current_session() {
+        let path = temp_path("session");
+        let _ = fs::remove_file(&path);
+        fs::write(&path, "100\t100\told\t结果: 1\n").unwrap();
+
+        let mut history = History::open(&path).unwrap();
+        history.record("2 * 21", "结果: 42").unwrap();
+
+        let markdown = history.session_markdown();
+        assert!(markdown.contains("## [2]"));
+        assert!(markdown.contains("```text\n2 * 21\n```"));
+        assert!(markdown.contains("结果: 42"));
+        assert!(!markdown.contains("old"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_sessions_started_together_are_distinct() {
+        let sessions: Vec<u64> = (0..100).map(|_| History::in_memory().session()).collect();
+        let mut unique = sessions.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), sessions.len());
+        // 低位是进程号，其他进程同一时刻开始的会话编号不同
+        let pid_mask = (1 << PID_BITS) - 1;
+        assert!(sessions.iter().all(|s| s & pid_mask == u64::from(process::id()) & pid_mask));
+    }
+
+    #[test]
+    fn test_markdown_fence_survives_backticks() {
+        let mut history = History::in_memory();
+        history.record("```\n# 不是标题", "错误: 无法识别的字符").unwrap();
+
+        let markdown = history.session_markdown();
+        assert!(markdown.contains("````text\n```\n# 不是标题\n````\n"), "{}", markdown);
+        assert_eq!(code_fence("1 + 2"), "```");
+        assert_eq!(code_fence("a ````` b"), "``````");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_281_800), "2024-03-01 08:30:00 UTC");
+    }
+}
@@ -0,0 +1,46 @@
+//! 计算器库：命令行程序和其他示例共用的计算逻辑
+//!
+//! 运算符保存在 OperatorRegistry 中，解析器和求值器都从注册表中查找，
+//! 因此下游代码可以注册自己的运算符而不需要修改本库：
+//!
+//! ```
+//! use calculator::{Associativity, Calculator, FnOperator, Value};
+//!
+//! let mut calculator = Calculator::new();
+//! calculator
+//!     .registry_mut()
+//!     .register(FnOperator::binary("//", 20, Associativity::Left, |a, b| {
+//!         Ok(Value::Float((a.to_f64() / b.to_f64()).floor()))
+//!     }))
+//!     .unwrap();
+//! assert_eq!(calculator.evaluate("7 // 2"), Ok(Value::Float(3.0)));
+//! ```
+
+pub mod eval;
+pub mod format;
+pub mod functions;
+pub mod history;
+pub mod number_theory;
+pub mod operator;
+pub mod parser;
+pub mod plot;
+pub mod polynomial;
+pub mod script;
+pub mod sheet;
+pub mod value;
+
+pub use eval::{Calculator, Environment};
+pub use format::{FormatOptions, Grouping, Notation};
+pub use history::{History, HistoryEntry};
+pub use sheet::{CellRef, Sheet};
+pub use operator::{Arity, Associativity, FnOperator, Operator, OperatorRegistry};
+pub use polynomial::Polynomial;
+pub use script::Interpreter;
+pub use value::Value;
+
+// 对两个浮点数应用二元运算符，运算符从默认注册表中查找
+pub fn calculate(first: f64, operator: &str, second: f64) -> Result<f64, String> {
+    OperatorRegistry::default()
+        .apply_binary(operator, Value::Float(first), Value::Float(second))
+        .map(|value| value.to_f64())
+}
@@ -1,45 +1,83 @@
 use std::env;
 use std::process;
 
+use calculator::number_theory::{self, parse_integer};
+use calculator::plot;
+use calculator::eval::EmptyEnvironment;
+use calculator::script::DEFAULT_STEP_LIMIT;
+use calculator::{CellRef, Calculator, FormatOptions, Interpreter, OperatorRegistry, Sheet};
+use num_bigint::BigInt;
+
 // 简单的命令行计算器实现
 fn main() {
     // 收集命令行参数
     let args: Vec<String> = env::args().collect();
     
-    // 检查参数数量
-    if args.len() != 4 {
-        eprintln!("用法: {} <数字> <操作符> <数字>", args[0]);
-        eprintln!("支持的操作符: +, -, *, /, %");
-        process::exit(1);
+    // 数论子命令，例如 `calculator gcd 12 18`
+    if args.len() >= 2 && is_number_theory_command(&args[1]) {
+        match run_number_theory(&args[1], &args[2..]) {
+            Ok(output) => println!("结果: {}", output),
+            Err(e) => {
+                eprintln!("计算错误: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
     }
     
-    // 解析第一个数字
-    let first_number = match args[1].parse::<f64>() {
-        Ok(num) => num,
-        Err(_) => {
-            eprintln!("错误: '{}' 不是有效的数字", args[1]);
+    // 电子表格子命令，例如 `calculator sheet budget.csv B4 "=sum(B1:B3)"`
+    if args.len() >= 2 && args[1] == "sheet" {
+        if let Err(e) = run_sheet(&args[2..]) {
+            eprintln!("表格错误: {}", e);
             process::exit(1);
         }
-    };
+        return;
+    }
     
-    // 获取操作符
-    let operator = &args[2];
+    // 脚本子命令，例如 `calculator run collatz.calc --steps 1000`
+    if args.len() >= 2 && args[1] == "run" {
+        if let Err(e) = run_script(&args[2..]) {
+            eprintln!("脚本错误: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
     
-    // 解析第二个数字
-    let second_number = match args[3].parse::<f64>() {
-        Ok(num) => num,
-        Err(_) => {
-            eprintln!("错误: '{}' 不是有效的数字", args[3]);
+    // 绘图子命令，例如 `calculator plot "sin(x), cos(x)" from -pi to pi --svg trig.svg`
+    if args.len() >= 2 && args[1] == "plot" {
+        match plot::run(&Calculator::new(), &args[2..].join(" "), &EmptyEnvironment) {
+            Ok(chart) => print!("{}", chart),
+            Err(e) => {
+                eprintln!("绘图错误: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    
+    // 提取输出格式选项，例如 `calculator 1 / 3 --fixed 4`
+    let mut options = FormatOptions::default();
+    let rest = match options.apply_args(&args[1..]) {
+        Ok(rest) => rest,
+        Err(e) => {
+            eprintln!("错误: {}", e);
             process::exit(1);
         }
     };
     
-    // 根据操作符计算结果
-    let result = calculate(first_number, operator, second_number);
+    // 检查参数数量
+    if rest.is_empty() {
+        print_usage(&args[0]);
+        process::exit(1);
+    }
+    
+    // 其余参数拼成一个表达式，既支持 `5 + 3` 也支持 `"1 + 2 * 3"`
+    let expression = rest.join(" ");
+    let calculator = Calculator::new();
     
     // 根据运算结果打印信息
-    match result {
-        Ok(value) => println!("结果: {}", value),
+    match calculator.evaluate(&expression) {
+        Ok(value) => println!("结果: {}", options.format(&value)),
         Err(e) => {
             eprintln!("计算错误: {}", e);
             process::exit(1);
@@ -47,34 +85,156 @@ fn main() {
     }
 }
 
-// 计算函数，返回 Result 类型的结果
-fn calculate(first: f64, operator: &str, second: f64) -> Result<f64, String> {
-    match operator {
-        "+" => Ok(first + second),
-        "-" => Ok(first - second),
-        "*" => Ok(first * second),
-        "/" => {
-            if second == 0.0 {
-                Err(String::from("除数不能为零"))
+// 支持的数论子命令及其参数说明
+const NUMBER_THEORY_COMMANDS: [(&str, &str); 9] = [
+    ("gcd", "<a> <b>"),
+    ("lcm", "<a> <b>"),
+    ("modpow", "<底数> <指数> <模数>"),
+    ("modinv", "<a> <模数>"),
+    ("isprime", "<n>"),
+    ("factor", "<n>"),
+    ("ncr", "<n> <r>"),
+    ("npr", "<n> <r>"),
+    ("factorial", "<n>"),
+];
+
+fn is_number_theory_command(name: &str) -> bool {
+    NUMBER_THEORY_COMMANDS.iter().any(|(command, _)| *command == name)
+}
+
+fn print_usage(program: &str) {
+    eprintln!("用法: {} <表达式>", program);
+    eprintln!("例如: {} 5 + 3 或 {} \"(1 + 2) * 3\"", program, program);
+    eprintln!("支持的操作符: {}", OperatorRegistry::default().symbols().join(", "));
+    eprintln!("输出格式: --fixed N, --sig N, --sci [N], --eng [N], --frac [最大分母], --base N, --group comma|space|locale|none");
+    eprintln!("电子表格: {} sheet <文件.csv> [<单元格> <内容>]...", program);
+    eprintln!("脚本: {} run <脚本文件> [--steps N] [输出格式]", program);
+    eprintln!("绘图: {} plot <函数>[, <函数>...] from <起点> to <终点> [--svg <文件>] [--width N] [--height N]", program);
+    eprintln!("数论命令:");
+    for (command, params) in NUMBER_THEORY_COMMANDS.iter() {
+        eprintln!("  {} {} {}", program, command, params);
+    }
+}
+
+// 执行数论子命令，返回完整打印的结果
+fn run_number_theory(command: &str, params: &[String]) -> Result<String, String> {
+    let expected = match command {
+        "modpow" => 3,
+        "gcd" | "lcm" | "modinv" | "ncr" | "npr" => 2,
+        _ => 1,
+    };
+    if params.len() != expected {
+        return Err(format!("{} 需要 {} 个参数", command, expected));
+    }
+    
+    let numbers = params
+        .iter()
+        .map(|p| parse_integer(p))
+        .collect::<Result<Vec<BigInt>, String>>()?;
+    
+    let output = match command {
+        "gcd" => number_theory::gcd(&numbers[0], &numbers[1]).to_string(),
+        "lcm" => number_theory::lcm(&numbers[0], &numbers[1]).to_string(),
+        "modpow" => number_theory::mod_pow(&numbers[0], &numbers[1], &numbers[2])?.to_string(),
+        "modinv" => number_theory::mod_inverse(&numbers[0], &numbers[1])?.to_string(),
+        "isprime" => {
+            if number_theory::is_prime(&numbers[0]) {
+                format!("{} 是质数", numbers[0])
             } else {
-                Ok(first / second)
+                format!("{} 不是质数", numbers[0])
             }
         },
-        "%" => {
-            if second == 0.0 {
-                Err(String::from("模数不能为零"))
+        "factor" => {
+            let factors = number_theory::factorize(&numbers[0])?;
+            format!("{} = {}", numbers[0], number_theory::format_factors(&factors))
+        },
+        "ncr" | "npr" => {
+            let n = number_theory::to_u64(&numbers[0], "n")?;
+            let r = number_theory::to_u64(&numbers[1], "r")?;
+            if command == "ncr" {
+                number_theory::n_choose_r(n, r)?.to_string()
             } else {
-                Ok(first % second)
+                number_theory::n_permute_r(n, r)?.to_string()
             }
         },
-        _ => Err(format!("不支持的操作符: {}", operator))
+        "factorial" => number_theory::factorial(number_theory::to_u64(&numbers[0], "n")?).to_string(),
+        _ => return Err(format!("不支持的命令: {}", command)),
+    };
+    
+    Ok(output)
+}
+
+// 加载 CSV 表格，依次应用修改，有修改时保存回文件，最后打印整张表
+// 文件不存在时从空表开始
+fn run_sheet(params: &[String]) -> Result<(), String> {
+    let path = match params.first() {
+        Some(path) => path,
+        None => return Err(String::from("缺少 CSV 文件路径")),
+    };
+    let edits = &params[1..];
+    if !edits.len().is_multiple_of(2) {
+        return Err(String::from("修改需要成对给出: <单元格> <内容>"));
+    }
+    
+    let mut sheet = if std::path::Path::new(path).exists() {
+        Sheet::load(path)?
+    } else {
+        Sheet::new()
+    };
+    
+    for edit in edits.chunks(2) {
+        let cell = CellRef::parse(&edit[0])?;
+        let recalculated = sheet.set(cell, &edit[1])?;
+        let names: Vec<String> = recalculated.iter().map(|c| c.to_string()).collect();
+        println!("{} 已更新，重新计算: {}", cell, names.join(", "));
+    }
+    if !edits.is_empty() {
+        sheet.save(path)?;
+    }
+    
+    print!("{}", sheet.render());
+    for (cell, error) in sheet.errors() {
+        println!("{}: {}", cell, error);
+    }
+    Ok(())
+}
+
+// 执行脚本文件，逐行打印每个表达式语句的结果
+// --steps N 修改步数上限，其余选项是输出格式
+fn run_script(params: &[String]) -> Result<(), String> {
+    let path = match params.first() {
+        Some(path) => path,
+        None => return Err(String::from("缺少脚本文件路径")),
+    };
+    
+    let mut flags = params[1..].to_vec();
+    let mut step_limit = DEFAULT_STEP_LIMIT;
+    if let Some(i) = flags.iter().position(|flag| flag == "--steps") {
+        let n = flags.get(i + 1).ok_or("--steps 需要一个数字")?;
+        step_limit = n.parse().map_err(|_| format!("无效的步数: {}", n))?;
+        flags.drain(i..i + 2);
+    }
+    
+    let mut options = FormatOptions::default();
+    let rest = options.apply_args(&flags)?;
+    if !rest.is_empty() {
+        return Err(format!("无法识别的参数: {}", rest.join(" ")));
     }
+    
+    let source = std::fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+    let calculator = Calculator::new();
+    let mut interpreter = Interpreter::new(&calculator).with_step_limit(step_limit);
+    for value in interpreter.run(&source)? {
+        println!("{}", options.format(&value));
+    }
+    Ok(())
 }
 
 // 测试代码
 #[cfg(test)]
 mod tests {
     use super::*;
+    use calculator::{calculate, Value};
     
     #[test]
     fn test_addition() {
@@ -117,4 +277,24 @@ mod tests {
         let result = calculate(10.0, "^", 2.0);
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_integer_precision() {
+        // 2^53 + 1 在 f64 中会被舍入为 2^53
+        let result = Calculator::new().evaluate("9007199254740992 + 1");
+        assert_eq!(result, Ok(Value::Int(parse_integer("9007199254740993").unwrap())));
+    }
+    
+    #[test]
+    fn test_integer_inexact_division_falls_back() {
+        let result = Calculator::new().evaluate("7 / 2");
+        assert_eq!(result, Ok(Value::Float(3.5)));
+    }
+    
+    #[test]
+    fn test_number_theory_command() {
+        let params = vec![String::from("12"), String::from("18")];
+        assert_eq!(run_number_theory("gcd", &params), Ok(String::from("6")));
+        assert!(run_number_theory("gcd", &params[..1]).is_err());
+    }
 }
@@ -0,0 +1,373 @@
+// 数论工具箱：基于任意精度整数（BigInt）的常用算法
+// 所有函数都不经过 f64，因此结果在任何大小下都是精确的
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+// Miller-Rabin 使用的底数：前 13 个质数对 n < 3.3 * 10^24 是确定性的，
+// 更大的数再追加几个底数，把误判概率压到 4^-20 以下
+const WITNESSES: [u32; 20] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+];
+
+// 分解质因数时先用小质数试除，剩下的部分再交给 Pollard rho
+const TRIAL_DIVISION_LIMIT: u32 = 10_000;
+
+// 把字符串解析为任意精度整数
+pub fn parse_integer(text: &str) -> Result<BigInt, String> {
+    let text = text.trim();
+    let digits = text.strip_prefix('+').unwrap_or(text);
+    digits
+        .parse::<BigInt>()
+        .map_err(|_| format!("'{}' 不是有效的整数", text))
+}
+
+// 最大公约数，结果总是非负数
+pub fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    a.gcd(b)
+}
+
+// 最小公倍数，结果总是非负数
+pub fn lcm(a: &BigInt, b: &BigInt) -> BigInt {
+    a.lcm(b)
+}
+
+// 模幂运算：计算 base^exponent mod modulus，结果落在 [0, |modulus|) 区间
+// 指数为负数时使用模逆元，因此要求 base 与 modulus 互质
+pub fn mod_pow(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> Result<BigInt, String> {
+    if modulus.is_zero() {
+        return Err(String::from("模数不能为零"));
+    }
+
+    let modulus = modulus.abs();
+    if exponent.is_negative() {
+        let inverse = mod_inverse(base, &modulus)?;
+        return Ok(inverse.modpow(&-exponent, &modulus));
+    }
+
+    Ok(base.mod_floor(&modulus).modpow(exponent, &modulus))
+}
+
+// 模逆元：求 x 使得 a * x ≡ 1 (mod modulus)，结果落在 [0, |modulus|) 区间
+pub fn mod_inverse(a: &BigInt, modulus: &BigInt) -> Result<BigInt, String> {
+    if modulus.is_zero() {
+        return Err(String::from("模数不能为零"));
+    }
+
+    let modulus = modulus.abs();
+    let extended = a.mod_floor(&modulus).extended_gcd(&modulus);
+    if !extended.gcd.is_one() {
+        return Err(format!("{} 在模 {} 下没有逆元", a, modulus));
+    }
+
+    Ok(extended.x.mod_floor(&modulus))
+}
+
+// Miller-Rabin 素性测试
+pub fn is_prime(n: &BigInt) -> bool {
+    let two = BigInt::from(2);
+    if *n < two {
+        return false;
+    }
+
+    // 小数字直接用底数表判断，同时排除掉它们的倍数
+    for &p in WITNESSES.iter() {
+        let p = BigInt::from(p);
+        if *n == p {
+            return true;
+        }
+        if (n % &p).is_zero() {
+            return false;
+        }
+    }
+
+    // 把 n - 1 写成 d * 2^s，其中 d 为奇数
+    let n_minus_one: BigInt = n - 1;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+
+    'witness: for &a in WITNESSES.iter() {
+        let mut x = BigInt::from(a).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+// 分解质因数，返回按质因数从小到大排列的 (质因数, 指数) 列表
+// 负数会先提取出因子 -1，0 不能分解，1 没有质因数（返回空列表）
+pub fn factorize(n: &BigInt) -> Result<Vec<(BigInt, u32)>, String> {
+    if n.is_zero() {
+        return Err(String::from("0 不能分解质因数"));
+    }
+
+    let mut factors: Vec<(BigInt, u32)> = Vec::new();
+    if n.is_negative() {
+        factors.push((BigInt::from(-1), 1));
+    }
+
+    let mut remaining = n.abs();
+
+    // 第一步：试除小质数
+    let mut divisor = 2u32;
+    while divisor <= TRIAL_DIVISION_LIMIT {
+        let d = BigInt::from(divisor);
+        if &d * &d > remaining {
+            break;
+        }
+
+        let mut count = 0;
+        while (&remaining % &d).is_zero() {
+            remaining /= &d;
+            count += 1;
+        }
+        if count > 0 {
+            factors.push((d, count));
+        }
+
+        divisor += if divisor == 2 { 1 } else { 2 };
+    }
+
+    // 第二步：对剩余部分递归地使用 Pollard rho 拆分
+    if !remaining.is_one() {
+        let mut large = Vec::new();
+        split_large_factor(&remaining, &mut large);
+        large.sort();
+
+        for p in large {
+            match factors.last_mut() {
+                Some((last, count)) if *last == p => *count += 1,
+                _ => factors.push((p, 1)),
+            }
+        }
+    }
+
+    Ok(factors)
+}
+
+// 把 n 拆成若干质因数（可以重复），追加到 out 中
+fn split_large_factor(n: &BigInt, out: &mut Vec<BigInt>) {
+    if n.is_one() {
+        return;
+    }
+    if is_prime(n) {
+        out.push(n.clone());
+        return;
+    }
+
+    let divisor = pollard_rho(n);
+    split_large_factor(&divisor, out);
+    split_large_factor(&(n / &divisor), out);
+}
+
+// Pollard rho 算法（Floyd 判圈：x 每次走一步，y 每次走两步），返回 n 的一个非平凡因子
+// 调用者需要保证 n 是合数
+fn pollard_rho(n: &BigInt) -> BigInt {
+    if n.is_even() {
+        return BigInt::from(2);
+    }
+
+    let mut c = BigInt::one();
+    loop {
+        let step = |x: &BigInt| (x * x + &c) % n;
+
+        let mut x = BigInt::from(2);
+        let mut y = x.clone();
+        let mut divisor = BigInt::one();
+
+        while divisor.is_one() {
+            x = step(&x);
+            y = step(&step(&y));
+            divisor = (&x - &y).abs().gcd(n);
+        }
+
+        if divisor != *n {
+            return divisor;
+        }
+
+        // 这一轮走进了退化的环，换一个常数重新开始
+        c += 1;
+    }
+}
+
+// 阶乘 n!，使用二分乘积让大数相乘时两边规模相近
+pub fn factorial(n: u64) -> BigInt {
+    if n < 2 {
+        return BigInt::one();
+    }
+    product_range(2, n)
+}
+
+// 组合数 C(n, r) = n! / (r! (n - r)!)
+pub fn n_choose_r(n: u64, r: u64) -> Result<BigInt, String> {
+    if r > n {
+        return Err(format!("r ({}) 不能大于 n ({})", r, n));
+    }
+
+    // C(n, r) = C(n, n - r)，取较小的 r 可以少做很多乘法
+    let r = r.min(n - r);
+    if r == 0 {
+        return Ok(BigInt::one());
+    }
+
+    Ok(product_range(n - r + 1, n) / factorial(r))
+}
+
+// 排列数 P(n, r) = n! / (n - r)!
+pub fn n_permute_r(n: u64, r: u64) -> Result<BigInt, String> {
+    if r > n {
+        return Err(format!("r ({}) 不能大于 n ({})", r, n));
+    }
+    if r == 0 {
+        return Ok(BigInt::one());
+    }
+
+    Ok(product_range(n - r + 1, n))
+}
+
+// 计算 low * (low + 1) * ... * high，要求 low <= high
+fn product_range(low: u64, high: u64) -> BigInt {
+    if high - low < 8 {
+        return (low..=high).map(BigInt::from).product();
+    }
+
+    let mid = low + (high - low) / 2;
+    product_range(low, mid) * product_range(mid + 1, high)
+}
+
+// 把质因数分解结果格式化为 2^3 × 3 × 5 的形式，没有质因数（n = 1）时显示为 1
+pub fn format_factors(factors: &[(BigInt, u32)]) -> String {
+    if factors.is_empty() {
+        return String::from("1");
+    }
+    factors
+        .iter()
+        .map(|(p, k)| {
+            if *k == 1 {
+                p.to_string()
+            } else {
+                format!("{}^{}", p, k)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" × ")
+}
+
+// 把大整数转换为 u64，用于阶乘、组合数这类只接受非负小整数的参数
+pub fn to_u64(n: &BigInt, name: &str) -> Result<u64, String> {
+    if n.is_negative() {
+        return Err(format!("{} 不能为负数", name));
+    }
+    n.to_u64().ok_or_else(|| format!("{} 太大了", name))
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        parse_integer(text).unwrap()
+    }
+
+    #[test]
+    fn test_gcd_and_lcm() {
+        assert_eq!(gcd(&big("12"), &big("18")), big("6"));
+        assert_eq!(gcd(&big("-12"), &big("18")), big("6"));
+        assert_eq!(lcm(&big("4"), &big("6")), big("12"));
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(&big("4"), &big("13"), &big("497")), Ok(big("445")));
+        assert_eq!(mod_pow(&big("-2"), &big("3"), &big("5")), Ok(big("2")));
+        assert!(mod_pow(&big("2"), &big("3"), &big("0")).is_err());
+    }
+
+    #[test]
+    fn test_mod_pow_negative_exponent() {
+        // 3 的逆元模 11 是 4，所以 3^-2 ≡ 16 ≡ 5
+        assert_eq!(mod_pow(&big("3"), &big("-2"), &big("11")), Ok(big("5")));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(&big("3"), &big("11")), Ok(big("4")));
+        assert_eq!(mod_inverse(&big("-3"), &big("11")), Ok(big("7")));
+        assert!(mod_inverse(&big("6"), &big("9")).is_err());
+    }
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<BigInt> = (0..60).map(BigInt::from).filter(is_prime).collect();
+        let expected: Vec<BigInt> = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59]
+            .iter()
+            .map(|&p| BigInt::from(p))
+            .collect();
+        assert_eq!(primes, expected);
+
+        // 2^127 - 1 是梅森素数，561 是 Carmichael 数
+        assert!(is_prime(&big("170141183460469231731687303715884105727")));
+        assert!(!is_prime(&big("561")));
+        assert!(!is_prime(&big("-7")));
+    }
+
+    #[test]
+    fn test_factorize() {
+        let factors = factorize(&big("360")).unwrap();
+        assert_eq!(format_factors(&factors), "2^3 × 3^2 × 5");
+
+        let factors = factorize(&big("-91")).unwrap();
+        assert_eq!(format_factors(&factors), "-1 × 7 × 13");
+
+        assert!(factorize(&big("0")).is_err());
+
+        // 1 没有质因数，显示为 1 = 1
+        assert_eq!(format_factors(&factorize(&big("1")).unwrap()), "1");
+        assert_eq!(format_factors(&factorize(&big("-1")).unwrap()), "-1");
+    }
+
+    #[test]
+    fn test_factorize_large_semiprime() {
+        // 两个超过试除上限的质数相乘，必须由 Pollard rho 拆开
+        let factors = factorize(&big("1000000016000000063")).unwrap();
+        assert_eq!(format_factors(&factors), "1000000007 × 1000000009");
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(factorial(0), big("1"));
+        assert_eq!(factorial(20), big("2432902008176640000"));
+        assert_eq!(
+            factorial(30),
+            big("265252859812191058636308480000000")
+        );
+    }
+
+    #[test]
+    fn test_combinations_and_permutations() {
+        assert_eq!(n_choose_r(5, 2), Ok(big("10")));
+        assert_eq!(n_choose_r(100, 50), Ok(big("100891344545564193334812497256")));
+        assert_eq!(n_permute_r(5, 2), Ok(big("20")));
+        assert!(n_choose_r(2, 5).is_err());
+    }
+
+    #[test]
+    fn test_precision_beyond_f64() {
+        // 2^53 + 1 无法用 f64 精确表示
+        let n = big("9007199254740993");
+        assert_eq!((&n + 1u32).to_string(), "9007199254740994");
+    }
+}
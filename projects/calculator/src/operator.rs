@@ -0,0 +1,420 @@
+// 运算符注册表
+// 解析器和求值器都通过注册表查找运算符，新增运算符只需要注册一次，
+// 下游代码也可以注册自己的运算符（例如 `//` 整除或 `**` 乘方）
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use num_integer::Integer;
+
+use crate::polynomial::Polynomial;
+use crate::value::Value;
+
+// 运算符的参数个数：一元运算符是前缀运算符（例如 -x），二元运算符是中缀运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arity {
+    Unary,
+    Binary,
+}
+
+// 结合性：决定 a - b - c 是 (a - b) - c 还是 a - (b - c)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+// 运算符特性，数字越大的优先级结合得越紧
+pub trait Operator {
+    fn symbol(&self) -> &str;
+    fn arity(&self) -> Arity;
+    fn precedence(&self) -> u8;
+    fn associativity(&self) -> Associativity;
+
+    // 二元运算符的左边是这个布尔值时直接得出结果（就是左边的值），不再计算右边，
+    // 例如 and 是 false，or 是 true
+    fn short_circuit(&self) -> Option<bool> {
+        None
+    }
+
+    // args 的长度与 arity 一致
+    fn eval(&self, args: &[Value]) -> Result<Value, String>;
+}
+
+// 内置运算符使用的优先级，自定义运算符可以参考这些数值
+pub const PRECEDENCE_OR: u8 = 2;
+pub const PRECEDENCE_AND: u8 = 3;
+pub const PRECEDENCE_NOT: u8 = 4;
+pub const PRECEDENCE_COMPARISON: u8 = 6;
+pub const PRECEDENCE_ADDITIVE: u8 = 10;
+pub const PRECEDENCE_MULTIPLICATIVE: u8 = 20;
+pub const PRECEDENCE_PREFIX: u8 = 30;
+
+type EvalFn = Box<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+// 用闭包实现的运算符，省去为每个运算符单独定义结构体
+pub struct FnOperator {
+    symbol: String,
+    arity: Arity,
+    precedence: u8,
+    associativity: Associativity,
+    short_circuit: Option<bool>,
+    eval: EvalFn,
+}
+
+impl FnOperator {
+    // 创建二元运算符
+    pub fn binary<F>(symbol: &str, precedence: u8, associativity: Associativity, eval: F) -> FnOperator
+    where
+        F: Fn(&Value, &Value) -> Result<Value, String> + 'static,
+    {
+        FnOperator {
+            symbol: symbol.to_string(),
+            arity: Arity::Binary,
+            precedence,
+            associativity,
+            short_circuit: None,
+            eval: Box::new(move |args| eval(&args[0], &args[1])),
+        }
+    }
+
+    // 左边是 decisive 时短路求值，右边不再计算
+    pub fn short_circuit(mut self, decisive: bool) -> FnOperator {
+        self.short_circuit = Some(decisive);
+        self
+    }
+
+    // 创建一元前缀运算符
+    pub fn unary<F>(symbol: &str, precedence: u8, eval: F) -> FnOperator
+    where
+        F: Fn(&Value) -> Result<Value, String> + 'static,
+    {
+        FnOperator {
+            symbol: symbol.to_string(),
+            arity: Arity::Unary,
+            precedence,
+            associativity: Associativity::Right,
+            short_circuit: None,
+            eval: Box::new(move |args| eval(&args[0])),
+        }
+    }
+}
+
+impl Operator for FnOperator {
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn precedence(&self) -> u8 {
+        self.precedence
+    }
+
+    fn associativity(&self) -> Associativity {
+        self.associativity
+    }
+
+    fn short_circuit(&self) -> Option<bool> {
+        self.short_circuit
+    }
+
+    fn eval(&self, args: &[Value]) -> Result<Value, String> {
+        (self.eval)(args)
+    }
+}
+
+// 运算符注册表，一元和二元运算符分开存放，因此 `-` 可以同时是两者
+pub struct OperatorRegistry {
+    operators: HashMap<(String, Arity), Box<dyn Operator>>,
+}
+
+impl OperatorRegistry {
+    // 创建空的注册表
+    pub fn new() -> OperatorRegistry {
+        OperatorRegistry {
+            operators: HashMap::new(),
+        }
+    }
+
+    // 注册运算符，同符号同元数的旧运算符会被替换
+    pub fn register<O: Operator + 'static>(&mut self, operator: O) -> Result<(), String> {
+        validate_symbol(operator.symbol())?;
+
+        let key = (operator.symbol().to_string(), operator.arity());
+        self.operators.insert(key, Box::new(operator));
+        Ok(())
+    }
+
+    // 移除运算符，返回是否存在
+    pub fn unregister(&mut self, symbol: &str, arity: Arity) -> bool {
+        self.operators.remove(&(symbol.to_string(), arity)).is_some()
+    }
+
+    pub fn get(&self, symbol: &str, arity: Arity) -> Option<&dyn Operator> {
+        self.operators
+            .get(&(symbol.to_string(), arity))
+            .map(|operator| operator.as_ref())
+    }
+
+    pub fn unary(&self, symbol: &str) -> Option<&dyn Operator> {
+        self.get(symbol, Arity::Unary)
+    }
+
+    pub fn binary(&self, symbol: &str) -> Option<&dyn Operator> {
+        self.get(symbol, Arity::Binary)
+    }
+
+    // 所有已注册的符号，按长度从长到短排列，词法分析时优先匹配最长的符号
+    pub fn symbols(&self) -> Vec<&str> {
+        let mut symbols: Vec<&str> = self.operators.keys().map(|(symbol, _)| symbol.as_str()).collect();
+        symbols.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        symbols.dedup();
+        symbols
+    }
+
+    // 直接对两个值应用二元运算符
+    pub fn apply_binary(&self, symbol: &str, left: Value, right: Value) -> Result<Value, String> {
+        match self.binary(symbol) {
+            Some(operator) => operator.eval(&[left, right]),
+            None => Err(format!("不支持的操作符: {}", symbol)),
+        }
+    }
+}
+
+impl Default for OperatorRegistry {
+    // 包含内置运算符 + - * / %、一元负号、比较运算符和逻辑运算符 and or not
+    fn default() -> OperatorRegistry {
+        let mut registry = OperatorRegistry::new();
+        for operator in builtin_operators() {
+            registry
+                .register(operator)
+                .expect("内置运算符的符号必须有效");
+        }
+        registry
+    }
+}
+
+// 符号要么全部由标点组成，要么是一个单词（例如 `mod`），不能包含空白、括号、逗号和冒号
+fn validate_symbol(symbol: &str) -> Result<(), String> {
+    if symbol.is_empty() {
+        return Err(String::from("运算符符号不能为空"));
+    }
+
+    let is_word = symbol.chars().all(|c| c.is_alphabetic() || c == '_');
+    let is_punctuation = symbol
+        .chars()
+        .all(|c| !c.is_alphanumeric() && !c.is_whitespace() && !"()[],.;:_".contains(c));
+
+    if is_word || is_punctuation {
+        Ok(())
+    } else {
+        Err(format!("无效的运算符符号: '{}'", symbol))
+    }
+}
+
+fn builtin_operators() -> Vec<FnOperator> {
+    use Associativity::Left;
+
+    vec![
+        FnOperator::binary("+", PRECEDENCE_ADDITIVE, Left, arithmetic("+", add)),
+        FnOperator::binary("-", PRECEDENCE_ADDITIVE, Left, arithmetic("-", subtract)),
+        FnOperator::binary("*", PRECEDENCE_MULTIPLICATIVE, Left, arithmetic("*", multiply)),
+        FnOperator::binary("/", PRECEDENCE_MULTIPLICATIVE, Left, arithmetic("/", divide)),
+        FnOperator::binary("%", PRECEDENCE_MULTIPLICATIVE, Left, arithmetic("%", remainder)),
+        FnOperator::unary("-", PRECEDENCE_PREFIX, negate),
+        FnOperator::binary("==", PRECEDENCE_COMPARISON, Left, comparison("==", |o| o == Some(Ordering::Equal))),
+        FnOperator::binary("!=", PRECEDENCE_COMPARISON, Left, comparison("!=", |o| o != Some(Ordering::Equal))),
+        FnOperator::binary("<", PRECEDENCE_COMPARISON, Left, comparison("<", |o| o == Some(Ordering::Less))),
+        FnOperator::binary("<=", PRECEDENCE_COMPARISON, Left, comparison("<=", |o| {
+            matches!(o, Some(Ordering::Less | Ordering::Equal))
+        })),
+        FnOperator::binary(">", PRECEDENCE_COMPARISON, Left, comparison(">", |o| o == Some(Ordering::Greater))),
+        FnOperator::binary(">=", PRECEDENCE_COMPARISON, Left, comparison(">=", |o| {
+            matches!(o, Some(Ordering::Greater | Ordering::Equal))
+        })),
+        FnOperator::binary("and", PRECEDENCE_AND, Left, logical("and", |a, b| a && b)).short_circuit(false),
+        FnOperator::binary("or", PRECEDENCE_OR, Left, logical("or", |a, b| a || b)).short_circuit(true),
+        FnOperator::unary("not", PRECEDENCE_NOT, not),
+    ]
+}
+
+type BinaryFn = fn(&Value, &Value) -> Result<Value, String>;
+
+// 算术运算符不接受布尔值，true + 1 是错误而不是 2；
+// 有一边是多项式时按多项式运算，数字当作常数多项式
+fn arithmetic(symbol: &'static str, f: BinaryFn) -> impl Fn(&Value, &Value) -> Result<Value, String> {
+    move |a, b| {
+        if a.is_bool() || b.is_bool() {
+            return Err(format!("布尔值不能参与 '{}' 运算", symbol));
+        }
+        match (a, b) {
+            (Value::Poly(_), _) | (_, Value::Poly(_)) => {
+                polynomial_arithmetic(symbol, &to_polynomial(a), &to_polynomial(b)).map(Value::Poly)
+            }
+            _ => f(a, b),
+        }
+    }
+}
+
+fn to_polynomial(value: &Value) -> Polynomial {
+    match value {
+        Value::Poly(p) => p.clone(),
+        _ => Polynomial::constant(value.clone()),
+    }
+}
+
+// 多项式的 / 和 % 与整数一样分别是带余除法的商和余数
+fn polynomial_arithmetic(symbol: &str, a: &Polynomial, b: &Polynomial) -> Result<Polynomial, String> {
+    match symbol {
+        "+" => a.add(b),
+        "-" => a.subtract(b),
+        "*" => a.multiply(b),
+        "/" => a.div_rem(b).map(|(quotient, _)| quotient),
+        "%" => a.div_rem(b).map(|(_, remainder)| remainder),
+        _ => Err(format!("多项式不支持 '{}' 运算", symbol)),
+    }
+}
+
+// 比较运算符：数字之间、布尔值之间可以比较，NaN 与任何值都不相等
+fn comparison(symbol: &'static str, test: fn(Option<Ordering>) -> bool) -> impl Fn(&Value, &Value) -> Result<Value, String> {
+    move |a, b| {
+        if a.is_bool() != b.is_bool() {
+            return Err(format!("'{}' 不能比较布尔值和数字: {} 和 {}", symbol, a, b));
+        }
+        Ok(Value::Bool(test(a.compare(b))))
+    }
+}
+
+fn logical(symbol: &'static str, f: fn(bool, bool) -> bool) -> impl Fn(&Value, &Value) -> Result<Value, String> {
+    move |a, b| match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => Ok(Value::Bool(f(*x, *y))),
+        _ => Err(format!("'{}' 需要两个布尔值，实际是 {} 和 {}", symbol, a, b)),
+    }
+}
+
+fn not(a: &Value) -> Result<Value, String> {
+    match a {
+        Value::Bool(b) => Ok(Value::Bool(!b)),
+        _ => Err(format!("'not' 需要布尔值，实际是 {}", a)),
+    }
+}
+
+pub(crate) fn add(a: &Value, b: &Value) -> Result<Value, String> {
+    Ok(match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Value::Int(x + y),
+        _ => Value::Float(a.to_f64() + b.to_f64()),
+    })
+}
+
+pub(crate) fn subtract(a: &Value, b: &Value) -> Result<Value, String> {
+    Ok(match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Value::Int(x - y),
+        _ => Value::Float(a.to_f64() - b.to_f64()),
+    })
+}
+
+pub(crate) fn multiply(a: &Value, b: &Value) -> Result<Value, String> {
+    Ok(match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Value::Int(x * y),
+        _ => Value::Float(a.to_f64() * b.to_f64()),
+    })
+}
+
+// 整数能整除时结果仍是整数，否则退回浮点数（7 / 2 = 3.5）
+pub(crate) fn divide(a: &Value, b: &Value) -> Result<Value, String> {
+    if b.is_zero() {
+        return Err(String::from("除数不能为零"));
+    }
+
+    Ok(match (a, b) {
+        (Value::Int(x), Value::Int(y)) if x.is_multiple_of(y) => Value::Int(x / y),
+        _ => Value::Float(a.to_f64() / b.to_f64()),
+    })
+}
+
+fn remainder(a: &Value, b: &Value) -> Result<Value, String> {
+    if b.is_zero() {
+        return Err(String::from("模数不能为零"));
+    }
+
+    Ok(match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Value::Int(x % y),
+        _ => Value::Float(a.to_f64() % b.to_f64()),
+    })
+}
+
+fn negate(a: &Value) -> Result<Value, String> {
+    Ok(match a {
+        Value::Int(x) => Value::Int(-x),
+        Value::Float(x) => Value::Float(-x),
+        Value::Bool(_) => return Err(String::from("布尔值不能取负")),
+        Value::Poly(p) => Value::Poly(p.negate()?),
+    })
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_operators() {
+        let registry = OperatorRegistry::default();
+        assert_eq!(registry.apply_binary("+", Value::from(2), Value::from(3)), Ok(Value::from(5)));
+        assert_eq!(registry.apply_binary("/", Value::from(7), Value::from(2)), Ok(Value::from(3.5)));
+        assert_eq!(registry.apply_binary("/", Value::from(8), Value::from(2)), Ok(Value::from(4)));
+        assert!(registry.apply_binary("%", Value::from(1), Value::from(0)).is_err());
+        assert!(registry.apply_binary("^", Value::from(1), Value::from(2)).is_err());
+    }
+
+    #[test]
+    fn test_register_custom_operator() {
+        let mut registry = OperatorRegistry::default();
+        registry
+            .register(FnOperator::binary("//", PRECEDENCE_MULTIPLICATIVE, Associativity::Left, |a, b| {
+                Ok(Value::Float((a.to_f64() / b.to_f64()).floor()))
+            }))
+            .unwrap();
+
+        assert_eq!(registry.apply_binary("//", Value::from(7), Value::from(2)), Ok(Value::from(3.0)));
+        // 较长的符号排在前面，词法分析时 `//` 不会被拆成两个 `/`
+        let symbols = registry.symbols();
+        let position = |symbol| symbols.iter().position(|s| *s == symbol).unwrap();
+        assert!(position("//") < position("/"));
+    }
+
+    #[test]
+    fn test_unary_and_binary_share_symbol() {
+        let registry = OperatorRegistry::default();
+        assert!(registry.unary("-").is_some());
+        assert!(registry.binary("-").is_some());
+        assert!(registry.unary("+").is_none());
+    }
+
+    #[test]
+    fn test_comparison_and_logical_operators() {
+        let registry = OperatorRegistry::default();
+        assert_eq!(registry.apply_binary("<", Value::from(2), Value::from(3.5)), Ok(Value::from(true)));
+        assert_eq!(registry.apply_binary("==", Value::from(4), Value::from(4.0)), Ok(Value::from(true)));
+        assert_eq!(registry.apply_binary("!=", Value::from(f64::NAN), Value::from(f64::NAN)), Ok(Value::from(true)));
+        assert_eq!(registry.apply_binary(">=", Value::from(f64::NAN), Value::from(1)), Ok(Value::from(false)));
+        assert_eq!(registry.apply_binary("and", Value::from(true), Value::from(false)), Ok(Value::from(false)));
+        assert_eq!(registry.apply_binary("or", Value::from(true), Value::from(false)), Ok(Value::from(true)));
+        assert!(registry.apply_binary("==", Value::from(true), Value::from(1)).is_err());
+        assert!(registry.apply_binary("and", Value::from(1), Value::from(true)).is_err());
+        assert!(registry.apply_binary("+", Value::from(true), Value::from(1)).is_err());
+        assert_eq!(registry.unary("not").unwrap().eval(&[Value::from(false)]), Ok(Value::from(true)));
+    }
+
+    #[test]
+    fn test_invalid_symbol() {
+        let mut registry = OperatorRegistry::new();
+        let result = registry.register(FnOperator::unary("(", PRECEDENCE_PREFIX, |a| Ok(a.clone())));
+        assert!(result.is_err());
+        let result = registry.register(FnOperator::unary("x1", PRECEDENCE_PREFIX, |a| Ok(a.clone())));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,501 @@
+// 表达式解析器：词法分析 + 优先级爬升
+// 运算符的符号、优先级和结合性全部来自 OperatorRegistry
+
+use crate::operator::{Associativity, OperatorRegistry};
+use crate::value::Value;
+
+// 语法树
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(Value),
+    Variable(String),
+    Unary {
+        op: String,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: String,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    // 区域引用，例如 sum(C1:C9) 中的 C1:C9，只能作为函数参数
+    Range {
+        from: String,
+        to: String,
+    },
+    // 列表，例如 poly([1, -3, 2]) 中的 [1, -3, 2]，只能作为函数参数
+    List(Vec<Expr>),
+    // 条件表达式 if c then a else b，只对选中的分支求值
+    If {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+}
+
+// 关键字不能用作变量名或函数名
+pub const KEYWORDS: &[&str] = &["if", "then", "else", "while", "for", "in", "do", "end"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    Ident(String),
+    Operator(String),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Colon,
+}
+
+// 把输入解析为语法树
+pub fn parse(input: &str, registry: &OperatorRegistry) -> Result<Expr, String> {
+    let tokens = tokenize(input, registry)?;
+    if tokens.is_empty() {
+        return Err(String::from("表达式不能为空"));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        registry,
+        depth: 0,
+    };
+    let expr = parser.expression(0)?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("多余的内容: {}", describe(token))),
+    }
+}
+
+// 词法分析：运算符按最长匹配识别，所以注册了 `//` 后它不会被拆成两个 `/`
+fn tokenize(input: &str, registry: &OperatorRegistry) -> Result<Vec<Token>, String> {
+    let symbols = registry.symbols();
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // 科学计数法，例如 1.5e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            // 单词形式的运算符（例如 `mod`）同样来自注册表
+            if symbols.contains(&word.as_str()) {
+                tokens.push(Token::Operator(word));
+            } else {
+                tokens.push(Token::Ident(word));
+            }
+        } else if c == '(' {
+            tokens.push(Token::LeftParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RightParen);
+            i += 1;
+        } else if c == '[' {
+            tokens.push(Token::LeftBracket);
+            i += 1;
+        } else if c == ']' {
+            tokens.push(Token::RightBracket);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == ':' {
+            tokens.push(Token::Colon);
+            i += 1;
+        } else {
+            let matched = symbols.iter().find(|symbol| {
+                let symbol: Vec<char> = symbol.chars().collect();
+                chars[i..].starts_with(&symbol)
+            });
+            match matched {
+                Some(symbol) => {
+                    tokens.push(Token::Operator(symbol.to_string()));
+                    i += symbol.chars().count();
+                }
+                None => return Err(format!("无法识别的字符: '{}'", c)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(text) | Token::Ident(text) | Token::Operator(text) => format!("'{}'", text),
+        Token::LeftParen => String::from("'('"),
+        Token::RightParen => String::from("')'"),
+        Token::LeftBracket => String::from("'['"),
+        Token::RightBracket => String::from("']'"),
+        Token::Comma => String::from("','"),
+        Token::Colon => String::from("':'"),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    registry: &'a OperatorRegistry,
+    // 当前位置在语法树中的层数：括号、前缀运算符、函数参数和每个二元运算符各算一层
+    depth: usize,
+}
+
+// 语法树最多的层数，更深的表达式在解析和求值时会耗尽栈空间
+const MAX_DEPTH: usize = 256;
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(ref token) if *token == expected => Ok(()),
+            Some(token) => Err(format!("期望 {}，实际是 {}", describe(&expected), describe(&token))),
+            None => Err(format!("缺少 {}", describe(&expected))),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Ident(ref word)) if word == keyword => Ok(()),
+            Some(token) => Err(format!("期望 '{}'，实际是 {}", keyword, describe(&token))),
+            None => Err(format!("缺少 '{}'", keyword)),
+        }
+    }
+
+    // 进入一层子表达式，嵌套过深时报错，而不是耗尽栈空间
+    fn expression(&mut self, min_precedence: u16) -> Result<Expr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(String::from("表达式嵌套过深"));
+        }
+        let depth = self.depth;
+        self.depth += 1;
+        let expr = self.climb(min_precedence);
+        self.depth = depth;
+        expr
+    }
+
+    // 优先级爬升：只消费优先级不低于 min_precedence 的二元运算符。
+    // min_precedence 比运算符的优先级宽一位，左结合的最高优先级 255 加 1 后不会溢出
+    fn climb(&mut self, min_precedence: u16) -> Result<Expr, String> {
+        let mut left = self.prefix()?;
+        // 左结合的运算符连成一串时语法树向左加深，每个运算符也算一层，求值时同样不会耗尽栈空间
+        let depth = self.depth;
+
+        while let Some(Token::Operator(symbol)) = self.peek() {
+            let operator = match self.registry.binary(symbol) {
+                Some(operator) => operator,
+                None => return Err(format!("'{}' 不能用作二元运算符", symbol)),
+            };
+            let precedence = u16::from(operator.precedence());
+            if precedence < min_precedence {
+                break;
+            }
+
+            let next_min = match operator.associativity() {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let op = symbol.clone();
+            self.position += 1;
+            if self.depth >= MAX_DEPTH {
+                return Err(String::from("表达式嵌套过深"));
+            }
+            self.depth += 1;
+
+            let right = self.expression(next_min)?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+
+        self.depth = depth;
+        Ok(left)
+    }
+
+    // 前缀运算符的操作数只吸收优先级更高的二元运算符，因此 -2 * 3 是 (-2) * 3
+    fn prefix(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Operator(symbol)) = self.peek() {
+            let precedence = match self.registry.unary(symbol) {
+                Some(operator) => operator.precedence(),
+                None => return Err(format!("'{}' 不能用作前缀运算符", symbol)),
+            };
+            let op = symbol.clone();
+            self.position += 1;
+
+            let operand = self.expression(u16::from(precedence))?;
+            return Ok(Expr::Unary {
+                op,
+                operand: Box::new(operand),
+            });
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(text)) => Ok(Expr::Number(Value::parse(&text)?)),
+            Some(Token::Ident(name)) if name == "if" => {
+                let condition = self.expression(0)?;
+                self.expect_keyword("then")?;
+                let then = self.expression(0)?;
+                self.expect_keyword("else")?;
+                // else 分支尽量向右延伸，if c then 1 else 2 + 3 的 else 分支是 2 + 3
+                let otherwise = self.expression(0)?;
+                Ok(Expr::If {
+                    condition: Box::new(condition),
+                    then: Box::new(then),
+                    otherwise: Box::new(otherwise),
+                })
+            }
+            Some(Token::Ident(name)) if KEYWORDS.contains(&name.as_str()) => Err(format!("意外的关键字 '{}'", name)),
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::Colon) {
+                    self.position += 1;
+                    return match self.next() {
+                        Some(Token::Ident(to)) => Ok(Expr::Range { from: name, to }),
+                        Some(token) => Err(format!("区域引用的 ':' 后面需要单元格，实际是 {}", describe(&token))),
+                        None => Err(String::from("区域引用不完整")),
+                    };
+                }
+                if self.peek() != Some(&Token::LeftParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                self.position += 1;
+
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::RightParen) {
+                    self.position += 1;
+                } else {
+                    loop {
+                        args.push(self.expression(0)?);
+                        match self.next() {
+                            Some(Token::Comma) => continue,
+                            Some(Token::RightParen) => break,
+                            Some(token) => return Err(format!("函数参数之间需要 ','，实际是 {}", describe(&token))),
+                            None => return Err(String::from("缺少 ')'")),
+                        }
+                    }
+                }
+                Ok(Expr::Call { name, args })
+            }
+            Some(Token::LeftParen) => {
+                let expr = self.expression(0)?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(Token::LeftBracket) => {
+                let mut items = Vec::new();
+                if self.peek() == Some(&Token::RightBracket) {
+                    self.position += 1;
+                    return Ok(Expr::List(items));
+                }
+                loop {
+                    items.push(self.expression(0)?);
+                    match self.next() {
+                        Some(Token::Comma) => continue,
+                        Some(Token::RightBracket) => break,
+                        Some(token) => return Err(format!("列表元素之间需要 ','，实际是 {}", describe(&token))),
+                        None => return Err(String::from("缺少 ']'")),
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            Some(token) => Err(format!("意外的 {}", describe(&token))),
+            None => Err(String::from("表达式不完整")),
+        }
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::{FnOperator, PRECEDENCE_PREFIX};
+
+    fn binary(op: &str, left: Expr, right: Expr) -> Expr {
+        Expr::Binary {
+            op: op.to_string(),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn number(n: i64) -> Expr {
+        Expr::Number(Value::from(n))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("1 + 2 * 3", &registry).unwrap();
+        assert_eq!(expr, binary("+", number(1), binary("*", number(2), number(3))));
+    }
+
+    #[test]
+    fn test_left_associativity() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("8 - 4 - 2", &registry).unwrap();
+        assert_eq!(expr, binary("-", binary("-", number(8), number(4)), number(2)));
+    }
+
+    #[test]
+    fn test_right_associative_custom_operator() {
+        let mut registry = OperatorRegistry::default();
+        registry
+            .register(FnOperator::binary("**", PRECEDENCE_PREFIX + 10, Associativity::Right, |a, _| {
+                Ok(a.clone())
+            }))
+            .unwrap();
+
+        let expr = parse("2 ** 3 ** 2", &registry).unwrap();
+        assert_eq!(expr, binary("**", number(2), binary("**", number(3), number(2))));
+    }
+
+    #[test]
+    fn test_highest_left_associative_precedence() {
+        let mut registry = OperatorRegistry::default();
+        registry
+            .register(FnOperator::binary("@", u8::MAX, Associativity::Left, |a, _| Ok(a.clone())))
+            .unwrap();
+
+        let expr = parse("1 @ 2 @ 3 * 4", &registry).unwrap();
+        assert_eq!(
+            expr,
+            binary("*", binary("@", binary("@", number(1), number(2)), number(3)), number(4))
+        );
+    }
+
+    #[test]
+    fn test_calls_and_variables() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("max(x, -1)", &registry).unwrap();
+        let expected = Expr::Call {
+            name: String::from("max"),
+            args: vec![
+                Expr::Variable(String::from("x")),
+                Expr::Unary {
+                    op: String::from("-"),
+                    operand: Box::new(number(1)),
+                },
+            ],
+        };
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_range() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("sum(C1:C9)", &registry).unwrap();
+        let expected = Expr::Call {
+            name: String::from("sum"),
+            args: vec![Expr::Range {
+                from: String::from("C1"),
+                to: String::from("C9"),
+            }],
+        };
+        assert_eq!(expr, expected);
+        assert!(parse("C1:", &registry).is_err());
+    }
+
+    #[test]
+    fn test_list() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("poly([1, -3])", &registry).unwrap();
+        let expected = Expr::Call {
+            name: String::from("poly"),
+            args: vec![Expr::List(vec![
+                number(1),
+                Expr::Unary {
+                    op: String::from("-"),
+                    operand: Box::new(number(3)),
+                },
+            ])],
+        };
+        assert_eq!(expr, expected);
+        assert_eq!(parse("[]", &registry).unwrap(), Expr::List(Vec::new()));
+        assert!(parse("[1, 2", &registry).is_err());
+        assert!(parse("[1 2]", &registry).is_err());
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let registry = OperatorRegistry::default();
+        let expr = parse("if x < 0 then -x else x + 1", &registry).unwrap();
+        let x = || Expr::Variable(String::from("x"));
+        let expected = Expr::If {
+            condition: Box::new(binary("<", x(), number(0))),
+            then: Box::new(Expr::Unary {
+                op: String::from("-"),
+                operand: Box::new(x()),
+            }),
+            otherwise: Box::new(binary("+", x(), number(1))),
+        };
+        assert_eq!(expr, expected);
+        assert!(parse("if x then 1", &registry).is_err());
+        assert!(parse("if x 1 else 2", &registry).is_err());
+        assert!(parse("then + 1", &registry).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let registry = OperatorRegistry::default();
+        assert!(parse("", &registry).is_err());
+        assert!(parse("1 +", &registry).is_err());
+        assert!(parse("(1 + 2", &registry).is_err());
+        assert!(parse("1 2", &registry).is_err());
+        assert!(parse("2 ^ 3", &registry).is_err());
+
+        // 嵌套过深时报错，而不是耗尽栈空间
+        let deep = format!("{}1{}", "(".repeat(50_000), ")".repeat(50_000));
+        assert_eq!(parse(&deep, &registry).unwrap_err(), "表达式嵌套过深");
+        assert_eq!(parse(&"- ".repeat(30_000), &registry).unwrap_err(), "表达式嵌套过深");
+        let chain = format!("1{}", " + 1".repeat(30_000));
+        assert_eq!(parse(&chain, &registry).unwrap_err(), "表达式嵌套过深");
+        let nested = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        assert_eq!(parse(&nested, &registry).unwrap(), number(1));
+    }
+}
@@ -0,0 +1,500 @@
+// 函数绘图：plot sin(x), cos(x) from -pi to pi
+// 每个点都通过计算器的解析器和求值器计算，因此绘图结果与直接求值一致；
+// 图表可以画成终端中的字符图，也可以导出为 SVG
+
+use std::fs;
+
+use crate::eval::{self, Calculator, Environment};
+use crate::format::{FormatOptions, Notation};
+use crate::value::Value;
+
+// 每条曲线的采样点数
+pub const DEFAULT_SAMPLES: usize = 400;
+// 字符图默认的宽度和高度（绘图区的字符数）
+pub const DEFAULT_WIDTH: usize = 60;
+pub const DEFAULT_HEIGHT: usize = 20;
+
+// SVG 图片的大小和边距（像素）
+const SVG_WIDTH: f64 = 640.0;
+const SVG_HEIGHT: f64 = 400.0;
+const SVG_MARGIN: f64 = 50.0;
+
+// 各条曲线在字符图中的标记和在 SVG 中的颜色
+const MARKERS: [char; 6] = ['*', 'o', 'x', '#', '@', '%'];
+const COLORS: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#8c564b"];
+
+// 绘图命令：函数列表、自变量范围和输出选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotSpec {
+    pub expressions: Vec<String>,
+    pub from: String,
+    pub to: String,
+    pub width: usize,
+    pub height: usize,
+    pub svg: Option<String>,
+}
+
+impl PlotSpec {
+    // 解析 plot 后面的内容，例如 `sin(x), cos(x) from -pi to pi --svg out.svg`
+    // 选项：--svg <文件>、--width N、--height N
+    pub fn parse(text: &str) -> Result<PlotSpec, String> {
+        let mut words = Vec::new();
+        let mut width = DEFAULT_WIDTH;
+        let mut height = DEFAULT_HEIGHT;
+        let mut svg = None;
+
+        let mut iter = text.split_whitespace();
+        while let Some(word) = iter.next() {
+            match word {
+                "--svg" => svg = Some(iter.next().ok_or("--svg 需要文件名")?.to_string()),
+                "--width" => width = parse_size(word, iter.next())?,
+                "--height" => height = parse_size(word, iter.next())?,
+                _ if word.starts_with("--") => return Err(format!("未知的绘图选项: {}", word)),
+                _ => words.push(word),
+            }
+        }
+
+        let text = words.join(" ");
+        let (functions, range) = text
+            .rsplit_once(" from ")
+            .ok_or("绘图命令的格式是 plot <函数>[, <函数>...] from <起点> to <终点>")?;
+        let (from, to) = range.split_once(" to ").ok_or("缺少 to <终点>")?;
+
+        let expressions: Vec<String> = split_top_level(functions)
+            .into_iter()
+            .map(|e| e.trim().to_string())
+            .collect();
+        if expressions.iter().any(|e| e.is_empty()) {
+            return Err(String::from("缺少要绘制的函数"));
+        }
+
+        Ok(PlotSpec {
+            expressions,
+            from: from.trim().to_string(),
+            to: to.trim().to_string(),
+            width,
+            height,
+            svg,
+        })
+    }
+}
+
+fn parse_size(flag: &str, value: Option<&str>) -> Result<usize, String> {
+    let value = value.ok_or_else(|| format!("{} 需要一个数字", flag))?;
+    match value.parse::<usize>() {
+        Ok(n) if n >= 2 => Ok(n),
+        _ => Err(format!("{} 需要不小于 2 的整数，实际是 {}", flag, value)),
+    }
+}
+
+// 按不在括号内的逗号切分，max(x, 0), sin(x) 是两个函数
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+// 一条曲线：求值失败或不是有限数字的点记为 None，绘图时留空
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(f64, Option<f64>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub from: f64,
+    pub to: f64,
+    pub series: Vec<Series>,
+}
+
+// 在外层环境之上加入自变量 x
+struct PlotEnvironment<'a> {
+    x: Value,
+    outer: &'a dyn Environment,
+}
+
+impl Environment for PlotEnvironment<'_> {
+    fn variable(&self, name: &str) -> Option<Result<Value, String>> {
+        if name == "x" {
+            Some(Ok(self.x.clone()))
+        } else {
+            self.outer.variable(name)
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+        self.outer.call(name, args)
+    }
+
+    fn range(&self, from: &str, to: &str) -> Option<Result<Vec<Value>, String>> {
+        self.outer.range(from, to)
+    }
+}
+
+impl Plot {
+    // 在 [from, to] 上等距取 samples 个点计算每个函数
+    pub fn sample(calculator: &Calculator, spec: &PlotSpec, env: &dyn Environment, samples: usize) -> Result<Plot, String> {
+        let from = number(calculator, &spec.from, env, "起点")?;
+        let to = number(calculator, &spec.to, env, "终点")?;
+        if from >= to {
+            return Err(format!("起点 {} 必须小于终点 {}", from, to));
+        }
+
+        let samples = samples.max(2);
+        let mut series = Vec::new();
+        for expression in &spec.expressions {
+            let expr = calculator.parse(expression)?;
+            let points = (0..samples)
+                .map(|i| {
+                    let x = from + (to - from) * i as f64 / (samples - 1) as f64;
+                    let env = PlotEnvironment {
+                        x: Value::Float(x),
+                        outer: env,
+                    };
+                    let y = match eval::evaluate(&expr, calculator.registry(), &env) {
+                        Ok(value @ (Value::Int(_) | Value::Float(_))) => Some(value.to_f64()).filter(|y| y.is_finite()),
+                        _ => None,
+                    };
+                    (x, y)
+                })
+                .collect();
+            series.push(Series {
+                label: expression.clone(),
+                points,
+            });
+        }
+
+        Ok(Plot { from, to, series })
+    }
+
+    // 所有曲线的纵坐标范围，全部相等时上下各留出 1
+    pub fn y_range(&self) -> Option<(f64, f64)> {
+        let values = self.series.iter().flat_map(|s| s.points.iter().filter_map(|(_, y)| *y));
+        let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| (min.min(y), max.max(y)));
+        if min > max {
+            None
+        } else if min == max {
+            Some((min - 1.0, max + 1.0))
+        } else {
+            Some((min, max))
+        }
+    }
+
+    // 字符图：左侧是纵坐标，下方是横坐标，坐标轴经过原点时画出坐标轴
+    pub fn render_text(&self, width: usize, height: usize) -> Result<String, String> {
+        let (y_min, y_max) = self.y_range().ok_or("没有可以绘制的点")?;
+        let column = |x: f64| ((x - self.from) / (self.to - self.from) * (width - 1) as f64).round() as usize;
+        let row = |y: f64| ((y_max - y) / (y_max - y_min) * (height - 1) as f64).round() as usize;
+
+        let mut grid = vec![vec![' '; width]; height];
+        if (y_min..=y_max).contains(&0.0) {
+            grid[row(0.0)].fill('-');
+        }
+        if (self.from..=self.to).contains(&0.0) {
+            let c = column(0.0);
+            for line in grid.iter_mut() {
+                line[c] = if line[c] == '-' { '+' } else { '|' };
+            }
+        }
+        for (index, series) in self.series.iter().enumerate() {
+            let marker = MARKERS[index % MARKERS.len()];
+            for (x, y) in &series.points {
+                if let Some(y) = y {
+                    grid[row(*y)][column(*x)] = marker;
+                }
+            }
+        }
+
+        // 纵坐标标注最大值、最小值，以及横轴（没有横轴时标注中间一行）
+        let mut labels = vec![String::new(); height];
+        if (y_min..=y_max).contains(&0.0) {
+            labels[row(0.0)] = String::from("0");
+        } else if height >= 5 {
+            let middle = (height - 1) / 2;
+            labels[middle] = format_label(y_max - (y_max - y_min) * middle as f64 / (height - 1) as f64);
+        }
+        labels[0] = format_label(y_max);
+        labels[height - 1] = format_label(y_min);
+        let gutter = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut output = String::new();
+        for (label, line) in labels.iter().zip(&grid) {
+            let line: String = line.iter().collect();
+            output.push_str(&format!("{:>gutter$} |{}\n", label, line.trim_end()));
+        }
+        output.push_str(&format!("{:>gutter$} +{}\n", "", "-".repeat(width)));
+
+        // 横坐标标注起点和终点
+        let left = format_label(self.from);
+        let right = format_label(self.to);
+        let padding = width.saturating_sub(left.chars().count() + right.chars().count()).max(1);
+        output.push_str(&format!("{:>gutter$}  {}{}{}\n", "", left, " ".repeat(padding), right));
+
+        for (index, series) in self.series.iter().enumerate() {
+            output.push_str(&format!("{:>gutter$}  {} {}\n", "", MARKERS[index % MARKERS.len()], series.label));
+        }
+        Ok(output)
+    }
+
+    // SVG 图：每条曲线是一组折线，在没有值的点处断开
+    pub fn render_svg(&self) -> Result<String, String> {
+        let (y_min, y_max) = self.y_range().ok_or("没有可以绘制的点")?;
+        let plot_width = SVG_WIDTH - 2.0 * SVG_MARGIN;
+        let plot_height = SVG_HEIGHT - 2.0 * SVG_MARGIN;
+        let px = |x: f64| SVG_MARGIN + (x - self.from) / (self.to - self.from) * plot_width;
+        let py = |y: f64| SVG_MARGIN + (y_max - y) / (y_max - y_min) * plot_height;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+            w = SVG_WIDTH,
+            h = SVG_HEIGHT
+        );
+        svg.push_str(&format!(
+            "  <rect x=\"{m}\" y=\"{m}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"white\" stroke=\"#999\"/>\n",
+            plot_width,
+            plot_height,
+            m = SVG_MARGIN
+        ));
+
+        // 经过原点的坐标轴
+        if (y_min..=y_max).contains(&0.0) {
+            svg.push_str(&format!(
+                "  <line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"#666\"/>\n",
+                px(self.from),
+                px(self.to),
+                y = py(0.0)
+            ));
+        }
+        if (self.from..=self.to).contains(&0.0) {
+            svg.push_str(&format!(
+                "  <line x1=\"{x:.1}\" y1=\"{:.1}\" x2=\"{x:.1}\" y2=\"{:.1}\" stroke=\"#666\"/>\n",
+                py(y_max),
+                py(y_min),
+                x = px(0.0)
+            ));
+        }
+
+        // 坐标标注
+        let bottom = SVG_HEIGHT - SVG_MARGIN;
+        svg.push_str(&text_element(SVG_MARGIN - 6.0, SVG_MARGIN + 4.0, "end", &format_label(y_max)));
+        svg.push_str(&text_element(SVG_MARGIN - 6.0, bottom + 4.0, "end", &format_label(y_min)));
+        svg.push_str(&text_element(SVG_MARGIN, bottom + 18.0, "middle", &format_label(self.from)));
+        svg.push_str(&text_element(SVG_WIDTH - SVG_MARGIN, bottom + 18.0, "middle", &format_label(self.to)));
+
+        for (index, series) in self.series.iter().enumerate() {
+            let color = COLORS[index % COLORS.len()];
+            for segment in series.points.split(|(_, y)| y.is_none()) {
+                if segment.is_empty() {
+                    continue;
+                }
+                let points: Vec<String> = segment
+                    .iter()
+                    .filter_map(|(x, y)| y.map(|y| format!("{:.1},{:.1}", px(*x), py(y))))
+                    .collect();
+                svg.push_str(&format!(
+                    "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n",
+                    color,
+                    points.join(" ")
+                ));
+            }
+
+            // 图例
+            let y = SVG_MARGIN + 16.0 * index as f64 + 12.0;
+            let x = SVG_WIDTH - SVG_MARGIN - 110.0;
+            svg.push_str(&format!(
+                "  <line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+                x,
+                x + 16.0,
+                color,
+                y = y - 4.0
+            ));
+            svg.push_str(&text_element(x + 22.0, y, "start", &series.label));
+        }
+
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+// 执行绘图命令：返回字符图，指定了 --svg 时同时写入 SVG 文件
+pub fn run(calculator: &Calculator, text: &str, env: &dyn Environment) -> Result<String, String> {
+    let spec = PlotSpec::parse(text)?;
+    let plot = Plot::sample(calculator, &spec, env, DEFAULT_SAMPLES)?;
+
+    let mut output = plot.render_text(spec.width, spec.height)?;
+    if let Some(path) = &spec.svg {
+        fs::write(path, plot.render_svg()?).map_err(|e| format!("无法写入 {}: {}", path, e))?;
+        output.push_str(&format!("已保存 SVG: {}\n", path));
+    }
+    Ok(output)
+}
+
+fn number(calculator: &Calculator, text: &str, env: &dyn Environment, what: &str) -> Result<f64, String> {
+    match calculator.evaluate_with(text, env)? {
+        value @ (Value::Int(_) | Value::Float(_)) if value.to_f64().is_finite() => Ok(value.to_f64()),
+        value => Err(format!("绘图范围的{}必须是有限的数字，实际是 {}", what, value)),
+    }
+}
+
+// 坐标标注保留 4 位有效数字，很大或很小的数用科学计数法，标注不会太长
+fn format_label(x: f64) -> String {
+    let notation = if x != 0.0 && !(1e-4..1e6).contains(&x.abs()) {
+        Notation::Scientific(Some(3))
+    } else {
+        Notation::Significant(4)
+    };
+    let options = FormatOptions { notation, grouping: None };
+    let text = options.format(&Value::Float(x));
+    // 去掉有效数字补齐的末尾 0，例如 1.000 显示为 1，1.500e300 显示为 1.5e300
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text.as_str(), None),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    match exponent {
+        Some(exponent) => format!("{}e{}", mantissa, exponent),
+        None => mantissa.to_string(),
+    }
+}
+
+fn text_element(x: f64, y: f64, anchor: &str, text: &str) -> String {
+    let escaped = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(
+        "  <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\">{}</text>\n",
+        x, y, anchor, escaped
+    )
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::EmptyEnvironment;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = PlotSpec::parse("sin(x), max(x, 0) from -pi to pi --svg out.svg --width 40").unwrap();
+        assert_eq!(spec.expressions, vec!["sin(x)", "max(x, 0)"]);
+        assert_eq!(spec.from, "-pi");
+        assert_eq!(spec.to, "pi");
+        assert_eq!(spec.width, 40);
+        assert_eq!(spec.height, DEFAULT_HEIGHT);
+        assert_eq!(spec.svg, Some(String::from("out.svg")));
+
+        assert!(PlotSpec::parse("sin(x)").is_err());
+        assert!(PlotSpec::parse("sin(x) from 0").is_err());
+        assert!(PlotSpec::parse(", from 0 to 1").is_err());
+        assert!(PlotSpec::parse("x from 0 to 1 --width 1").is_err());
+        assert!(PlotSpec::parse("x from 0 to 1 --color red").is_err());
+    }
+
+    #[test]
+    fn test_sample_uses_calculator() {
+        let calculator = Calculator::new();
+        let spec = PlotSpec::parse("x * x, 1 / x from -1 to 1").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &EmptyEnvironment, 3).unwrap();
+
+        assert_eq!(plot.series[0].points, vec![(-1.0, Some(1.0)), (0.0, Some(0.0)), (1.0, Some(1.0))]);
+        // 1 / 0 求值失败，这个点留空
+        assert_eq!(plot.series[1].points[1], (0.0, None));
+        assert_eq!(plot.y_range(), Some((-1.0, 1.0)));
+
+        let spec = PlotSpec::parse("x from 1 to 0").unwrap();
+        assert!(Plot::sample(&calculator, &spec, &EmptyEnvironment, 10).is_err());
+    }
+
+    #[test]
+    fn test_outer_variables() {
+        let calculator = Calculator::new();
+        let mut variables = std::collections::HashMap::new();
+        variables.insert(String::from("p"), calculator.evaluate("poly([1, 0, -1])").unwrap());
+        variables.insert(String::from("x"), Value::from(100));
+
+        let spec = PlotSpec::parse("p(x) from 0 to 2").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &variables, 3).unwrap();
+        // 自变量 x 覆盖外层同名变量
+        assert_eq!(plot.series[0].points[2], (2.0, Some(3.0)));
+    }
+
+    #[test]
+    fn test_render_text() {
+        let calculator = Calculator::new();
+        let spec = PlotSpec::parse("x, -x from -4 to 4 --width 9 --height 9").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &EmptyEnvironment, 9).unwrap();
+        let text = plot.render_text(spec.width, spec.height).unwrap();
+        let expected = [
+            " 4 |o   |   *",
+            "   | o  |  *",
+            "   |  o | *",
+            "   |   o|*",
+            " 0 |----o----",
+            "   |   *|o",
+            "   |  * | o",
+            "   | *  |  o",
+            "-4 |*   |   o",
+            "   +---------",
+            "    -4      4",
+            "    * x",
+            "    o -x",
+        ];
+        assert_eq!(text.lines().collect::<Vec<&str>>(), expected);
+    }
+
+    #[test]
+    fn test_labels_stay_short() {
+        assert_eq!(format_label(1.0), "1");
+        assert_eq!(format_label(-2.5), "-2.5");
+        assert_eq!(format_label(123456.0), "123500");
+        assert_eq!(format_label(1e300), "1e300");
+        assert_eq!(format_label(-1.5e300), "-1.5e300");
+        assert_eq!(format_label(0.00001234), "1.234e-5");
+        assert_eq!(format_label(0.0), "0");
+
+        let calculator = Calculator::new();
+        let spec = PlotSpec::parse("x*1e300, -x*1e300 from 0 to 1 --width 20 --height 5").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &EmptyEnvironment, 20).unwrap();
+        let text = plot.render_text(spec.width, spec.height).unwrap();
+        assert!(text.lines().all(|line| line.chars().count() < 40), "{}", text);
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let calculator = Calculator::new();
+        let spec = PlotSpec::parse("1 / x, x < 2 from -1 to 1").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &EmptyEnvironment, 5).unwrap();
+        let svg = plot.render_svg().unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // x = 0 处断开成两段折线；布尔值的曲线没有点
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("&lt; 2"));
+    }
+
+    #[test]
+    fn test_nothing_to_plot() {
+        let calculator = Calculator::new();
+        let spec = PlotSpec::parse("sqrt(-1 - x * x) from -1 to 1").unwrap();
+        let plot = Plot::sample(&calculator, &spec, &EmptyEnvironment, 5).unwrap();
+        assert!(plot.render_text(10, 5).is_err());
+        assert!(plot.render_svg().is_err());
+    }
+}
@@ -0,0 +1,556 @@
+// 多项式：加减乘、带余除法、求值、求导和求全部复数根
+// 系数是计算器的数值（整数保持精确），根使用复数浮点数计算：
+// 四次及以下使用求根公式，更高次数使用 Durand-Kerner 迭代
+
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::fmt;
+
+use num_complex::Complex64;
+
+use crate::format::FormatOptions;
+use crate::operator::{add, divide, multiply, subtract};
+use crate::value::Value;
+
+// 数值迭代的最大轮数
+const MAX_ITERATIONS: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    // 按次数从低到高存放，coefficients[i] 是 x^i 的系数；最高次系数不为零
+    coefficients: Vec<Value>,
+}
+
+impl Polynomial {
+    // 按次数从高到低给出系数，poly([1, -3, 2]) 表示 x^2 - 3x + 2
+    pub fn from_descending(coefficients: &[Value]) -> Result<Polynomial, String> {
+        let mut ascending = Vec::with_capacity(coefficients.len());
+        for coefficient in coefficients.iter().rev() {
+            match coefficient {
+                Value::Int(_) | Value::Float(_) => ascending.push(coefficient.clone()),
+                _ => return Err(format!("多项式的系数必须是数字，实际是 {}", coefficient)),
+            }
+        }
+        Ok(Polynomial::from_ascending(ascending))
+    }
+
+    fn from_ascending(mut coefficients: Vec<Value>) -> Polynomial {
+        while coefficients.last().is_some_and(|c| c.is_zero()) {
+            coefficients.pop();
+        }
+        Polynomial { coefficients }
+    }
+
+    pub fn constant(value: Value) -> Polynomial {
+        Polynomial::from_ascending(vec![value])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    // 零多项式的次数记为 0
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    // 按次数从高到低返回系数
+    pub fn coefficients(&self) -> Vec<Value> {
+        self.coefficients.iter().rev().cloned().collect()
+    }
+
+    fn leading(&self) -> Option<&Value> {
+        self.coefficients.last()
+    }
+
+    fn coefficient(&self, power: usize) -> Value {
+        self.coefficients.get(power).cloned().unwrap_or_else(|| Value::from(0))
+    }
+
+    pub fn add(&self, other: &Polynomial) -> Result<Polynomial, String> {
+        self.combine(other, add)
+    }
+
+    pub fn subtract(&self, other: &Polynomial) -> Result<Polynomial, String> {
+        self.combine(other, subtract)
+    }
+
+    fn combine(&self, other: &Polynomial, f: fn(&Value, &Value) -> Result<Value, String>) -> Result<Polynomial, String> {
+        let length = self.coefficients.len().max(other.coefficients.len());
+        let coefficients = (0..length)
+            .map(|i| f(&self.coefficient(i), &other.coefficient(i)))
+            .collect::<Result<Vec<Value>, String>>()?;
+        Ok(Polynomial::from_ascending(coefficients))
+    }
+
+    pub fn multiply(&self, other: &Polynomial) -> Result<Polynomial, String> {
+        if self.is_zero() || other.is_zero() {
+            return Ok(Polynomial::from_ascending(Vec::new()));
+        }
+
+        let mut coefficients = vec![Value::from(0); self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = add(&coefficients[i + j], &multiply(a, b)?)?;
+            }
+        }
+        Ok(Polynomial::from_ascending(coefficients))
+    }
+
+    pub fn negate(&self) -> Result<Polynomial, String> {
+        Polynomial::from_ascending(Vec::new()).subtract(self)
+    }
+
+    // 带余除法：self = 商 × divisor + 余数，余数的次数小于除数
+    pub fn div_rem(&self, divisor: &Polynomial) -> Result<(Polynomial, Polynomial), String> {
+        let leading = match divisor.leading() {
+            Some(leading) => leading,
+            None => return Err(String::from("除数不能为零多项式")),
+        };
+
+        let mut remainder = self.coefficients.clone();
+        let mut quotient = vec![Value::from(0); remainder.len().saturating_sub(divisor.degree())];
+
+        while remainder.len() > divisor.degree() && !remainder.is_empty() {
+            let shift = remainder.len() - 1 - divisor.degree();
+            let factor = divide(remainder.last().unwrap(), leading)?;
+            // 最高次项按构造一定被消去，直接丢弃，避免浮点误差留下很小的系数
+            remainder.pop();
+            for (i, d) in divisor.coefficients.iter().enumerate().take(divisor.degree()) {
+                remainder[shift + i] = subtract(&remainder[shift + i], &multiply(&factor, d)?)?;
+            }
+            quotient[shift] = factor;
+            while remainder.last().is_some_and(|c| c.is_zero()) {
+                remainder.pop();
+            }
+        }
+
+        Ok((Polynomial::from_ascending(quotient), Polynomial::from_ascending(remainder)))
+    }
+
+    // 秦九韶算法求值，整数系数和整数 x 的结果保持精确
+    pub fn evaluate(&self, x: &Value) -> Result<Value, String> {
+        if x.is_bool() {
+            return Err(format!("多项式只能在数字上求值，实际是 {}", x));
+        }
+        let mut result = Value::from(0);
+        for coefficient in self.coefficients.iter().rev() {
+            result = add(&multiply(&result, x)?, coefficient)?;
+        }
+        Ok(result)
+    }
+
+    pub fn derivative(&self) -> Result<Polynomial, String> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(power, c)| multiply(c, &Value::from(power as i64)))
+            .collect::<Result<Vec<Value>, String>>()?;
+        Ok(Polynomial::from_ascending(coefficients))
+    }
+
+    // 全部复数根（重根重复出现），按实部、虚部从小到大排列
+    pub fn roots(&self) -> Vec<Complex64> {
+        let coefficients: Vec<f64> = self.coefficients.iter().map(|c| c.to_f64()).collect();
+
+        // 先提出 x = 0 的根，剩下的多项式常数项不为零
+        let zeros = coefficients.iter().take_while(|c| **c == 0.0).count();
+        let reduced = &coefficients[zeros..];
+
+        let mut roots = vec![Complex64::new(0.0, 0.0); zeros];
+        let found = match reduced.len().saturating_sub(1) {
+            0 => Vec::new(),
+            1 => vec![Complex64::new(-reduced[0] / reduced[1], 0.0)],
+            2 => quadratic_roots(reduced[2], reduced[1], reduced[0]).to_vec(),
+            3 => cubic_roots(reduced),
+            4 => quartic_roots(reduced),
+            _ => durand_kerner(reduced),
+        };
+        roots.extend(found.into_iter().map(|root| clean(polish(reduced, root))));
+
+        roots.sort_by(|a, b| {
+            (a.im != 0.0)
+                .cmp(&(b.im != 0.0))
+                .then(a.re.partial_cmp(&b.re).unwrap_or(Ordering::Equal))
+                .then(a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal))
+        });
+        roots
+    }
+
+    // 因式分解形式，例如 2(x-1)(x+0.5)，重根写成 (x-1)^2
+    pub fn factored(&self) -> String {
+        let leading = match self.leading() {
+            Some(leading) => leading,
+            None => return String::from("0"),
+        };
+
+        let mut factors: Vec<(String, u32)> = Vec::new();
+        for root in self.roots() {
+            let factor = linear_factor(root);
+            match factors.last_mut() {
+                Some((last, count)) if *last == factor => *count += 1,
+                _ => factors.push((factor, 1)),
+            }
+        }
+
+        let mut output = match leading.compare(&Value::from(1)) {
+            Some(Ordering::Equal) => String::new(),
+            _ if leading.compare(&Value::from(-1)) == Some(Ordering::Equal) => String::from("-"),
+            _ => format_coefficient(leading),
+        };
+        for (factor, count) in factors {
+            output.push_str(&factor);
+            if count > 1 {
+                output.push_str(&format!("^{}", count));
+            }
+        }
+        output
+    }
+}
+
+impl fmt::Display for Polynomial {
+    // 展开形式，一次以上的多项式再附上因式分解，例如 x^2 - 3x + 2 = (x-1)(x-2)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expanded = self.expanded();
+        write!(f, "{}", expanded)?;
+
+        // 因式分解与展开形式相同（例如 -x^2）时不再重复
+        let factored = self.factored();
+        let monic_linear = self.degree() == 1 && self.leading() == Some(&Value::from(1));
+        if self.degree() >= 1 && !monic_linear && factored != expanded.replace(' ', "") {
+            write!(f, " = {}", factored)?;
+        }
+        Ok(())
+    }
+}
+
+impl Polynomial {
+    // 展开形式，例如 x^2 - 3x + 2
+    fn expanded(&self) -> String {
+        if self.is_zero() {
+            return String::from("0");
+        }
+
+        let mut output = String::new();
+        let mut first = true;
+        for (power, coefficient) in self.coefficients.iter().enumerate().rev() {
+            if coefficient.is_zero() {
+                continue;
+            }
+            let negative = coefficient.compare(&Value::from(0)) == Some(Ordering::Less);
+            let magnitude = if negative {
+                subtract(&Value::from(0), coefficient).unwrap_or_else(|_| coefficient.clone())
+            } else {
+                coefficient.clone()
+            };
+
+            output.push_str(match (first, negative) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            });
+            first = false;
+
+            let is_one = magnitude.compare(&Value::from(1)) == Some(Ordering::Equal);
+            if power == 0 || !is_one {
+                output.push_str(&format_coefficient(&magnitude));
+            }
+            match power {
+                0 => {}
+                1 => output.push('x'),
+                _ => output.push_str(&format!("x^{}", power)),
+            }
+        }
+        output
+    }
+}
+
+fn format_coefficient(value: &Value) -> String {
+    FormatOptions::default().format(value)
+}
+
+// 单个根对应的一次因式：0 写成 x，正数 r 写成 (x-r)，负数写成 (x+|r|)，复数写成 (x-(a+bi))
+fn linear_factor(root: Complex64) -> String {
+    if root.im != 0.0 {
+        return format!("(x-({}))", format_complex(root));
+    }
+    match root.re.partial_cmp(&0.0) {
+        Some(Ordering::Equal) => String::from("x"),
+        Some(Ordering::Less) => format!("(x+{})", format_real(-root.re)),
+        _ => format!("(x-{})", format_real(root.re)),
+    }
+}
+
+fn format_complex(z: Complex64) -> String {
+    let imaginary = if z.im.abs() == 1.0 {
+        String::from("i")
+    } else {
+        format!("{}i", format_real(z.im.abs()))
+    };
+    let sign = if z.im < 0.0 { "-" } else { "+" };
+    if z.re == 0.0 {
+        format!("{}{}", if z.im < 0.0 { "-" } else { "" }, imaginary)
+    } else {
+        format!("{}{}{}", format_real(z.re), sign, imaginary)
+    }
+}
+
+// 根的显示保留 9 位小数并去掉末尾的 0
+fn format_real(x: f64) -> String {
+    let text = format!("{:.9}", x);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        String::from("0")
+    } else {
+        text.to_string()
+    }
+}
+
+// 接近整数的部分取整，非常小的虚部视为 0，使 (x-1)(x-2) 不显示成 (x-0.9999999999)
+fn clean(z: Complex64) -> Complex64 {
+    let snap = |x: f64, scale: f64| {
+        if (x - x.round()).abs() <= 1e-9 * scale.max(1.0) {
+            x.round()
+        } else {
+            x
+        }
+    };
+    let scale = z.norm();
+    let im = if z.im.abs() <= 1e-9 * scale.max(1.0) { 0.0 } else { snap(z.im, scale) };
+    Complex64::new(snap(z.re, scale), im)
+}
+
+// 复系数多项式求值，coefficients 按次数从低到高排列
+fn evaluate_complex(coefficients: &[f64], z: Complex64) -> Complex64 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Complex64::new(0.0, 0.0), |acc, c| acc * z + c)
+}
+
+fn evaluate_derivative(coefficients: &[f64], z: Complex64) -> Complex64 {
+    coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .rev()
+        .fold(Complex64::new(0.0, 0.0), |acc, (power, c)| acc * z + c * power as f64)
+}
+
+// 用牛顿法修正求根公式的舍入误差，只接受让 |p(z)| 变小的修正
+fn polish(coefficients: &[f64], mut z: Complex64) -> Complex64 {
+    let mut residual = evaluate_complex(coefficients, z).norm();
+    for _ in 0..50 {
+        let slope = evaluate_derivative(coefficients, z);
+        if slope.norm() == 0.0 || residual == 0.0 {
+            break;
+        }
+        let next = z - evaluate_complex(coefficients, z) / slope;
+        let next_residual = evaluate_complex(coefficients, next).norm();
+        if next_residual.partial_cmp(&residual) != Some(Ordering::Less) {
+            break;
+        }
+        z = next;
+        residual = next_residual;
+    }
+    z
+}
+
+// a x^2 + b x + c = 0
+fn quadratic_roots(a: f64, b: f64, c: f64) -> [Complex64; 2] {
+    let discriminant = Complex64::new(b * b - 4.0 * a * c, 0.0).sqrt();
+    let b = Complex64::new(b, 0.0);
+    [(-b - discriminant) / (2.0 * a), (-b + discriminant) / (2.0 * a)]
+}
+
+// 卡尔达诺公式：先化为 t^3 + p t + q = 0，其中 x = t - b / 3
+fn cubic_roots(coefficients: &[f64]) -> Vec<Complex64> {
+    let a = coefficients[3];
+    let (b, c, d) = (coefficients[2] / a, coefficients[1] / a, coefficients[0] / a);
+
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let shift = Complex64::new(-b / 3.0, 0.0);
+
+    let discriminant = Complex64::new(q * q / 4.0 + p * p * p / 27.0, 0.0).sqrt();
+    let mut u = (Complex64::new(-q / 2.0, 0.0) + discriminant).powf(1.0 / 3.0);
+    if u.norm() < 1e-12 {
+        u = (Complex64::new(-q / 2.0, 0.0) - discriminant).powf(1.0 / 3.0);
+    }
+    if u.norm() < 1e-12 {
+        // p = q = 0，三重根
+        return vec![shift; 3];
+    }
+
+    (0..3)
+        .map(|k| {
+            let uk = u * Complex64::from_polar(1.0, 2.0 * PI * k as f64 / 3.0);
+            uk - p / (3.0 * uk) + shift
+        })
+        .collect()
+}
+
+// 费拉里方法：化为 y^4 + p y^2 + q y + r = 0，其中 x = y - b / 4，
+// 再借助三次预解式的一个非零根 m 拆成两个二次方程
+fn quartic_roots(coefficients: &[f64]) -> Vec<Complex64> {
+    let a = coefficients[4];
+    let (b, c, d, e) = (
+        coefficients[3] / a,
+        coefficients[2] / a,
+        coefficients[1] / a,
+        coefficients[0] / a,
+    );
+
+    let p = c - 3.0 * b * b / 8.0;
+    let q = d - b * c / 2.0 + b * b * b / 8.0;
+    let r = e - b * d / 4.0 + b * b * c / 16.0 - 3.0 * b * b * b * b / 256.0;
+    let shift = Complex64::new(-b / 4.0, 0.0);
+
+    if q.abs() < 1e-12 {
+        // 双二次方程：y^2 是 z^2 + p z + r = 0 的根
+        return quadratic_roots(1.0, p, r)
+            .iter()
+            .flat_map(|z| {
+                let y = z.sqrt();
+                [y + shift, -y + shift]
+            })
+            .collect();
+    }
+
+    // 8m^3 + 8p m^2 + (2p^2 - 8r) m - q^2 = 0，q 不为零时它的根都不为零
+    let resolvent = [-q * q, 2.0 * p * p - 8.0 * r, 8.0 * p, 8.0];
+    let m = cubic_roots(&resolvent)
+        .into_iter()
+        .max_by(|x, y| x.norm().partial_cmp(&y.norm()).unwrap_or(Ordering::Equal))
+        .unwrap();
+
+    let sqrt_2m = (2.0 * m).sqrt();
+    let mut roots = Vec::with_capacity(4);
+    for sign in [1.0, -1.0] {
+        let inner = (-(2.0 * p + 2.0 * m + sign * 2.0_f64.sqrt() * q / m.sqrt())).sqrt();
+        for inner_sign in [1.0, -1.0] {
+            roots.push((sign * sqrt_2m + inner_sign * inner) / 2.0 + shift);
+        }
+    }
+    roots
+}
+
+// Durand-Kerner 迭代：同时逼近所有根，初值取复平面上不对称分布的点
+fn durand_kerner(coefficients: &[f64]) -> Vec<Complex64> {
+    let degree = coefficients.len() - 1;
+    let leading = coefficients[degree];
+    let monic: Vec<f64> = coefficients.iter().map(|c| c / leading).collect();
+
+    let seed = Complex64::new(0.4, 0.9);
+    let mut roots: Vec<Complex64> = (0..degree).map(|k| seed.powu(k as u32)).collect();
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut largest_step: f64 = 0.0;
+        for i in 0..degree {
+            let denominator = (0..degree)
+                .filter(|j| *j != i)
+                .fold(Complex64::new(1.0, 0.0), |acc, j| acc * (roots[i] - roots[j]));
+            if denominator.norm() == 0.0 {
+                continue;
+            }
+            let step = evaluate_complex(&monic, roots[i]) / denominator;
+            roots[i] -= step;
+            largest_step = largest_step.max(step.norm());
+        }
+        if largest_step < 1e-14 {
+            break;
+        }
+    }
+    roots
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(coefficients: &[i64]) -> Polynomial {
+        let values: Vec<Value> = coefficients.iter().map(|c| Value::from(*c)).collect();
+        Polynomial::from_descending(&values).unwrap()
+    }
+
+    #[test]
+    fn test_display_with_factorization() {
+        assert_eq!(poly(&[1, -3, 2]).to_string(), "x^2 - 3x + 2 = (x-1)(x-2)");
+        assert_eq!(poly(&[2, 0, -2]).to_string(), "2x^2 - 2 = 2(x+1)(x-1)");
+        assert_eq!(poly(&[1, -2, 1]).to_string(), "x^2 - 2x + 1 = (x-1)^2");
+        assert_eq!(poly(&[-1, 0, 0]).to_string(), "-x^2");
+        assert_eq!(poly(&[1, 5]).to_string(), "x + 5");
+        assert_eq!(poly(&[0, 0, 7]).to_string(), "7");
+        assert_eq!(poly(&[]).to_string(), "0");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = poly(&[1, -1]);
+        let b = poly(&[1, -2]);
+        assert_eq!(a.multiply(&b).unwrap(), poly(&[1, -3, 2]));
+        assert_eq!(a.add(&b).unwrap(), poly(&[2, -3]));
+        assert_eq!(a.subtract(&a).unwrap(), poly(&[]));
+        assert_eq!(a.negate().unwrap(), poly(&[-1, 1]));
+    }
+
+    #[test]
+    fn test_division_with_remainder() {
+        // x^3 - 2x^2 - 4 = (x - 3)(x^2 + x + 3) + 5
+        let (quotient, remainder) = poly(&[1, -2, 0, -4]).div_rem(&poly(&[1, -3])).unwrap();
+        assert_eq!(quotient, poly(&[1, 1, 3]));
+        assert_eq!(remainder, poly(&[5]));
+        assert!(poly(&[1]).div_rem(&poly(&[])).is_err());
+
+        // 首项系数不整除时得到浮点系数
+        let (quotient, remainder) = poly(&[1, 0]).div_rem(&poly(&[2, 1])).unwrap();
+        assert_eq!(quotient.coefficients(), vec![Value::from(0.5)]);
+        assert_eq!(remainder.coefficients(), vec![Value::from(-0.5)]);
+    }
+
+    #[test]
+    fn test_evaluate_and_derivative() {
+        let p = poly(&[1, -3, 2]);
+        assert_eq!(p.evaluate(&Value::from(5)), Ok(Value::from(12)));
+        assert_eq!(p.derivative().unwrap(), poly(&[2, -3]));
+        assert!(p.evaluate(&Value::from(true)).is_err());
+    }
+
+    #[test]
+    fn test_complex_roots() {
+        assert_eq!(poly(&[1, 0, 1]).to_string(), "x^2 + 1 = (x-(-i))(x-(i))");
+        assert_eq!(poly(&[1, -2, 5]).factored(), "(x-(1-2i))(x-(1+2i))");
+    }
+
+    #[test]
+    fn test_cubic_and_quartic_roots() {
+        // (x-1)(x-2)(x-3)
+        assert_eq!(poly(&[1, -6, 11, -6]).factored(), "(x-1)(x-2)(x-3)");
+        // (x+1)^3
+        assert_eq!(poly(&[1, 3, 3, 1]).factored(), "(x+1)^3");
+        // (x-1)(x+2)(x^2+1)
+        assert_eq!(poly(&[1, 1, -1, 1, -2]).factored(), "(x+2)(x-1)(x-(-i))(x-(i))");
+        // (x-1)(x-2)(x-3)(x-4)
+        assert_eq!(poly(&[1, -10, 35, -50, 24]).factored(), "(x-1)(x-2)(x-3)(x-4)");
+        // x^4 - 5x^2 + 4 = (x-1)(x+1)(x-2)(x+2)
+        assert_eq!(poly(&[1, 0, -5, 0, 4]).factored(), "(x+2)(x+1)(x-1)(x-2)");
+    }
+
+    #[test]
+    fn test_numeric_roots_for_higher_degrees() {
+        // (x-1)(x-2)(x-3)(x-4)(x-5)
+        assert_eq!(poly(&[1, -15, 85, -225, 274, -120]).factored(), "(x-1)(x-2)(x-3)(x-4)(x-5)");
+        // x^6 - 1 的根是六次单位根
+        let roots = poly(&[1, 0, 0, 0, 0, 0, -1]).roots();
+        assert_eq!(roots.len(), 6);
+        for root in roots {
+            assert!((root.norm() - 1.0).abs() < 1e-9);
+        }
+        // x^2 提出零根
+        assert_eq!(poly(&[1, -1, 0, 0, 0, 0]).factored(), "x^4(x-1)");
+    }
+}
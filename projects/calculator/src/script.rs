@@ -0,0 +1,410 @@
+// 计算器脚本：在表达式之上加入赋值、while 循环和 for 循环
+//
+// 每行（或用 ';' 分隔的每段）是一条语句，循环体以 do 开始、以 end 结束：
+//
+//     total = 0
+//     for i in 1..=10 do
+//         total = total + i
+//     end
+//     total
+//
+// 单独一行的表达式会作为输出，# 开头的行是注释。
+// 为了防止死循环，执行的步数超过上限时停止并报错。
+
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+
+use crate::eval::{self, Calculator};
+use crate::operator::OperatorRegistry;
+use crate::parser::{self, Expr, KEYWORDS};
+use crate::value::Value;
+
+// 默认的步数上限，每执行一条语句或判断一次循环条件算一步
+pub const DEFAULT_STEP_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    // 语句所在的行号，从 1 开始，用于错误信息
+    pub line: usize,
+    pub kind: StatementKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    // 单独的表达式，结果作为输出
+    Expr(Expr),
+    Assign {
+        name: String,
+        value: Expr,
+    },
+    While {
+        condition: Expr,
+        body: Vec<Statement>,
+    },
+    // for i in a..b 不包含 b，for i in a..=b 包含 b，与 Rust 的区间写法一致
+    For {
+        variable: String,
+        from: Expr,
+        to: Expr,
+        inclusive: bool,
+        body: Vec<Statement>,
+    },
+}
+
+// 脚本按行切分后的片段：循环头、end 或普通语句
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Header(String),
+    End,
+    Plain(String),
+}
+
+// 把脚本解析为语句列表
+pub fn parse(source: &str, registry: &OperatorRegistry) -> Result<Vec<Statement>, String> {
+    let segments = split_segments(source)?;
+    let mut position = 0;
+    let statements = parse_block(&segments, &mut position, registry)?;
+
+    match segments.get(position) {
+        None => Ok(statements),
+        Some((line, _)) => Err(format!("第 {} 行: 多余的 end", line)),
+    }
+}
+
+// 按换行和 ';' 切分，再把 do 和 end 单独切出来，
+// 因此 for i in 1..4 do total = total + i end 也可以写在一行里
+fn split_segments(source: &str) -> Result<Vec<(usize, Segment)>, String> {
+    let mut segments = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        for piece in line.split(';') {
+            let mut current: Vec<&str> = Vec::new();
+            for word in piece.split_whitespace() {
+                let is_header = matches!(current.first(), Some(&"while") | Some(&"for"));
+                if word == "do" && is_header {
+                    segments.push((number, Segment::Header(current.join(" "))));
+                    current.clear();
+                } else if word == "end" {
+                    if !current.is_empty() {
+                        segments.push((number, plain_segment(number, &current)?));
+                        current.clear();
+                    }
+                    segments.push((number, Segment::End));
+                } else {
+                    current.push(word);
+                }
+            }
+            if !current.is_empty() {
+                segments.push((number, plain_segment(number, &current)?));
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+fn plain_segment(line: usize, words: &[&str]) -> Result<Segment, String> {
+    match words[0] {
+        "while" | "for" => Err(format!("第 {} 行: {} 后面缺少 do", line, words[0])),
+        _ => Ok(Segment::Plain(words.join(" "))),
+    }
+}
+
+// 解析语句直到遇到 end 或脚本结束，end 留给调用者处理
+fn parse_block(
+    segments: &[(usize, Segment)],
+    position: &mut usize,
+    registry: &OperatorRegistry,
+) -> Result<Vec<Statement>, String> {
+    let mut statements = Vec::new();
+
+    while let Some((line, segment)) = segments.get(*position) {
+        let line = *line;
+        let kind = match segment {
+            Segment::End => break,
+            Segment::Plain(text) => parse_simple(text, registry),
+            Segment::Header(text) => {
+                *position += 1;
+                let body = parse_block(segments, position, registry)?;
+                if segments.get(*position).is_none() {
+                    return Err(format!("第 {} 行: 循环缺少 end", line));
+                }
+                parse_loop(text, body, registry)
+            }
+        }
+        .map_err(|e| format!("第 {} 行: {}", line, e))?;
+
+        statements.push(Statement { line, kind });
+        *position += 1;
+    }
+
+    Ok(statements)
+}
+
+// 赋值语句或表达式，x == 1 这样的比较不会被当成赋值
+fn parse_simple(text: &str, registry: &OperatorRegistry) -> Result<StatementKind, String> {
+    if let Some((name, value)) = text.split_once('=') {
+        let name = name.trim();
+        if !value.starts_with('=') && is_identifier(name) {
+            check_variable_name(name)?;
+            let value = parser::parse(value, registry)?;
+            return Ok(StatementKind::Assign {
+                name: name.to_string(),
+                value,
+            });
+        }
+    }
+
+    parser::parse(text, registry).map(StatementKind::Expr)
+}
+
+fn parse_loop(header: &str, body: Vec<Statement>, registry: &OperatorRegistry) -> Result<StatementKind, String> {
+    if let Some(condition) = header.strip_prefix("while ") {
+        let condition = parser::parse(condition, registry)?;
+        return Ok(StatementKind::While { condition, body });
+    }
+
+    // for <变量> in <起点>..<终点>
+    let rest = header.strip_prefix("for ").ok_or("for 后面缺少循环变量")?;
+    let (variable, range) = rest.split_once(" in ").ok_or("for 循环的格式是 for i in a..b do")?;
+    let variable = variable.trim();
+    if !is_identifier(variable) {
+        return Err(format!("无效的循环变量: '{}'", variable));
+    }
+    check_variable_name(variable)?;
+
+    let (from, to) = range.split_once("..").ok_or("for 循环需要区间，例如 1..10 或 1..=10")?;
+    let (to, inclusive) = match to.strip_prefix('=') {
+        Some(to) => (to, true),
+        None => (to, false),
+    };
+
+    Ok(StatementKind::For {
+        variable: variable.to_string(),
+        from: parser::parse(from, registry)?,
+        to: parser::parse(to, registry)?,
+        inclusive,
+        body,
+    })
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn check_variable_name(name: &str) -> Result<(), String> {
+    if KEYWORDS.contains(&name) || name == "true" || name == "false" {
+        Err(format!("'{}' 不能用作变量名", name))
+    } else {
+        Ok(())
+    }
+}
+
+// 脚本解释器：变量在多次 run 之间保留，因此可以在交互式环境中逐行执行
+pub struct Interpreter<'a> {
+    calculator: &'a Calculator,
+    variables: HashMap<String, Value>,
+    step_limit: usize,
+    steps: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(calculator: &'a Calculator) -> Interpreter<'a> {
+        Interpreter {
+            calculator,
+            variables: HashMap::new(),
+            step_limit: DEFAULT_STEP_LIMIT,
+            steps: 0,
+        }
+    }
+
+    pub fn with_step_limit(mut self, step_limit: usize) -> Interpreter<'a> {
+        self.step_limit = step_limit;
+        self
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.variables
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: Value) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    // 执行一段脚本，返回每个表达式语句的结果
+    pub fn run(&mut self, source: &str) -> Result<Vec<Value>, String> {
+        let statements = parse(source, self.calculator.registry())?;
+        self.steps = 0;
+
+        let mut outputs = Vec::new();
+        self.execute_block(&statements, &mut outputs)?;
+        Ok(outputs)
+    }
+
+    fn execute_block(&mut self, statements: &[Statement], outputs: &mut Vec<Value>) -> Result<(), String> {
+        for statement in statements {
+            self.execute(statement, outputs)
+                .map_err(|e| if e.starts_with("第 ") { e } else { format!("第 {} 行: {}", statement.line, e) })?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, statement: &Statement, outputs: &mut Vec<Value>) -> Result<(), String> {
+        self.step()?;
+
+        match &statement.kind {
+            StatementKind::Expr(expr) => outputs.push(self.evaluate(expr)?),
+            StatementKind::Assign { name, value } => {
+                let value = self.evaluate(value)?;
+                self.variables.insert(name.clone(), value);
+            }
+            StatementKind::While { condition, body } => {
+                while self.evaluate(condition)?.to_bool()? {
+                    self.execute_block(body, outputs)?;
+                    self.step()?;
+                }
+            }
+            StatementKind::For {
+                variable,
+                from,
+                to,
+                inclusive,
+                body,
+            } => {
+                let from = self.integer(from, "起点")?;
+                let mut to = self.integer(to, "终点")?;
+                if *inclusive {
+                    to += 1;
+                }
+
+                let mut i = from;
+                while i < to {
+                    self.variables.insert(variable.clone(), Value::Int(i.clone()));
+                    self.execute_block(body, outputs)?;
+                    self.step()?;
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&self, expr: &Expr) -> Result<Value, String> {
+        eval::evaluate(expr, self.calculator.registry(), &self.variables)
+    }
+
+    fn integer(&self, expr: &Expr, what: &str) -> Result<BigInt, String> {
+        let value = self.evaluate(expr)?;
+        value
+            .to_integer()
+            .ok_or_else(|| format!("for 循环的{}必须是整数，实际是 {}", what, value))
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if self.steps > self.step_limit {
+            Err(format!("执行超过 {} 步，已停止（可能是死循环）", self.step_limit))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> Result<Vec<Value>, String> {
+        let calculator = Calculator::new();
+        let mut interpreter = Interpreter::new(&calculator);
+        interpreter.run(source)
+    }
+
+    #[test]
+    fn test_assignment_and_output() {
+        assert_eq!(run("x = 6\ny = x * 7\ny"), Ok(vec![Value::from(42)]));
+        assert_eq!(run("x = 1; x == 1; x = x + 1; x"), Ok(vec![Value::from(true), Value::from(2)]));
+    }
+
+    #[test]
+    fn test_for_loop_ranges() {
+        let source = "
+            # 1 到 10 的和
+            total = 0
+            for i in 1..=10 do
+                total = total + i
+            end
+            total
+        ";
+        assert_eq!(run(source), Ok(vec![Value::from(55)]));
+        // 不包含终点，并且可以写在一行里
+        assert_eq!(
+            run("for i in 1..4 do i * i end"),
+            Ok(vec![Value::from(1), Value::from(4), Value::from(9)])
+        );
+        assert_eq!(run("for i in 5..1 do i end"), Ok(vec![]));
+        assert!(run("for i in 1..2.5 do i end").is_err());
+    }
+
+    #[test]
+    fn test_while_loop_and_if() {
+        let source = "
+            n = 27
+            steps = 0
+            while n != 1 do
+                n = if n % 2 == 0 then n / 2 else 3 * n + 1
+                steps = steps + 1
+            end
+            steps
+        ";
+        assert_eq!(run(source), Ok(vec![Value::from(111)]));
+    }
+
+    #[test]
+    fn test_nested_loops() {
+        let source = "
+            count = 0
+            for i in 0..3 do
+                for j in 0..3 do
+                    count = count + 1
+                end
+            end
+            count
+        ";
+        assert_eq!(run(source), Ok(vec![Value::from(9)]));
+    }
+
+    #[test]
+    fn test_step_limit_stops_runaway_loops() {
+        let calculator = Calculator::new();
+        let mut interpreter = Interpreter::new(&calculator).with_step_limit(1000);
+        let error = interpreter.run("x = 0\nwhile true do\n x = x + 1\nend").unwrap_err();
+        assert!(error.contains("1000"), "{}", error);
+        // 每次运行重新计数
+        assert_eq!(interpreter.run("x > 100"), Ok(vec![Value::from(true)]));
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        assert_eq!(run("x = 1\ny = x +").unwrap_err().split(':').next(), Some("第 2 行"));
+        assert!(run("while 1 do\nx = 1\nend").unwrap_err().starts_with("第 1 行"));
+        assert!(run("for i in 1..3 do\ni").is_err());
+        assert!(run("while true\nend").is_err());
+        assert!(run("end").is_err());
+        assert!(run("if = 3").is_err());
+        assert!(run("true = 3").is_err());
+    }
+}
@@ -0,0 +1,651 @@
+// 电子表格引擎：单元格 A1..Z999 保存数字、文本或公式（例如 `=A1*B2+sum(C1:C9)`）
+// 公式使用计算器的解析器和求值器，修改单元格时只重算受影响的单元格
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+
+use crate::eval::{self, Calculator, Environment};
+use crate::parser::Expr;
+use crate::value::Value;
+
+pub const MAX_COLUMNS: u8 = 26;
+pub const MAX_ROWS: u16 = 999;
+
+// 单元格坐标，column 从 0 开始（A = 0），row 从 1 开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellRef {
+    pub row: u16,
+    pub column: u8,
+}
+
+impl CellRef {
+    pub fn new(column: u8, row: u16) -> Result<CellRef, String> {
+        if column >= MAX_COLUMNS || row == 0 || row > MAX_ROWS {
+            return Err(format!("单元格超出范围 A1..Z{}", MAX_ROWS));
+        }
+        Ok(CellRef { row, column })
+    }
+
+    // 解析 A1、z999 这样的单元格名称，不区分大小写
+    pub fn parse(text: &str) -> Result<CellRef, String> {
+        let mut chars = text.chars();
+        let letter = match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+            _ => return Err(format!("无效的单元格: {}", text)),
+        };
+        let digits = chars.as_str();
+        if digits.is_empty() || digits.len() > 3 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("无效的单元格: {}", text));
+        }
+
+        let row: u16 = digits.parse().map_err(|_| format!("无效的单元格: {}", text))?;
+        CellRef::new(letter as u8 - b'A', row)
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'A' + self.column) as char, self.row)
+    }
+}
+
+// 单元格内容
+#[derive(Debug, Clone)]
+enum Content {
+    Number(Value),
+    Text(String),
+    Formula(Expr),
+}
+
+#[derive(Debug, Clone)]
+struct Cell {
+    // 用户输入的原始文本，保存 CSV 时原样写回
+    raw: String,
+    content: Content,
+}
+
+pub struct Sheet {
+    calculator: Calculator,
+    cells: HashMap<CellRef, Cell>,
+    // 数字和公式单元格的计算结果
+    values: HashMap<CellRef, Result<Value, String>>,
+    // 单元格 -> 它的公式读取的单元格
+    dependencies: HashMap<CellRef, HashSet<CellRef>>,
+    // 单元格 -> 读取它的公式单元格
+    dependents: HashMap<CellRef, HashSet<CellRef>>,
+}
+
+impl Sheet {
+    pub fn new() -> Sheet {
+        Sheet::with_calculator(Calculator::new())
+    }
+
+    // 使用自定义的计算器（例如注册了额外运算符的计算器）
+    pub fn with_calculator(calculator: Calculator) -> Sheet {
+        Sheet {
+            calculator,
+            cells: HashMap::new(),
+            values: HashMap::new(),
+            dependencies: HashMap::new(),
+            dependents: HashMap::new(),
+        }
+    }
+
+    // 设置单元格内容，空字符串表示清空
+    // 以 `=` 开头的是公式，能解析为数字的是数字，其余是文本
+    // 返回按计算顺序排列的、被重新计算的单元格；出现循环引用时拒绝修改
+    pub fn set(&mut self, cell: CellRef, raw: &str) -> Result<Vec<CellRef>, String> {
+        let raw = raw.trim();
+        let content = if raw.is_empty() {
+            None
+        } else if let Some(formula) = raw.strip_prefix('=') {
+            let expr = self
+                .calculator
+                .parse(formula)
+                .map_err(|e| format!("{} 的公式有误: {}", cell, e))?;
+            Some(Content::Formula(expr))
+        } else if let Ok(value) = Value::parse(raw) {
+            Some(Content::Number(value))
+        } else {
+            Some(Content::Text(raw.to_string()))
+        };
+
+        let new_dependencies = match &content {
+            Some(Content::Formula(expr)) => references(expr)?,
+            _ => HashSet::new(),
+        };
+        if let Some(path) = self.find_cycle(cell, &new_dependencies) {
+            let path: Vec<String> = path.iter().map(|c| c.to_string()).collect();
+            return Err(format!("循环引用: {}", path.join(" -> ")));
+        }
+
+        self.set_dependencies(cell, new_dependencies);
+        match content {
+            Some(content) => {
+                self.cells.insert(cell, Cell {
+                    raw: raw.to_string(),
+                    content,
+                });
+            }
+            None => {
+                self.cells.remove(&cell);
+            }
+        }
+
+        Ok(self.recalculate_from(cell))
+    }
+
+    // 按名称设置单元格，例如 sheet.set_by_name("A1", "=B1 + 1")
+    pub fn set_by_name(&mut self, name: &str, raw: &str) -> Result<Vec<CellRef>, String> {
+        self.set(CellRef::parse(name)?, raw)
+    }
+
+    // 单元格的计算结果，空单元格和文本单元格返回 None
+    pub fn value(&self, cell: CellRef) -> Option<&Result<Value, String>> {
+        self.values.get(&cell)
+    }
+
+    // 单元格的原始输入
+    pub fn raw(&self, cell: CellRef) -> Option<&str> {
+        self.cells.get(&cell).map(|c| c.raw.as_str())
+    }
+
+    // 单元格显示的文本：文本原样显示，出错的单元格显示 #ERR
+    pub fn display(&self, cell: CellRef) -> String {
+        match self.cells.get(&cell) {
+            None => String::new(),
+            Some(Cell {
+                content: Content::Text(text),
+                ..
+            }) => text.clone(),
+            Some(_) => match self.values.get(&cell) {
+                Some(Ok(value)) => value.to_string(),
+                _ => String::from("#ERR"),
+            },
+        }
+    }
+
+    // 所有出错的单元格及错误信息
+    pub fn errors(&self) -> Vec<(CellRef, String)> {
+        let mut errors: Vec<(CellRef, String)> = self
+            .values
+            .iter()
+            .filter_map(|(cell, value)| value.as_ref().err().map(|e| (*cell, e.clone())))
+            .collect();
+        errors.sort();
+        errors
+    }
+
+    // 已使用区域的右下角，空表返回 None
+    pub fn extent(&self) -> Option<CellRef> {
+        let max_row = self.cells.keys().map(|c| c.row).max()?;
+        let max_column = self.cells.keys().map(|c| c.column).max()?;
+        Some(CellRef {
+            row: max_row,
+            column: max_column,
+        })
+    }
+
+    // 检查把 cell 的依赖改为 new_dependencies 后是否会形成环，返回环上的路径
+    fn find_cycle(&self, cell: CellRef, new_dependencies: &HashSet<CellRef>) -> Option<Vec<CellRef>> {
+        let mut sorted: Vec<&CellRef> = new_dependencies.iter().collect();
+        sorted.sort();
+
+        for &start in sorted {
+            let mut path = vec![cell];
+            let mut visited = HashSet::new();
+            if self.reaches(start, cell, &mut path, &mut visited) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    // 深度优先搜索：从 from 沿依赖关系能否走到 target，能走到时 path 记录经过的单元格
+    fn reaches(&self, from: CellRef, target: CellRef, path: &mut Vec<CellRef>, visited: &mut HashSet<CellRef>) -> bool {
+        path.push(from);
+        if from == target {
+            return true;
+        }
+        if visited.insert(from) {
+            if let Some(next) = self.dependencies.get(&from) {
+                let mut next: Vec<&CellRef> = next.iter().collect();
+                next.sort();
+                for &dependency in next {
+                    if self.reaches(dependency, target, path, visited) {
+                        return true;
+                    }
+                }
+            }
+        }
+        path.pop();
+        false
+    }
+
+    fn set_dependencies(&mut self, cell: CellRef, new_dependencies: HashSet<CellRef>) {
+        if let Some(old) = self.dependencies.remove(&cell) {
+            for dependency in old {
+                if let Some(readers) = self.dependents.get_mut(&dependency) {
+                    readers.remove(&cell);
+                }
+            }
+        }
+        for dependency in &new_dependencies {
+            self.dependents.entry(*dependency).or_default().insert(cell);
+        }
+        if !new_dependencies.is_empty() {
+            self.dependencies.insert(cell, new_dependencies);
+        }
+    }
+
+    // 重算 cell 以及所有直接或间接依赖它的单元格，按拓扑顺序进行
+    fn recalculate_from(&mut self, cell: CellRef) -> Vec<CellRef> {
+        // 在“被依赖”关系上做后序遍历，逆序后就是拓扑顺序
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        self.post_order(cell, &mut visited, &mut order);
+        order.reverse();
+
+        for &target in &order {
+            let value = self.compute(target);
+            match value {
+                Some(value) => self.values.insert(target, value),
+                None => self.values.remove(&target),
+            };
+        }
+        order
+    }
+
+    fn post_order(&self, cell: CellRef, visited: &mut HashSet<CellRef>, order: &mut Vec<CellRef>) {
+        if !visited.insert(cell) {
+            return;
+        }
+        if let Some(readers) = self.dependents.get(&cell) {
+            let mut readers: Vec<&CellRef> = readers.iter().collect();
+            readers.sort();
+            for &reader in readers {
+                self.post_order(reader, visited, order);
+            }
+        }
+        order.push(cell);
+    }
+
+    // 公式中直接引用的单元格：空单元格当作 0，文本单元格不能参与计算
+    fn cell_value(&self, cell: CellRef) -> Result<Value, String> {
+        match self.cells.get(&cell) {
+            None => Ok(Value::from(0)),
+            Some(Cell {
+                content: Content::Text(_),
+                ..
+            }) => Err(format!("{} 不是数字", cell)),
+            Some(_) => match self.values.get(&cell) {
+                Some(Ok(value)) => Ok(value.clone()),
+                _ => Err(format!("{} 有错误", cell)),
+            },
+        }
+    }
+
+    fn compute(&self, cell: CellRef) -> Option<Result<Value, String>> {
+        match &self.cells.get(&cell)?.content {
+            Content::Number(value) => Some(Ok(value.clone())),
+            Content::Text(_) => None,
+            Content::Formula(expr) => Some(eval::evaluate(expr, self.calculator.registry(), self)),
+        }
+    }
+
+    // 从 CSV 文本加载，公式原样保留
+    pub fn from_csv(text: &str) -> Result<Sheet, String> {
+        let mut sheet = Sheet::new();
+        for (row_index, record) in parse_csv(text)?.into_iter().enumerate() {
+            for (column_index, field) in record.iter().enumerate() {
+                if field.trim().is_empty() {
+                    continue;
+                }
+                // 先检查能否转换，直接截断会让超出范围的位置绕回前面的单元格
+                let cell = match (u8::try_from(column_index), u16::try_from(row_index + 1)) {
+                    (Ok(column), Ok(row)) => CellRef::new(column, row),
+                    _ => Err(format!("单元格超出范围 A1..Z{}", MAX_ROWS)),
+                }
+                .map_err(|e| format!("第 {} 行: {}", row_index + 1, e))?;
+                sheet
+                    .set(cell, field)
+                    .map_err(|e| format!("第 {} 行: {}", row_index + 1, e))?;
+            }
+        }
+        Ok(sheet)
+    }
+
+    // 导出为 CSV 文本，公式写成 `=...` 的原始形式
+    pub fn to_csv(&self) -> String {
+        let extent = match self.extent() {
+            Some(extent) => extent,
+            None => return String::new(),
+        };
+
+        let mut output = String::new();
+        for row in 1..=extent.row {
+            let fields: Vec<String> = (0..=extent.column)
+                .map(|column| {
+                    let raw = self.raw(CellRef { row, column }).unwrap_or("");
+                    escape_csv(raw)
+                })
+                .collect();
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        }
+        output
+    }
+
+    pub fn load(path: &str) -> Result<Sheet, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+        Sheet::from_csv(&text)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_csv()).map_err(|e| format!("无法写入 {}: {}", path, e))
+    }
+
+    // 把已使用区域渲染成带行号和列名的文本表格
+    pub fn render(&self) -> String {
+        let extent = match self.extent() {
+            Some(extent) => extent,
+            None => return String::from("(空表)\n"),
+        };
+
+        let columns: Vec<u8> = (0..=extent.column).collect();
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|&column| {
+                (1..=extent.row)
+                    .map(|row| display_width(&self.display(CellRef { row, column })))
+                    .max()
+                    .unwrap_or(0)
+                    .max(1)
+            })
+            .collect();
+
+        let mut output = format!("{:>4} |", "");
+        for (&column, &width) in columns.iter().zip(&widths) {
+            output.push_str(&format!(" {:^width$} |", (b'A' + column) as char, width = width));
+        }
+        output.push('\n');
+
+        for row in 1..=extent.row {
+            output.push_str(&format!("{:>4} |", row));
+            for (&column, &width) in columns.iter().zip(&widths) {
+                let text = self.display(CellRef { row, column });
+                let padding = " ".repeat(width - display_width(&text));
+                output.push_str(&format!(" {}{} |", padding, text));
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl Default for Sheet {
+    fn default() -> Sheet {
+        Sheet::new()
+    }
+}
+
+// 公式求值时的环境：单元格名称就是变量
+impl Environment for Sheet {
+    fn variable(&self, name: &str) -> Option<Result<Value, String>> {
+        let cell = CellRef::parse(name).ok()?;
+        Some(self.cell_value(cell))
+    }
+
+    fn range(&self, from: &str, to: &str) -> Option<Result<Vec<Value>, String>> {
+        let cells = match expand_range(from, to) {
+            Ok(cells) => cells,
+            Err(e) => return Some(Err(e)),
+        };
+
+        // 区域中的空单元格和文本单元格会被跳过，和常见电子表格的 sum/avg 行为一致
+        let mut values = Vec::new();
+        for cell in cells {
+            match self.values.get(&cell) {
+                Some(Ok(value)) => values.push(value.clone()),
+                Some(Err(_)) => return Some(Err(format!("{} 有错误", cell))),
+                None => {}
+            }
+        }
+        Some(Ok(values))
+    }
+}
+
+// 收集公式中引用的所有单元格，区域会展开成其中的每个单元格
+fn references(expr: &Expr) -> Result<HashSet<CellRef>, String> {
+    let mut cells = HashSet::new();
+    collect_references(expr, &mut cells)?;
+    Ok(cells)
+}
+
+fn collect_references(expr: &Expr, cells: &mut HashSet<CellRef>) -> Result<(), String> {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Variable(name) => {
+            if let Ok(cell) = CellRef::parse(name) {
+                cells.insert(cell);
+            }
+        }
+        Expr::Unary { operand, .. } => collect_references(operand, cells)?,
+        Expr::Binary { left, right, .. } => {
+            collect_references(left, cells)?;
+            collect_references(right, cells)?;
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_references(arg, cells)?;
+            }
+        }
+        Expr::Range { from, to } => cells.extend(expand_range(from, to)?),
+        Expr::List(items) => {
+            for item in items {
+                collect_references(item, cells)?;
+            }
+        }
+        Expr::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            collect_references(condition, cells)?;
+            collect_references(then, cells)?;
+            collect_references(otherwise, cells)?;
+        }
+    }
+    Ok(())
+}
+
+// 把 A1:B3 这样的区域展开成单元格列表，起点和终点的顺序可以颠倒
+fn expand_range(from: &str, to: &str) -> Result<Vec<CellRef>, String> {
+    let from = CellRef::parse(from)?;
+    let to = CellRef::parse(to)?;
+
+    let mut cells = Vec::new();
+    for row in from.row.min(to.row)..=from.row.max(to.row) {
+        for column in from.column.min(to.column)..=from.column.max(to.column) {
+            cells.push(CellRef { row, column });
+        }
+    }
+    Ok(cells)
+}
+
+// 终端中的显示宽度：中日韩文字和全角符号占两列
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+// 解析 CSV：支持用双引号包裹含逗号、引号或换行的字段，"" 表示一个引号
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(String::from("CSV 中的引号没有闭合"));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(name: &str) -> CellRef {
+        CellRef::parse(name).unwrap()
+    }
+
+    fn value_of(sheet: &Sheet, name: &str) -> Value {
+        sheet.value(cell(name)).unwrap().clone().unwrap()
+    }
+
+    #[test]
+    fn test_cell_ref() {
+        assert_eq!(cell("A1"), CellRef { row: 1, column: 0 });
+        assert_eq!(cell("z999").to_string(), "Z999");
+        assert!(CellRef::parse("A0").is_err());
+        assert!(CellRef::parse("A1000").is_err());
+        assert!(CellRef::parse("AA1").is_err());
+    }
+
+    #[test]
+    fn test_formula_with_range() {
+        let mut sheet = Sheet::new();
+        sheet.set_by_name("A1", "2").unwrap();
+        sheet.set_by_name("B2", "3").unwrap();
+        for (i, n) in ["1", "2", "3"].iter().enumerate() {
+            sheet.set_by_name(&format!("C{}", i + 1), n).unwrap();
+        }
+        sheet.set_by_name("D1", "=A1*B2+sum(C1:C9)").unwrap();
+
+        assert_eq!(value_of(&sheet, "D1"), Value::from(12));
+    }
+
+    #[test]
+    fn test_incremental_recalculation() {
+        let mut sheet = Sheet::new();
+        sheet.set_by_name("A1", "1").unwrap();
+        sheet.set_by_name("B1", "=A1 + 1").unwrap();
+        sheet.set_by_name("C1", "=B1 * 10").unwrap();
+        sheet.set_by_name("D1", "100").unwrap();
+
+        // 只重算 A1 和依赖它的单元格，并且按依赖顺序进行
+        let recalculated = sheet.set_by_name("A1", "5").unwrap();
+        assert_eq!(recalculated, vec![cell("A1"), cell("B1"), cell("C1")]);
+        assert_eq!(value_of(&sheet, "C1"), Value::from(60));
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let mut sheet = Sheet::new();
+        sheet.set_by_name("A1", "=B1 + 1").unwrap();
+        sheet.set_by_name("B1", "=C1 + 1").unwrap();
+
+        let error = sheet.set_by_name("C1", "=A1").unwrap_err();
+        assert_eq!(error, "循环引用: C1 -> A1 -> B1 -> C1");
+        assert!(sheet.set_by_name("D1", "=D1").is_err());
+        // 被拒绝的修改不会生效
+        assert_eq!(sheet.raw(cell("C1")), None);
+    }
+
+    #[test]
+    fn test_errors_propagate() {
+        let mut sheet = Sheet::new();
+        sheet.set_by_name("A1", "名称").unwrap();
+        sheet.set_by_name("B1", "=A1 + 1").unwrap();
+        sheet.set_by_name("C1", "=B1 * 2").unwrap();
+
+        assert!(sheet.value(cell("C1")).unwrap().is_err());
+        assert_eq!(sheet.display(cell("C1")), "#ERR");
+
+        sheet.set_by_name("A1", "4").unwrap();
+        assert_eq!(value_of(&sheet, "C1"), Value::from(10));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "项目,数量,单价\n苹果,3,2.5\n\"梨,大\",2,4\n合计,=sum(B2:B3),=B2*C2+B3*C3\n";
+        let sheet = Sheet::from_csv(csv).unwrap();
+
+        assert_eq!(value_of(&sheet, "B4"), Value::from(5));
+        assert_eq!(value_of(&sheet, "C4"), Value::from(15.5));
+        assert_eq!(sheet.display(cell("A3")), "梨,大");
+        assert_eq!(sheet.to_csv(), csv);
+    }
+
+    #[test]
+    fn test_csv_cycle_reports_line() {
+        let error = Sheet::from_csv("=B1,=A1\n").err().unwrap();
+        assert!(error.starts_with("第 1 行: 循环引用"));
+    }
+
+    #[test]
+    fn test_csv_deep_formula() {
+        let csv = format!("1,={}A1{}\n", "(".repeat(50_000), ")".repeat(50_000));
+        assert_eq!(Sheet::from_csv(&csv).err().unwrap(), "第 1 行: B1 的公式有误: 表达式嵌套过深");
+    }
+
+    #[test]
+    fn test_csv_out_of_range() {
+        // 第 257 列不会绕回 A 列
+        let wide = format!("1{}", ",".repeat(256)) + "2\n";
+        assert!(Sheet::from_csv(&wide).err().unwrap().contains("单元格超出范围"));
+
+        // 第 65536 行以后的内容不会绕回前面的行
+        let mut tall = "\n".repeat(65_535);
+        tall.push_str("1\n");
+        assert!(Sheet::from_csv(&tall).err().unwrap().contains("单元格超出范围"));
+        tall.insert(0, '\n');
+        assert!(Sheet::from_csv(&tall).err().unwrap().contains("单元格超出范围"));
+    }
+}
@@ -0,0 +1,127 @@
+// 计算器中的值：整数使用任意精度表示，其余数字使用 f64，
+// 比较和逻辑运算的结果是布尔值，poly([...]) 的结果是多项式
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+
+use crate::number_theory::parse_integer;
+use crate::polynomial::Polynomial;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(BigInt),
+    Float(f64),
+    Bool(bool),
+    Poly(Polynomial),
+}
+
+impl Value {
+    // 解析数字字面量：能作为整数解析时保持精确，否则按浮点数解析
+    pub fn parse(text: &str) -> Result<Value, String> {
+        let text = text.trim();
+        if let Ok(n) = parse_integer(text) {
+            return Ok(Value::Int(n));
+        }
+        match text.parse::<f64>() {
+            Ok(x) => Ok(Value::Float(x)),
+            Err(_) => Err(format!("'{}' 不是有效的数字", text)),
+        }
+    }
+
+    // 转换为 f64，大整数可能会丢失精度；布尔值按 1 和 0 处理，多项式不是数字
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Int(n) => n.to_f64().unwrap_or(f64::NAN),
+            Value::Float(x) => *x,
+            Value::Bool(b) => *b as u8 as f64,
+            Value::Poly(_) => f64::NAN,
+        }
+    }
+
+    // 取出整数值；整数值的浮点数（例如 4.0）也算整数
+    pub fn to_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Int(n) => Some(n.clone()),
+            Value::Float(x) if x.is_finite() && x.fract() == 0.0 => BigInt::from_f64(*x),
+            Value::Float(_) | Value::Bool(_) | Value::Poly(_) => None,
+        }
+    }
+
+    // 比较大小，两个整数精确比较，其余数字按 f64 比较（NaN 无法比较），
+    // 布尔值只能和布尔值比较，多项式只能判断是否相等
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Bool(_), _) | (_, Value::Bool(_)) => None,
+            (Value::Poly(a), Value::Poly(b)) if a == b => Some(Ordering::Equal),
+            (Value::Poly(_), _) | (_, Value::Poly(_)) => None,
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Value::Bool(_))
+    }
+
+    // 取出布尔值，用于条件判断
+    pub fn to_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(format!("条件必须是布尔值，实际是 {}", self)),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Value::Int(n) => n.is_zero(),
+            Value::Float(x) => *x == 0.0,
+            Value::Bool(b) => !b,
+            Value::Poly(p) => p.is_zero(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Poly(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Value {
+        Value::Float(x)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Value {
+        Value::Int(BigInt::from(n))
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(n: BigInt) -> Value {
+        Value::Int(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<Polynomial> for Value {
+    fn from(p: Polynomial) -> Value {
+        Value::Poly(p)
+    }
+}
@@ -0,0 +1,609 @@
+// 审计日志：每次修改都追加写入一个只增不改的事件日志，回答"谁在什么时候改了哪个成绩"
+//
+// 日志是 JSON Lines 文件，每行一条记录：
+//
+//     {"sequence":1,"time":1709281800,"actor":"teacher","event":{"type":"add_student","id":1,"name":"张三","age":20},
+//      "previous":"0000…","hash":"5f1c…"}
+//
+// hash 是 SHA-256(sequence、time、actor、event 和 previous)，previous 是上一条记录的 hash，
+// 第一条记录的 previous 为 64 个 0。修改任何一条过去的记录都会让它的 hash 或下一条记录的 previous 对不上，
+// verify 可以找出被修改的第一条记录。只修改最后一条并重新计算 hash，或者删掉末尾的记录，单靠日志本身无法发现，
+// 需要把最新的 hash 另外保存。
+//
+// 日志从空仓库开始时，按顺序重放所有事件就能重建学生和课程数据；在已有数据的仓库上开始记录时，
+// 第一条记录是当时全部数据的快照。
+//
+// 几个程序同时修改同一份数据时，每次修改都锁住 <日志文件名>.lock，接在日志当前的最后一条记录之后，
+// 修改数据和追加记录在同一个锁中完成，日志中记录的顺序就是数据修改的顺序。
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::course::Course;
+use crate::history::Clock;
+use crate::lock::FileLock;
+use crate::repository::{Query, StudentRepository};
+use crate::student::{Student, StudentManagement};
+
+// 设置这个环境变量可以指定审计日志文件
+pub const AUDIT_PATH_VARIABLE: &str = "STUDENT_AUDIT_LOG";
+
+// 设置这个环境变量可以指定日志中记录的操作人，否则使用系统用户名
+pub const ACTOR_VARIABLE: &str = "STUDENT_ACTOR";
+
+// 第一条记录的 previous
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// 一次修改；学生的修改保存修改后的完整记录，其中包括成绩修改历史
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    // 开始记录时仓库中已有的数据
+    Snapshot { courses: Vec<Course>, students: Vec<Student> },
+    AddStudent { id: u32, name: String, age: u8 },
+    UpdateStudent { student: Student },
+    RemoveStudent { id: u32 },
+    RestoreStudent { student: Student },
+    AddCourse { course: Course },
+    RemoveCourse { code: String },
+}
+
+impl Event {
+    // 在内存中的学生管理系统上重做这次修改
+    pub fn apply(&self, system: &mut StudentManagement) -> Result<(), String> {
+        match self {
+            Event::Snapshot { courses, students } => {
+                for course in courses {
+                    system.insert_course(course.clone())?;
+                }
+                for student in students {
+                    system.restore(student.clone())?;
+                }
+                Ok(())
+            }
+            Event::AddStudent { id, name, age } => {
+                // 开始记录前删除过的 ID 不在快照中，按日志中的 ID 继续分配
+                if *id < system.next_id() {
+                    return Err(format!("ID {} 已经分配过，不能再添加", id));
+                }
+                system.next_id = *id;
+                system.insert(name.clone(), *age).map(|_| ())
+            }
+            Event::UpdateStudent { student } => system.update(student.clone()),
+            Event::RemoveStudent { id } => {
+                if system.remove(*id)? {
+                    Ok(())
+                } else {
+                    Err(format!("未找到ID为 {} 的学生", id))
+                }
+            }
+            Event::RestoreStudent { student } => system.restore(student.clone()),
+            Event::AddCourse { course } => system.insert_course(course.clone()),
+            Event::RemoveCourse { code } => {
+                if system.remove_course(code)? {
+                    Ok(())
+                } else {
+                    Err(format!("课程 {} 不存在", code))
+                }
+            }
+        }
+    }
+}
+
+// 日志中的一条记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    // 从 1 开始连续编号
+    pub sequence: u64,
+    pub time: u64,
+    pub actor: String,
+    pub event: Event,
+    pub previous: String,
+    pub hash: String,
+}
+
+impl Entry {
+    fn new(sequence: u64, time: u64, actor: &str, event: Event, previous: &str) -> Result<Entry, String> {
+        let hash = compute_hash(sequence, time, actor, &event, previous)?;
+        Ok(Entry {
+            sequence,
+            time,
+            actor: actor.to_string(),
+            event,
+            previous: previous.to_string(),
+            hash,
+        })
+    }
+}
+
+fn compute_hash(sequence: u64, time: u64, actor: &str, event: &Event, previous: &str) -> Result<String, String> {
+    let content = serde_json::to_string(&(sequence, time, actor, event, previous))
+        .map_err(|e| format!("无法生成审计记录: {}", e))?;
+    let digest = Sha256::digest(content.as_bytes());
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// 读取日志中的所有记录，不检查哈希链；文件不存在时返回空列表
+pub fn read(path: &Path) -> Result<Vec<Entry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("无法读取审计日志 {}: {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| format!("审计日志第 {} 行无法解析（{}）", index + 1, e))
+        })
+        .collect()
+}
+
+// 检查哈希链，返回记录数；发现被修改、删除或插入的记录时返回说明第几条记录有问题的错误
+pub fn verify(entries: &[Entry]) -> Result<usize, String> {
+    let mut previous = GENESIS_HASH;
+    for (index, entry) in entries.iter().enumerate() {
+        let expected = index as u64 + 1;
+        if entry.sequence != expected {
+            return Err(format!("第 {} 条记录的序号是 {}，之前的记录可能被删除或插入", expected, entry.sequence));
+        }
+        if entry.previous != previous {
+            return Err(format!("第 {} 条记录与上一条记录的哈希对不上，之前的记录可能被修改", expected));
+        }
+        let hash = compute_hash(entry.sequence, entry.time, &entry.actor, &entry.event, &entry.previous)?;
+        if entry.hash != hash {
+            return Err(format!("第 {} 条记录的内容与哈希不符，记录已被修改", expected));
+        }
+        previous = &entry.hash;
+    }
+    Ok(entries.len())
+}
+
+// 按顺序重放所有记录，得到内存中的学生管理系统
+pub fn replay(entries: &[Entry]) -> Result<StudentManagement, String> {
+    let mut system = StudentManagement::new();
+    for entry in entries {
+        entry
+            .event
+            .apply(&mut system)
+            .map_err(|e| format!("重放第 {} 条记录失败: {}", entry.sequence, e))?;
+    }
+    Ok(system)
+}
+
+// 只能追加的日志文件
+pub struct EventLog {
+    path: PathBuf,
+    next_sequence: u64,
+    last_hash: String,
+}
+
+impl EventLog {
+    // 打开日志并接在最后一条记录之后，文件不存在时在第一次追加时创建
+    pub fn open(path: &Path) -> Result<EventLog, String> {
+        let mut log = EventLog {
+            path: path.to_path_buf(),
+            next_sequence: 1,
+            last_hash: GENESIS_HASH.to_string(),
+        };
+        log.reload()?;
+        Ok(log)
+    }
+
+    // 重新读取最后一条记录，其他程序可能在这之后追加过
+    fn reload(&mut self) -> Result<(), String> {
+        let last = read(&self.path)?.pop();
+        self.next_sequence = last.as_ref().map_or(1, |entry| entry.sequence + 1);
+        self.last_hash = last.map_or_else(|| GENESIS_HASH.to_string(), |entry| entry.hash);
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_sequence == 1
+    }
+
+    // 追加一条记录并立即写入磁盘
+    pub fn append(&mut self, time: u64, actor: &str, event: Event) -> Result<Entry, String> {
+        let entry = Entry::new(self.next_sequence, time, actor, event, &self.last_hash)?;
+        let mut line = serde_json::to_string(&entry).map_err(|e| format!("无法生成审计记录: {}", e))?;
+        line.push('\n');
+
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("无法创建目录 {}: {}", dir.display(), e))?;
+        }
+        let error = |e: std::io::Error| format!("无法写入审计日志 {}: {}", self.path.display(), e);
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).map_err(error)?;
+        file.write_all(line.as_bytes()).map_err(error)?;
+        file.sync_data().map_err(error)?;
+
+        self.next_sequence += 1;
+        self.last_hash = entry.hash.clone();
+        Ok(entry)
+    }
+}
+
+// 把每次成功的修改写入审计日志的仓库，读取直接交给被包装的仓库
+pub struct AuditedRepository {
+    repository: Box<dyn StudentRepository>,
+    log: EventLog,
+    actor: String,
+    clock: Box<dyn Clock>,
+}
+
+impl AuditedRepository {
+    // 日志为空而仓库中已有数据时，先记录一条快照，之后的重放才能得到同样的数据
+    pub fn open(
+        repository: Box<dyn StudentRepository>,
+        path: &Path,
+        actor: &str,
+        clock: Box<dyn Clock>,
+    ) -> Result<AuditedRepository, String> {
+        // 在锁中检查日志是否为空，两个程序同时第一次打开时只记录一次快照
+        let _lock = FileLock::exclusive(path)?;
+        let mut log = EventLog::open(path)?;
+        if log.is_empty() {
+            let courses = repository.list_courses()?;
+            let students = repository.list()?;
+            if !courses.is_empty() || !students.is_empty() {
+                log.append(clock.now(), actor, Event::Snapshot { courses, students })?;
+            }
+        }
+        Ok(AuditedRepository {
+            repository,
+            log,
+            actor: actor.to_string(),
+            clock,
+        })
+    }
+
+    pub fn log_path(&self) -> &Path {
+        self.log.path()
+    }
+
+    // 锁住日志，接在最新的记录之后执行修改，修改成功后按顺序追加它产生的记录
+    fn record<T>(&mut self, change: impl FnOnce(&mut Recording) -> Result<T, String>) -> Result<T, String> {
+        let _lock = FileLock::exclusive(self.log.path())?;
+        self.log.reload()?;
+        let mut recording = Recording {
+            repository: self.repository.as_mut(),
+            events: Vec::new(),
+        };
+        let result = change(&mut recording)?;
+        let time = self.clock.now();
+        for event in recording.events {
+            self.log.append(time, &self.actor, event)?;
+        }
+        Ok(result)
+    }
+}
+
+impl StudentRepository for AuditedRepository {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        self.repository.get(id)
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        self.record(|recording| recording.insert(name, age))
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        self.record(|recording| recording.update(student))
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        self.record(|recording| recording.modify(id, change))
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        self.record(|recording| recording.remove(id))
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        self.record(|recording| recording.restore(student))
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        self.record(|recording| recording.batch(change))
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.repository.list()
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        self.repository.query(query)
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        self.record(|recording| recording.insert_course(course))
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        self.record(|recording| recording.remove_course(code))
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        self.repository.list_courses()
+    }
+}
+
+// 执行修改并记下对应的事件；batch 中的修改交给被包装仓库的 batch，整批成功后才记下其中的所有事件
+struct Recording<'a> {
+    repository: &'a mut dyn StudentRepository,
+    events: Vec<Event>,
+}
+
+impl StudentRepository for Recording<'_> {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        self.repository.get(id)
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        let id = self.repository.insert(name.clone(), age)?;
+        self.events.push(Event::AddStudent { id, name, age });
+        Ok(id)
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        self.repository.update(student.clone())?;
+        self.events.push(Event::UpdateStudent { student });
+        Ok(())
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        let student = self.repository.modify(id, change)?;
+        self.events.push(Event::UpdateStudent { student: student.clone() });
+        Ok(student)
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        let removed = self.repository.remove(id)?;
+        if removed {
+            self.events.push(Event::RemoveStudent { id });
+        }
+        Ok(removed)
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        self.repository.restore(student.clone())?;
+        self.events.push(Event::RestoreStudent { student });
+        Ok(())
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        let mut events = Vec::new();
+        self.repository.batch(&mut |repository| {
+            let mut recording = Recording {
+                repository,
+                events: Vec::new(),
+            };
+            change(&mut recording)?;
+            events = recording.events;
+            Ok(())
+        })?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.repository.list()
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        self.repository.query(query)
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        self.repository.insert_course(course.clone())?;
+        self.events.push(Event::AddCourse { course });
+        Ok(())
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        let removed = self.repository.remove_course(code)?;
+        if removed {
+            self.events.push(Event::RemoveCourse { code: code.to_string() });
+        }
+        Ok(removed)
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        self.repository.list_courses()
+    }
+}
+
+// 检查日志的哈希链，并确认重放日志得到的数据与仓库中的数据相同，返回记录数
+pub fn verify_against(path: &Path, repository: &dyn StudentRepository) -> Result<usize, String> {
+    let entries = read(path)?;
+    let count = verify(&entries)?;
+    let replayed = replay(&entries)?;
+    if replayed.list_courses()? != repository.list_courses()? {
+        return Err(String::from("重放日志得到的课程与数据文件不一致，数据可能在日志之外被修改"));
+    }
+    let replayed = replayed.list()?;
+    let students = repository.list()?;
+    for student in &students {
+        if !replayed.contains(student) {
+            return Err(format!("重放日志得到的学生 {}（ID {}）与数据文件不一致，数据可能在日志之外被修改", student.name, student.id));
+        }
+    }
+    if replayed.len() != students.len() {
+        return Err(String::from("重放日志得到的学生数量与数据文件不一致，数据可能在日志之外被修改"));
+    }
+    Ok(count)
+}
+
+// 审计日志的默认位置：环境变量 STUDENT_AUDIT_LOG，否则是数据文件旁边的 <数据文件名>.audit.jsonl
+pub fn default_path(data_path: &Path) -> PathBuf {
+    match std::env::var_os(AUDIT_PATH_VARIABLE) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let mut name = data_path.file_name().unwrap_or_default().to_os_string();
+            name.push(".audit.jsonl");
+            data_path.with_file_name(name)
+        }
+    }
+}
+
+// 日志中记录的操作人：环境变量 STUDENT_ACTOR，否则是系统用户名
+pub fn current_actor() -> String {
+    [ACTOR_VARIABLE, "USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|actor| !actor.trim().is_empty())
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+    use crate::repository::conformance;
+    use crate::storage::JsonFileRepository;
+    use std::sync::Arc;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("student_audit_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("students.json.audit.jsonl")
+    }
+
+    fn audited(path: &Path, clock: Arc<ManualClock>) -> AuditedRepository {
+        AuditedRepository::open(Box::new(StudentManagement::new()), path, "teacher", Box::new(clock)).unwrap()
+    }
+
+    #[test]
+    fn test_log_records_changes_and_replays() {
+        let path = temp_log("replay");
+        let clock = Arc::new(ManualClock::new(1_709_281_800));
+        let mut repository = audited(&path, clock.clone());
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        repository.insert_course(math.clone()).unwrap();
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+        let second = repository.insert(String::from("李四"), 21).unwrap();
+        let mut student = repository.get(first).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        clock.advance(60);
+        student.record_grade(&math, 88.0, "期末考试", clock.as_ref()).unwrap();
+        repository.update(student.clone()).unwrap();
+        assert!(repository.remove(second).unwrap());
+        // 失败的修改不会写入日志
+        assert!(repository.update(Student::new(42, String::from("王五"), 19)).is_err());
+        assert!(!repository.remove(42).unwrap());
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].previous, GENESIS_HASH);
+        assert_eq!(entries[3].actor, "teacher");
+        assert_eq!(entries[3].time, 1_709_281_860);
+        assert_eq!(entries[3].event, Event::UpdateStudent { student: student.clone() });
+        assert_eq!(verify_against(&path, &repository).unwrap(), 5);
+
+        // 重新打开后接着原来的哈希链写
+        drop(repository);
+        let mut repository = AuditedRepository::open(Box::new(replay(&entries).unwrap()), &path, "admin", Box::new(clock)).unwrap();
+        assert_eq!(repository.insert(String::from("王五"), 19).unwrap(), second + 1);
+        let entries = read(&path).unwrap();
+        assert_eq!(verify(&entries).unwrap(), 6);
+        let replayed = replay(&entries).unwrap();
+        assert_eq!(replayed.list().unwrap(), repository.list().unwrap());
+        assert_eq!(replayed.next_id(), second + 2);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let path = temp_log("tamper");
+        let mut repository = audited(&path, Arc::new(ManualClock::default()));
+        for name in ["张三", "李四", "王五"] {
+            repository.insert(String::from(name), 20).unwrap();
+        }
+        let entries = read(&path).unwrap();
+        assert_eq!(verify(&entries).unwrap(), 3);
+
+        // 只改内容
+        let mut edited = entries.clone();
+        edited[1].actor = String::from("someone");
+        assert!(verify(&edited).unwrap_err().contains("第 2 条"));
+
+        // 改内容并重新计算这一条的哈希，下一条记录对不上
+        let mut edited = entries.clone();
+        edited[1].event = Event::AddStudent { id: 2, name: String::from("李斯"), age: 20 };
+        edited[1].hash = compute_hash(2, edited[1].time, &edited[1].actor, &edited[1].event, &edited[1].previous).unwrap();
+        assert!(verify(&edited).unwrap_err().contains("第 3 条"));
+
+        // 删除中间的记录
+        let mut edited = entries.clone();
+        edited.remove(1);
+        assert!(verify(&edited).unwrap_err().contains("第 2 条"));
+
+        // 直接修改文件
+        let text = fs::read_to_string(&path).unwrap().replace("王五", "赵六");
+        fs::write(&path, text).unwrap();
+        assert!(verify_against(&path, &repository).unwrap_err().contains("第 3 条"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_of_existing_data() {
+        let path = temp_log("snapshot");
+        let mut system = StudentManagement::new();
+        system.insert(String::from("张三"), 20).unwrap();
+        let removed = system.insert(String::from("李四"), 21).unwrap();
+        system.remove(removed).unwrap();
+        let mut repository = AuditedRepository::open(Box::new(system), &path, "teacher", Box::new(ManualClock::default())).unwrap();
+        // 快照中没有删除过的 ID 2，重放时按日志中的 ID 继续分配
+        assert_eq!(repository.insert(String::from("王五"), 19).unwrap(), 3);
+        let entries = read(&path).unwrap();
+        assert!(matches!(entries[0].event, Event::Snapshot { .. }));
+        assert_eq!(verify_against(&path, &repository).unwrap(), 2);
+
+        // 数据在日志之外被修改
+        let mut bypassed = replay(&entries).unwrap();
+        bypassed.insert(String::from("赵六"), 22).unwrap();
+        assert!(verify_against(&path, &bypassed).unwrap_err().contains("赵六"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_audited_conformance() {
+        let path = temp_log("conformance");
+        let mut count = 0;
+        conformance::run(&mut || {
+            count += 1;
+            let path = path.with_file_name(format!("{}.audit.jsonl", count));
+            Box::new(audited(&path, Arc::new(ManualClock::default())))
+        });
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_one_chain() {
+        // 几个程序同时修改同一个数据文件，日志仍然是一条完整的哈希链，重放的顺序与数据修改的顺序一致
+        let path = temp_log("concurrent");
+        let data = path.with_file_name("students.json");
+        let open = || -> Box<dyn StudentRepository> {
+            let repository = Box::new(JsonFileRepository::open(&data).unwrap());
+            Box::new(AuditedRepository::open(repository, &path, "teacher", Box::new(ManualClock::default())).unwrap())
+        };
+        conformance::check_concurrent_writers(&open, 4, 10);
+        // 登记课程、添加学生、选课，再加上每个线程每轮的添加和修改
+        assert_eq!(verify_against(&path, open().as_ref()).unwrap(), 3 + 4 * 10 * 2);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_default_path() {
+        if std::env::var_os(AUDIT_PATH_VARIABLE).is_none() {
+            assert_eq!(default_path(Path::new("/data/students.db")), PathBuf::from("/data/students.db.audit.jsonl"));
+        }
+    }
+}
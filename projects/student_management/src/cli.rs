@@ -0,0 +1,1494 @@
+// 非交互式命令行：解析子命令并在存储后端上执行，方便脚本管理学生记录
+//
+//     student_management add --name 张三 --age 20
+//     student_management course add --code MATH101 --name 数学 --credits 4
+//     student_management enroll 3 --course MATH101
+//     student_management grade 3 --course 数学 --score 92 --reason 补考
+//     student_management grade-history 3 数学
+//     student_management assess 3 --course MATH101 --name 作业1 --category 作业 --score 18 --out-of 20
+//     student_management list --sort avg --format csv
+//     student_management list --sort gpa --scale 4.3
+//     student_management stats 数学
+//     student_management rank --class 一班 --dense --top 10
+//     student_management report 3 --format md --output 张三.md
+//     student_management report --all --dir reports --template school.html
+//     student_management list --where "age >= 18 and avg < 60" --sort -avg,name --limit 20 --offset 40
+//     student_management remove 3 --yes
+//     student_management import students.csv
+//     student_management verify-log
+//     student_management serve --port 8080
+//     student_management tui
+//     student_management --storage sqlite list
+//
+// 交互式菜单在 examples/02_student_management.rs 中，对应 interactive 子命令；
+// 全屏终端界面在 tui 模块中，对应 tui 子命令。
+
+use std::cmp::Ordering;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::course::{Course, DEFAULT_MAX_SCORE};
+use crate::csv;
+use crate::filter::Filter;
+use crate::gradebook::{Assessment, Category};
+use crate::audit;
+use crate::grading::{self, GradingConfig};
+use crate::history::{self, Clock, SystemClock};
+use crate::import::{self, import_csv};
+use crate::report::{self, ReportCard, ReportFormat, Template};
+use crate::repository::{Backend, StudentRepository};
+use crate::ranking::{Basis, Limit, RankStyle, Ranking};
+use crate::server;
+use crate::stats::{self, CourseStats};
+use crate::student::Student;
+
+pub const USAGE: &str = "用法: student_management [--storage memory|json|sqlite] <命令>
+
+命令:
+  interactive                                 交互式菜单（不带命令时的默认行为）
+  add --name <姓名> --age <年龄> [--class <班级>]
+                                              添加学生，输出新学生的 ID
+  class <ID> <班级>                            设置学生的班级，--clear 表示取消分班
+  course add --code <代码> --name <名称> --credits <学分> [--max-score <满分>] [--pass-fail]
+      [--category <类别>=<权重>[,drop=<次数>][,late=<每天扣分>]]...
+                                              登记课程，满分默认为 100，
+                                              --pass-fail 表示通过制课程，不计入平均分和 GPA，
+                                              --category 可以重复，权重合计 100，
+                                              例如 --category 作业=30,drop=1,late=10
+                                              有评分类别的课程成绩由 assess 记录的评估计算
+  course list                                 列出所有课程
+  course remove <代码> --yes                   删除没有学生选修的课程
+  enroll <ID> --course <课程>                  选修课程，课程可以是代码或名称
+  grade <ID> --course <课程> --score <分数> [--reason <原因>]
+                                              为选修的课程记录成绩（0 到课程满分），
+                                              原来的成绩和修改原因记入历史
+  grade-history <ID> <课程>                    显示课程成绩的修改历史
+  assess <ID> --course <课程> --name <名称> --category <类别> --score <分数> [--out-of <满分>] [--late <天数>]
+                                              记录一次作业或考试，满分默认为 100，
+                                              同名评估会被替换，课程成绩随之重新计算
+  list [--where <条件>] [--sort <字段>[,<字段>...]] [--limit <数量>] [--offset <跳过数量>]
+       [--format table|csv] [--scale <换算表>]
+                                              列出学生的学分加权平均分和 GPA，换算表默认为 4.0；
+                                              条件例如 \"age >= 18 and avg < 60 and has(数学)\"，
+                                              排序字段为 id、name、age、avg、gpa，前面加 - 从大到小、
+                                              加 + 从小到大，不加时 avg、gpa 从高到低，其余从小到大
+  scales                                      显示 GPA 换算表
+  stats [<课程>]                               课程统计：人数、平均分、中位数、标准差、四分位数、及格率和分数段分布，
+                                              指定课程时还列出每个学生的百分位
+  rank [--course <课程>] [--class <班级>] [--dense] [--top <N> | --bottom <N>]
+                                              按平均分（或一门课程的成绩）从高到低排名，
+                                              同分名次相同，默认 1, 2, 2, 4，--dense 为 1, 2, 2, 3；
+                                              --class 只在班级内排名，--top、--bottom 只显示前后 N 名（含同分），
+                                              没有成绩的学生不参与排名
+  report <ID> [--format html|md] [--template <文件>] [--output <文件>]
+                                              生成学生的成绩单（基本信息、课程成绩表、平均分、GPA 和班级排名），
+                                              格式默认为 html，不指定 --output 时输出到屏幕；
+                                              模板中的 {{name}}、{{courses}} 等占位符在生成时替换
+  report --all --dir <目录> [--format html|md] [--template <文件>]
+                                              为每个学生生成一份成绩单，文件名为 <ID>-<姓名>.html
+  remove <ID> --yes                           删除学生，必须加 --yes 确认
+  import <文件.csv>                            批量导入学生（name,age）、选课（id,course）
+                                              或成绩（id,course,score），
+                                              有任何一行不合格时不导入任何记录
+  verify-log [<日志文件>]                      检查审计日志的哈希链，并确认重放日志得到的数据与当前数据相同，
+                                              日志默认为数据文件旁边的 <数据文件名>.audit.jsonl
+  serve [--port <端口>]                        在 127.0.0.1 上启动 REST API（默认端口 8080），
+                                              用 JSON 增删改查学生、课程和成绩，接口说明见 /openapi.json
+  tui                                         全屏终端界面：学生表格、成绩详情、编辑表单和边输入边搜索，
+                                              按 ? 查看快捷键
+  help                                        显示本帮助";
+
+// 解析后的命令行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub backend: Backend,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Interactive,
+    Help,
+    Add { name: String, age: u8, class: Option<String> },
+    // class 为 None 时取消分班
+    SetClass { id: u32, class: Option<String> },
+    AddCourse { course: Course },
+    ListCourses,
+    RemoveCourse { code: String },
+    Enroll { id: u32, course: String },
+    Grade { id: u32, course: String, score: f32, reason: String },
+    GradeHistory { id: u32, course: String },
+    Assess { id: u32, course: String, assessment: Assessment },
+    List {
+        filter: Option<Filter>,
+        sort: Vec<SortField>,
+        // 跳过前 offset 名，最多显示 limit 名
+        offset: usize,
+        limit: Option<usize>,
+        format: ListFormat,
+        scale: Option<String>,
+    },
+    Scales,
+    Stats { course: Option<String> },
+    Rank {
+        course: Option<String>,
+        class: Option<String>,
+        style: RankStyle,
+        limit: Option<Limit>,
+    },
+    Report {
+        target: ReportTarget,
+        format: ReportFormat,
+        // 自定义模板，None 时使用内置模板
+        template: Option<PathBuf>,
+    },
+    Remove { id: u32 },
+    Import { path: PathBuf },
+    VerifyLog { path: Option<PathBuf> },
+    Serve { port: u16 },
+    Tui,
+}
+
+// 为一个学生生成成绩单（output 为 None 时输出到屏幕），或者为所有学生各生成一个文件
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportTarget {
+    Student { id: u32, output: Option<PathBuf> },
+    All { dir: PathBuf },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Id,
+    Name,
+    Age,
+    Average,
+    Gpa,
+}
+
+// 排序字段和方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortField {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl SortField {
+    // 解析 "-avg,name" 形式的排序字段列表
+    pub fn parse_list(text: &str) -> Result<Vec<SortField>, String> {
+        text.split(',').map(|part| SortField::parse(part.trim())).collect()
+    }
+
+    fn parse(text: &str) -> Result<SortField, String> {
+        let (direction, name) = match text.chars().next() {
+            Some(sign @ ('-' | '+')) => (Some(sign == '-'), &text[1..]),
+            _ => (None, text),
+        };
+        let key = match name {
+            "id" => SortKey::Id,
+            "name" => SortKey::Name,
+            "age" => SortKey::Age,
+            "avg" => SortKey::Average,
+            "gpa" => SortKey::Gpa,
+            _ => return Err(format!("未知的排序方式: {}（可选 id、name、age、avg、gpa，前面可以加 - 或 +）", text)),
+        };
+        // 不指定方向时平均分和 GPA 从高到低
+        let descending = direction.unwrap_or(matches!(key, SortKey::Average | SortKey::Gpa));
+        Ok(SortField { key, descending })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Table,
+    Csv,
+}
+
+// 解析命令行参数（不含程序名），--storage 可以出现在任何位置
+pub fn parse(args: &[String]) -> Result<Cli, String> {
+    let mut args = args.to_vec();
+    let backend = match take_option(&mut args, "--storage")? {
+        Some(name) => Backend::parse(&name)?,
+        None => Backend::Json,
+    };
+
+    let command = if args.is_empty() {
+        Command::Interactive
+    } else {
+        let name = args.remove(0);
+        parse_command(&name, args)?
+    };
+    Ok(Cli { backend, command })
+}
+
+fn parse_command(name: &str, mut args: Vec<String>) -> Result<Command, String> {
+    let command = match name {
+        "interactive" => Command::Interactive,
+        "tui" => Command::Tui,
+        "help" | "--help" | "-h" => Command::Help,
+        "add" => {
+            let name = required_option(&mut args, "--name")?;
+            if name.trim().is_empty() {
+                return Err(String::from("姓名不能为空"));
+            }
+            let age = import::parse_age(&required_option(&mut args, "--age")?)?;
+            let class = take_class(&mut args)?;
+            Command::Add { name, age, class }
+        }
+        "class" => {
+            let clear = take_flag(&mut args, "--clear");
+            let id = take_id(&mut args)?;
+            let class = if clear {
+                None
+            } else if args.is_empty() {
+                return Err(String::from("缺少班级，取消分班请加 --clear"));
+            } else {
+                Some(parse_class(&args.remove(0))?)
+            };
+            Command::SetClass { id, class }
+        }
+        "course" => {
+            if args.is_empty() {
+                return Err(String::from("缺少课程子命令（add、list 或 remove）"));
+            }
+            match args.remove(0).as_str() {
+                "add" => {
+                    let code = required_option(&mut args, "--code")?;
+                    let name = required_option(&mut args, "--name")?;
+                    let credits = required_option(&mut args, "--credits")?;
+                    let credits = credits.parse().map_err(|_| format!("无效学分: {}", credits))?;
+                    let max_score = match take_option(&mut args, "--max-score")? {
+                        Some(text) => text.parse().map_err(|_| format!("无效满分: {}", text))?,
+                        None => DEFAULT_MAX_SCORE,
+                    };
+                    let mut categories = Vec::new();
+                    while let Some(text) = take_option(&mut args, "--category")? {
+                        categories.push(Category::parse(&text)?);
+                    }
+                    let mut course = Course::new(&code, &name, credits, max_score)?.with_categories(categories)?;
+                    course.pass_fail = take_flag(&mut args, "--pass-fail");
+                    Command::AddCourse { course }
+                }
+                "list" => Command::ListCourses,
+                "remove" => {
+                    let confirmed = take_flag(&mut args, "--yes");
+                    if args.is_empty() {
+                        return Err(String::from("缺少课程代码"));
+                    }
+                    let code = args.remove(0);
+                    if !confirmed {
+                        return Err(format!("删除课程 {} 需要加上 --yes 确认", code));
+                    }
+                    Command::RemoveCourse { code }
+                }
+                other => return Err(format!("未知的课程子命令: {}", other)),
+            }
+        }
+        "enroll" => {
+            let course = required_option(&mut args, "--course")?;
+            let id = take_id(&mut args)?;
+            Command::Enroll { id, course }
+        }
+        "grade" => {
+            // --subject 是旧的写法
+            let course = match take_option(&mut args, "--subject")? {
+                Some(course) => course,
+                None => required_option(&mut args, "--course")?,
+            };
+            let score = import::parse_score(&required_option(&mut args, "--score")?)?;
+            let reason = take_option(&mut args, "--reason")?.unwrap_or_default();
+            let id = take_id(&mut args)?;
+            Command::Grade { id, course, score, reason }
+        }
+        "grade-history" => {
+            let id = take_id(&mut args)?;
+            if args.is_empty() {
+                return Err(String::from("缺少课程代码或名称"));
+            }
+            Command::GradeHistory { id, course: args.remove(0) }
+        }
+        "assess" => {
+            let course = required_option(&mut args, "--course")?;
+            let name = required_option(&mut args, "--name")?;
+            let category = required_option(&mut args, "--category")?;
+            let score = import::parse_score(&required_option(&mut args, "--score")?)?;
+            let max_score = match take_option(&mut args, "--out-of")? {
+                Some(text) => text.parse().map_err(|_| format!("无效满分: {}", text))?,
+                None => DEFAULT_MAX_SCORE,
+            };
+            let days_late = match take_option(&mut args, "--late")? {
+                Some(text) => text.parse().map_err(|_| format!("无效迟交天数: {}", text))?,
+                None => 0,
+            };
+            let id = take_id(&mut args)?;
+            let assessment = Assessment::new(&name, &category, score, max_score, days_late)?;
+            Command::Assess { id, course, assessment }
+        }
+        "list" => {
+            let filter = match take_option(&mut args, "--where")? {
+                Some(text) => Some(Filter::parse(&text)?),
+                None => None,
+            };
+            let sort = match take_option(&mut args, "--sort")? {
+                Some(text) => SortField::parse_list(&text)?,
+                None => Vec::new(),
+            };
+            let offset = match take_option(&mut args, "--offset")? {
+                Some(text) => text.parse().map_err(|_| format!("无效的跳过数量: {}", text))?,
+                None => 0,
+            };
+            let limit = match take_option(&mut args, "--limit")? {
+                Some(text) => Some(text.parse().map_err(|_| format!("无效的显示数量: {}", text))?),
+                None => None,
+            };
+            let format = match take_option(&mut args, "--format")?.as_deref() {
+                None | Some("table") => ListFormat::Table,
+                Some("csv") => ListFormat::Csv,
+                Some(other) => return Err(format!("未知的输出格式: {}（可选 table、csv）", other)),
+            };
+            // 换算表名称要等读取配置文件后才能检查
+            let scale = take_option(&mut args, "--scale")?;
+            Command::List { filter, sort, offset, limit, format, scale }
+        }
+        "scales" => Command::Scales,
+        "stats" => {
+            let course = if args.is_empty() { None } else { Some(args.remove(0)) };
+            Command::Stats { course }
+        }
+        "rank" => {
+            let course = take_option(&mut args, "--course")?;
+            let class = take_class(&mut args)?;
+            let style = if take_flag(&mut args, "--dense") { RankStyle::Dense } else { RankStyle::Competition };
+            let top = take_count(&mut args, "--top")?;
+            let bottom = take_count(&mut args, "--bottom")?;
+            let limit = match (top, bottom) {
+                (Some(_), Some(_)) => return Err(String::from("--top 和 --bottom 不能同时使用")),
+                (Some(count), None) => Some(Limit::Top(count)),
+                (None, Some(count)) => Some(Limit::Bottom(count)),
+                (None, None) => None,
+            };
+            Command::Rank { course, class, style, limit }
+        }
+        "report" => {
+            let format = match take_option(&mut args, "--format")? {
+                Some(name) => ReportFormat::parse(&name)?,
+                None => ReportFormat::Html,
+            };
+            let template = take_option(&mut args, "--template")?.map(PathBuf::from);
+            let target = if take_flag(&mut args, "--all") {
+                if args.iter().any(|arg| arg == "--output") {
+                    return Err(String::from("--all 生成多个文件，请用 --dir 指定目录"));
+                }
+                ReportTarget::All { dir: PathBuf::from(required_option(&mut args, "--dir")?) }
+            } else {
+                let output = take_option(&mut args, "--output")?.map(PathBuf::from);
+                ReportTarget::Student { id: take_id(&mut args)?, output }
+            };
+            Command::Report { target, format, template }
+        }
+        "remove" => {
+            let confirmed = take_flag(&mut args, "--yes");
+            let id = take_id(&mut args)?;
+            // 脚本中没有办法交互确认，所以删除必须显式加上 --yes
+            if !confirmed {
+                return Err(format!("删除学生 {} 需要加上 --yes 确认", id));
+            }
+            Command::Remove { id }
+        }
+        "import" => {
+            if args.is_empty() {
+                return Err(String::from("缺少要导入的 CSV 文件"));
+            }
+            Command::Import { path: PathBuf::from(args.remove(0)) }
+        }
+        "verify-log" => {
+            let path = if args.is_empty() { None } else { Some(PathBuf::from(args.remove(0))) };
+            Command::VerifyLog { path }
+        }
+        "serve" => {
+            let port = match take_option(&mut args, "--port")? {
+                Some(text) => text.parse().map_err(|_| format!("无效端口: {}", text))?,
+                None => server::DEFAULT_PORT,
+            };
+            Command::Serve { port }
+        }
+        _ => return Err(format!("未知命令: {}", name)),
+    };
+
+    if !args.is_empty() {
+        return Err(format!("无法识别的参数: {}", args.join(" ")));
+    }
+    Ok(command)
+}
+
+// 取出 "--key value" 形式的选项
+fn take_option(args: &mut Vec<String>, key: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == key) {
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("{} 需要一个值", key)),
+        None => Ok(None),
+    }
+}
+
+fn required_option(args: &mut Vec<String>, key: &str) -> Result<String, String> {
+    take_option(args, key)?.ok_or_else(|| format!("缺少参数 {}", key))
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+// 去掉首尾空白的班级名称，不能为空
+fn parse_class(text: &str) -> Result<String, String> {
+    let class = text.trim();
+    if class.is_empty() {
+        return Err(String::from("班级不能为空"));
+    }
+    Ok(class.to_string())
+}
+
+fn take_class(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    take_option(args, "--class")?.map(|text| parse_class(&text)).transpose()
+}
+
+// --top、--bottom 的人数
+fn take_count(args: &mut Vec<String>, key: &str) -> Result<Option<usize>, String> {
+    match take_option(args, key)? {
+        Some(text) => match text.parse() {
+            Ok(count) if count > 0 => Ok(Some(count)),
+            _ => Err(format!("{} 需要一个正整数: {}", key, text)),
+        },
+        None => Ok(None),
+    }
+}
+
+// 取出第一个不是选项的参数作为学生 ID
+fn take_id(args: &mut Vec<String>) -> Result<u32, String> {
+    let index = args
+        .iter()
+        .position(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| String::from("缺少学生ID"))?;
+    let id = args.remove(index);
+    id.parse().map_err(|_| format!("无效ID: {}", id))
+}
+
+// 执行命令用到的设置：计算 GPA 的换算表、记录成绩修改时间的时钟和正在写入的审计日志
+pub struct Context {
+    pub grading: GradingConfig,
+    pub clock: Box<dyn Clock>,
+    // 内存后端不记录审计日志
+    pub audit_log: Option<PathBuf>,
+}
+
+impl Context {
+    // 使用系统时间
+    pub fn new(grading: GradingConfig) -> Context {
+        Context {
+            grading,
+            clock: Box::new(SystemClock),
+            audit_log: None,
+        }
+    }
+}
+
+// 执行一个非交互式命令，结果写到 out
+pub fn run(
+    command: &Command,
+    repository: &mut dyn StudentRepository,
+    context: &Context,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let grading = &context.grading;
+    match command {
+        Command::Interactive => return Err(String::from("interactive 命令需要在菜单程序中运行")),
+        Command::Serve { .. } => return Err(String::from("serve 命令需要在主程序中运行")),
+        Command::Tui => return Err(String::from("tui 命令需要在主程序中运行")),
+        Command::Help => write!(out, "{}", USAGE),
+        Command::Add { name, age, class } => {
+            let id = repository.insert(name.clone(), *age)?;
+            if class.is_some() {
+                repository.modify(id, &mut |student| {
+                    student.class = class.clone();
+                    Ok(())
+                })?;
+            }
+            // 只输出 ID，脚本可以直接使用
+            write!(out, "{}", id)
+        }
+        Command::AddCourse { course } => {
+            repository.insert_course(course.clone())?;
+            write!(out, "已登记课程 {} {}", course.code, course.name)
+        }
+        Command::SetClass { id, class } => {
+            repository.modify(*id, &mut |student| {
+                student.class = class.clone();
+                Ok(())
+            })?;
+            match class {
+                Some(class) => write!(out, "学生ID {} 的班级已设置为 {}", id, class),
+                None => write!(out, "学生ID {} 已取消分班", id),
+            }
+        }
+        Command::ListCourses => write_courses(&repository.list_courses()?, out),
+        Command::RemoveCourse { code } => {
+            if !repository.remove_course(code)? {
+                return Err(format!("课程 {} 不存在", code));
+            }
+            write!(out, "课程 {} 已删除", code.trim().to_uppercase())
+        }
+        Command::Enroll { id, course } => {
+            let course = find_course(repository, course)?;
+            repository.modify(*id, &mut |student| {
+                if !student.enroll(course.code.clone()) {
+                    return Err(format!("学生 {} 已经选修了课程 {}", id, course.code));
+                }
+                Ok(())
+            })?;
+            write!(out, "学生ID {} 已选修课程 {} {}", id, course.code, course.name)
+        }
+        Command::Grade { id, course, score, reason } => {
+            let course = find_course(repository, course)?;
+            repository.modify(*id, &mut |student| student.record_grade(&course, *score, reason, context.clock.as_ref()))?;
+            write!(out, "已为学生ID {} 添加课程 {} 的成绩: {}", id, course.name, score)
+        }
+        Command::Assess { id, course, assessment } => {
+            let course = find_course(repository, course)?;
+            let student = repository.modify(*id, &mut |student| {
+                student.record_assessment(&course, assessment.clone(), context.clock.as_ref())
+            })?;
+            let grade = match student.grades.get(&course.code) {
+                Some(grade) => format!("，课程成绩: {:.2}", grade),
+                None => String::new(),
+            };
+            write!(out, "已为学生ID {} 记录课程 {} 的 {}{}", id, course.name, assessment.name, grade)
+        }
+        Command::GradeHistory { id, course } => {
+            let course = find_course(repository, course)?;
+            let student = find_student(repository, *id)?;
+            write_history(&student, &course, out)
+        }
+        Command::List { filter, sort, offset, limit, format, scale } => {
+            let grading = match scale {
+                Some(name) => grading.clone().with_default_scale(name)?,
+                None => grading.clone(),
+            };
+            let courses = repository.list_courses()?;
+            let filter = match filter {
+                Some(filter) => Some(filter.clone().resolve(&courses)?),
+                None => None,
+            };
+            let mut rows: Vec<Row> = repository
+                .list()?
+                .into_iter()
+                .map(|student| Row {
+                    average: grading::weighted_average(&student, &courses),
+                    gpa: grading.gpa(&student, &courses),
+                    student,
+                })
+                .filter(|row| filter.as_ref().is_none_or(|filter| filter.matches(&row.student, row.average, row.gpa)))
+                .collect();
+            sort_rows(&mut rows, sort);
+            let total = rows.len();
+            let page: Vec<Row> = rows.into_iter().skip(*offset).take(limit.unwrap_or(usize::MAX)).collect();
+            match format {
+                ListFormat::Table if filter.is_some() || *offset > 0 || limit.is_some() => {
+                    write_page(&page, total, *offset, &grading, out)
+                }
+                ListFormat::Table => write_table(&page, &grading, out),
+                ListFormat::Csv => write_csv(&page, out),
+            }
+        }
+        Command::Scales => write_scales(grading, out),
+        Command::Stats { course } => {
+            let students = repository.list()?;
+            match course {
+                Some(course) => write_course_stats(&find_course(repository, course)?, &students, grading, true, out),
+                None => {
+                    let courses = repository.list_courses()?;
+                    if courses.is_empty() {
+                        write!(out, "尚未登记课程")
+                    } else {
+                        courses.iter().enumerate().try_for_each(|(index, course)| {
+                            if index > 0 {
+                                write!(out, "\n\n")?;
+                            }
+                            write_course_stats(course, &students, grading, false, out)
+                        })
+                    }
+                }
+            }
+        }
+        Command::Rank { course, class, style, limit } => {
+            let courses = repository.list_courses()?;
+            let (basis, title) = match course {
+                Some(course) => {
+                    let course = find_course(repository, course)?;
+                    (Basis::Course(course.code.clone()), format!("{} {} 成绩排名", course.code, course.name))
+                }
+                None => (Basis::Overall, String::from("平均分排名")),
+            };
+            let students = repository.list()?;
+            if let Some(class) = class {
+                if !students.iter().any(|student| student.class.as_ref() == Some(class)) {
+                    return Err(format!("班级 {} 没有学生", class));
+                }
+            }
+            let ranking = Ranking::compute(&students, &courses, &basis, class.as_deref(), *style);
+            write_ranking(&title, class.as_deref(), &ranking, *limit, out)
+        }
+        Command::Report { target, format, template } => {
+            let template = match template {
+                Some(path) => Template::load(*format, path)?,
+                None => Template::builtin(*format),
+            };
+            let students = repository.list()?;
+            let courses = repository.list_courses()?;
+            let render = |student: &Student| {
+                template.render(&ReportCard::build(student, &students, &courses, grading, context.clock.as_ref()))
+            };
+            match target {
+                ReportTarget::Student { id, output } => {
+                    let student = find_student(repository, *id)?;
+                    match output {
+                        Some(path) => {
+                            write_file(path, &render(&student))?;
+                            write!(out, "已生成 {} 的成绩单: {}", student.name, path.display())
+                        }
+                        None => write!(out, "{}", render(&student).trim_end()),
+                    }
+                }
+                ReportTarget::All { dir } => {
+                    if students.is_empty() {
+                        return Err(String::from("系统中尚无学生记录"));
+                    }
+                    fs::create_dir_all(dir).map_err(|e| format!("无法创建目录 {}: {}", dir.display(), e))?;
+                    for student in &students {
+                        write_file(&dir.join(report::file_name(student, *format)), &render(student))?;
+                    }
+                    write!(out, "已为 {} 名学生生成成绩单，保存在 {}", students.len(), dir.display())
+                }
+            }
+        }
+        Command::Remove { id } => {
+            if !repository.remove(*id)? {
+                return Err(format!("未找到ID为 {} 的学生", id));
+            }
+            write!(out, "学生ID {} 已成功删除", id)
+        }
+        Command::Import { path } => {
+            let text = fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+            let report = import_csv(repository, &text, context.clock.as_ref()).map_err(|e| format!("{}: {}", path.display(), e))?;
+            writeln!(out, "{}", report).map_err(|e| format!("无法写出结果: {}", e))?;
+            // 报告已经写出，被拒绝的行同时通过退出码告诉脚本
+            if !report.rejected.is_empty() {
+                return Err(format!("{} 行被拒绝，没有导入任何记录", report.rejected.len()));
+            }
+            return Ok(());
+        }
+        Command::VerifyLog { path } => {
+            let path = path
+                .as_ref()
+                .or(context.audit_log.as_ref())
+                .ok_or_else(|| String::from("内存后端不记录审计日志，请指定日志文件"))?;
+            let count = audit::verify_against(path, repository).map_err(|e| format!("{}: {}", path.display(), e))?;
+            write!(out, "审计日志 {} 完整：{} 条记录，重放结果与当前数据一致", path.display(), count)
+        }
+    }
+    .and_then(|_| writeln!(out))
+    .map_err(|e| format!("无法写出结果: {}", e))
+}
+
+fn write_file(path: &Path, text: &str) -> Result<(), String> {
+    fs::write(path, text).map_err(|e| format!("无法写入 {}: {}", path.display(), e))
+}
+
+fn find_student(repository: &dyn StudentRepository, id: u32) -> Result<Student, String> {
+    repository.get(id)?.ok_or_else(|| format!("未找到ID为 {} 的学生", id))
+}
+
+fn find_course(repository: &dyn StudentRepository, text: &str) -> Result<Course, String> {
+    repository
+        .find_course(text)?
+        .ok_or_else(|| format!("课程 {} 没有登记，可以用 course add 登记", text.trim()))
+}
+
+// list 输出的一行：学生和按学分加权的平均分、GPA，没有计分课程的成绩时为 None
+struct Row {
+    student: Student,
+    average: Option<f32>,
+    gpa: Option<f32>,
+}
+
+// 按字段依次比较；排序是稳定的，所有字段都相同的学生保持 ID 顺序，没有成绩的学生总是排在最后
+fn sort_rows(rows: &mut [Row], fields: &[SortField]) {
+    rows.sort_by(|a, b| {
+        fields
+            .iter()
+            .fold(Ordering::Equal, |ordering, field| ordering.then_with(|| compare_rows(a, b, *field)))
+    });
+}
+
+fn compare_rows(a: &Row, b: &Row, field: SortField) -> Ordering {
+    let ordering = match field.key {
+        SortKey::Id => a.student.id.cmp(&b.student.id),
+        SortKey::Name => a.student.name.cmp(&b.student.name),
+        SortKey::Age => a.student.age.cmp(&b.student.age),
+        SortKey::Average => return compare_optional(a.average, b.average, field.descending),
+        SortKey::Gpa => return compare_optional(a.gpa, b.gpa, field.descending),
+    };
+    if field.descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn compare_optional(a: Option<f32>, b: Option<f32>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.total_cmp(&a),
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// 保留两位小数，没有数值时为 empty
+fn format_optional(value: Option<f32>, empty: &str) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => empty.to_string(),
+    }
+}
+
+fn write_table(rows: &[Row], grading: &GradingConfig, out: &mut dyn Write) -> std::io::Result<()> {
+    if rows.is_empty() {
+        return write!(out, "系统中尚无学生记录");
+    }
+    let gpa_title = format!("GPA（{}）", grading.default().name);
+    writeln!(out, "{:<5} | {:<15} | {:<5} | {:<10} | {}", "ID", "姓名", "年龄", "平均分", gpa_title)?;
+    writeln!(out, "{:-^60}", "")?;
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            format!(
+                "{:<5} | {:<15} | {:<5} | {:<10} | {}",
+                row.student.id,
+                row.student.name,
+                row.student.age,
+                format_optional(row.average, "-"),
+                format_optional(row.gpa, "-")
+            )
+        })
+        .collect();
+    write!(out, "{}", lines.join("\n"))
+}
+
+fn write_courses(courses: &[Course], out: &mut dyn Write) -> std::io::Result<()> {
+    if courses.is_empty() {
+        return write!(out, "尚未登记课程");
+    }
+    writeln!(out, "{:<10} | {:<15} | {:<5} | {:<5} | 计分方式", "代码", "名称", "学分", "满分")?;
+    writeln!(out, "{:-^60}", "")?;
+    let rows: Vec<String> = courses
+        .iter()
+        .map(|course| {
+            format!(
+                "{:<10} | {:<15} | {:<5} | {:<5} | {}",
+                course.code,
+                course.name,
+                course.credits,
+                course.max_score,
+                if course.pass_fail { "通过制" } else { "分数" }
+            )
+        })
+        .zip(courses)
+        .map(|(row, course)| {
+            let categories: Vec<String> = course.categories.iter().map(|category| format!("\n  {}", category)).collect();
+            row + &categories.concat()
+        })
+        .collect();
+    write!(out, "{}", rows.join("\n"))
+}
+
+// CSV 列：id,name,age,average,gpa，保留两位小数，没有成绩时留空
+// 筛选或分页后的表格，最后一行说明显示的是第几名到第几名
+fn write_page(rows: &[Row], total: usize, offset: usize, grading: &GradingConfig, out: &mut dyn Write) -> std::io::Result<()> {
+    if total == 0 {
+        return write!(out, "没有符合条件的学生");
+    }
+    if rows.is_empty() {
+        return write!(out, "共 {} 名学生符合条件，跳过 {} 名后没有更多学生", total, offset);
+    }
+    write_table(rows, grading, out)?;
+    write!(out, "\n第 {}-{} 名，共 {} 名学生符合条件", offset + 1, offset + rows.len(), total)
+}
+
+fn write_csv(rows: &[Row], out: &mut dyn Write) -> std::io::Result<()> {
+    write!(out, "id,name,age,average,gpa")?;
+    for row in rows {
+        write!(
+            out,
+            "\n{},{},{},{},{}",
+            row.student.id,
+            csv::escape(&row.student.name),
+            row.student.age,
+            format_optional(row.average, ""),
+            format_optional(row.gpa, "")
+        )?;
+    }
+    Ok(())
+}
+
+// 课程的统计和分数段柱状图，with_ranks 为 true 时按成绩从高到低列出每个学生的百分位
+fn write_course_stats(
+    course: &Course,
+    students: &[Student],
+    grading: &GradingConfig,
+    with_ranks: bool,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    write!(out, "{} {}（满分 {}）", course.code, course.name, course.max_score)?;
+    let Some(stats) = CourseStats::compute(course, students, grading) else {
+        return write!(out, "\n还没有成绩");
+    };
+    writeln!(out)?;
+    writeln!(out, "人数: {}  平均分: {:.2}  标准差: {:.2}", stats.count, stats.mean, stats.std_dev)?;
+    writeln!(
+        out,
+        "最低分: {:.2}  下四分位: {:.2}  中位数: {:.2}  上四分位: {:.2}  最高分: {:.2}",
+        stats.min, stats.q1, stats.median, stats.q3, stats.max
+    )?;
+    writeln!(out, "及格率: {:.1}%（及格线为满分的 {}%）", stats.pass_rate, grading.pass_percent)?;
+    write!(out, "分数段（百分制）:")?;
+    let max_count = stats.histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+    for bucket in &stats.histogram {
+        write!(out, "\n  {:<10} | {:<30} {}", bucket.label, stats::bar(bucket.count, max_count, 30), bucket.count)?;
+    }
+    if !with_ranks {
+        return Ok(());
+    }
+
+    let scores = stats::scores(&course.code, students);
+    let mut graded: Vec<(&Student, f32)> = students
+        .iter()
+        .filter_map(|student| student.current_grade(&course.code).map(|score| (student, score)))
+        .collect();
+    graded.sort_by(|a, b| b.1.total_cmp(&a.1));
+    write!(out, "\n\n{:<5} | {:<15} | {:<8} | 百分位", "ID", "姓名", "成绩")?;
+    write!(out, "\n{:-^50}", "")?;
+    for (student, score) in graded {
+        write!(
+            out,
+            "\n{:<5} | {:<15} | {:<8.2} | {:.1}",
+            student.id,
+            student.name,
+            score,
+            stats::percentile_rank(&scores, score)
+        )?;
+    }
+    Ok(())
+}
+
+// 排名表，最后说明没有成绩、未参与排名的学生
+fn write_ranking(
+    title: &str,
+    class: Option<&str>,
+    ranking: &Ranking,
+    limit: Option<Limit>,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match class {
+        Some(class) => write!(out, "{}（{}）", title, class)?,
+        None => write!(out, "{}", title)?,
+    }
+    match limit {
+        Some(Limit::Top(count)) => write!(out, "，前 {} 名", count)?,
+        Some(Limit::Bottom(count)) => write!(out, "，后 {} 名", count)?,
+        None => {}
+    }
+    if ranking.entries.is_empty() {
+        write!(out, "\n没有可以排名的成绩")?;
+    } else {
+        write!(out, "\n{:<5} | {:<5} | {:<15} | {:<10} | 分数", "名次", "ID", "姓名", "班级")?;
+        write!(out, "\n{:-^60}", "")?;
+        let entries = match limit {
+            Some(limit) => ranking.limit(limit),
+            None => &ranking.entries,
+        };
+        for entry in entries {
+            write!(
+                out,
+                "\n{:<5} | {:<5} | {:<15} | {:<10} | {:.2}",
+                entry.rank,
+                entry.student.id,
+                entry.student.name,
+                entry.student.class.as_deref().unwrap_or("-"),
+                entry.score
+            )?;
+        }
+    }
+    if !ranking.ungraded.is_empty() {
+        let names: Vec<String> = ranking
+            .ungraded
+            .iter()
+            .map(|student| format!("{}（ID {}）", student.name, student.id))
+            .collect();
+        write!(out, "\n{} 名学生没有成绩，未参与排名: {}", ranking.ungraded.len(), names.join("、"))?;
+    }
+    Ok(())
+}
+
+// 当前成绩和按时间顺序排列的修改记录
+fn write_history(student: &Student, course: &Course, out: &mut dyn Write) -> std::io::Result<()> {
+    let score = |score: Option<f32>| match score {
+        Some(score) => format!("{:.2}", score),
+        None => String::from("-"),
+    };
+    writeln!(
+        out,
+        "{} 的课程 {} {} 当前成绩: {}",
+        student.name,
+        course.code,
+        course.name,
+        score(student.current_grade(&course.code))
+    )?;
+    let changes = student.grade_history(&course.code);
+    if changes.is_empty() {
+        return write!(out, "没有修改记录");
+    }
+    writeln!(out, "{:<23} | {:<8} | {:<8} | 原因", "时间", "原成绩", "新成绩")?;
+    writeln!(out, "{:-^60}", "")?;
+    let lines: Vec<String> = changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{:<23} | {:<8} | {:<8} | {}",
+                history::format_timestamp(change.time),
+                score(change.old),
+                score(change.new),
+                change.reason
+            )
+        })
+        .collect();
+    write!(out, "{}", lines.join("\n"))
+}
+
+// 每个换算表一段，默认换算表标上（默认）
+fn write_scales(grading: &GradingConfig, out: &mut dyn Write) -> std::io::Result<()> {
+    let mut sections = Vec::new();
+    for scale in &grading.scales {
+        let mut lines = vec![if scale.name == grading.default_scale {
+            format!("{} 制（默认）", scale.name)
+        } else {
+            format!("{} 制", scale.name)
+        }];
+        for band in &scale.bands {
+            lines.push(format!("  >= {:<5} {:<3} {}", band.min, band.letter, band.points));
+        }
+        sections.push(lines.join("\n"));
+    }
+    sections.push(format!("通过制课程及格线: {}", grading.pass_percent));
+    write!(out, "{}", sections.join("\n\n"))
+}
+
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedRepository;
+    use crate::history::ManualClock;
+    use std::sync::Arc;
+    use crate::student::StudentManagement;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    // 时钟固定在 2024-03-01 08:30:00 UTC
+    fn context() -> Context {
+        Context {
+            grading: GradingConfig::builtin(),
+            clock: Box::new(ManualClock::new(1_709_281_800)),
+            audit_log: None,
+        }
+    }
+
+    fn execute(repository: &mut StudentManagement, line: &str) -> Result<String, String> {
+        let cli = parse(&args(line))?;
+        let mut out = Vec::new();
+        run(&cli.command, repository, &context(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap().command, Command::Interactive);
+        assert_eq!(parse(&args("serve")).unwrap().command, Command::Serve { port: 8080 });
+        assert_eq!(parse(&args("serve --port 0")).unwrap().command, Command::Serve { port: 0 });
+        assert_eq!(parse(&args("tui")).unwrap().command, Command::Tui);
+        assert_eq!(
+            parse(&args("add --age 20 --name 张三")).unwrap().command,
+            Command::Add { name: String::from("张三"), age: 20, class: None }
+        );
+        assert_eq!(
+            parse(&args("grade 3 --subject 数学 --score 92")).unwrap().command,
+            Command::Grade { id: 3, course: String::from("数学"), score: 92.0, reason: String::new() }
+        );
+        assert_eq!(
+            parse(&args("grade 3 --course math101 --score 92")).unwrap().command,
+            Command::Grade { id: 3, course: String::from("math101"), score: 92.0, reason: String::new() }
+        );
+        assert_eq!(
+            parse(&args("course add --code eng --name 英语 --credits 2 --max-score 150")).unwrap().command,
+            Command::AddCourse { course: Course::new("ENG", "英语", 2.0, 150.0).unwrap() }
+        );
+        assert_eq!(
+            parse(&args("enroll 3 --course ENG")).unwrap().command,
+            Command::Enroll { id: 3, course: String::from("ENG") }
+        );
+        assert_eq!(parse(&args("course list")).unwrap().command, Command::ListCourses);
+        assert_eq!(
+            parse(&args("list --sort avg --format csv")).unwrap().command,
+            Command::List {
+                filter: None,
+                sort: vec![SortField { key: SortKey::Average, descending: true }],
+                offset: 0,
+                limit: None,
+                format: ListFormat::Csv,
+                scale: None
+            }
+        );
+        assert_eq!(
+            parse(&args("list --scale 4.3 --sort gpa")).unwrap().command,
+            Command::List {
+                filter: None,
+                sort: vec![SortField { key: SortKey::Gpa, descending: true }],
+                offset: 0,
+                limit: None,
+                format: ListFormat::Table,
+                scale: Some(String::from("4.3"))
+            }
+        );
+        assert_eq!(parse(&args("remove --yes 3")).unwrap().command, Command::Remove { id: 3 });
+
+        let cli = parse(&args("list --storage sqlite")).unwrap();
+        assert_eq!(cli.backend, Backend::Sqlite);
+        assert_eq!(parse(&args("list")).unwrap().backend, Backend::Json);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&args("remove 3")).unwrap_err().contains("--yes"));
+        assert!(parse(&args("add --name 张三")).unwrap_err().contains("--age"));
+        assert!(parse(&args("add --name 张三 --age 300")).is_err());
+        assert!(parse(&args("grade 3 --course 数学 --score abc")).is_err());
+        assert!(parse(&args("grade --course 数学 --score 90")).unwrap_err().contains("ID"));
+        assert!(parse(&args("course add --code X --name 美术 --credits 0")).is_err());
+        assert!(parse(&args("course remove ART")).unwrap_err().contains("--yes"));
+        assert!(parse(&args("course")).is_err());
+        assert!(parse(&args("list --sort height")).is_err());
+        assert!(parse(&args("list extra")).unwrap_err().contains("extra"));
+        assert!(parse(&args("list --format")).unwrap_err().contains("需要一个值"));
+        assert!(parse(&args("frobnicate")).is_err());
+    }
+
+    #[test]
+    fn test_run_commands() {
+        let mut repository = StudentManagement::new();
+        assert_eq!(execute(&mut repository, "add --name 张三 --age 20").unwrap(), "1\n");
+        assert_eq!(execute(&mut repository, "add --name Smith,J --age 22").unwrap(), "2\n");
+        execute(&mut repository, "course add --code math101 --name 数学 --credits 4").unwrap();
+        assert!(execute(&mut repository, "grade 1 --course 数学 --score 80").unwrap_err().contains("请先选课"));
+        execute(&mut repository, "enroll 1 --course 数学").unwrap();
+        execute(&mut repository, "enroll 2 --course MATH101").unwrap();
+        assert!(execute(&mut repository, "enroll 2 --course math101").unwrap_err().contains("已经选修"));
+        assert!(execute(&mut repository, "enroll 2 --course Math").unwrap_err().contains("没有登记"));
+        assert!(execute(&mut repository, "grade 1 --course 数学 --score 101").is_err());
+        execute(&mut repository, "grade 1 --course 数学 --score 80").unwrap();
+        execute(&mut repository, "grade 2 --subject math101 --score 95").unwrap();
+        assert!(execute(&mut repository, "course list").unwrap().contains("MATH101    | 数学"));
+
+        assert_eq!(
+            execute(&mut repository, "list --sort avg --format csv").unwrap(),
+            "id,name,age,average,gpa\n2,\"Smith,J\",22,95.00,4.00\n1,张三,20,80.00,3.00\n"
+        );
+        assert!(execute(&mut repository, "grade 9 --course 数学 --score 80").unwrap_err().contains("9"));
+        assert!(execute(&mut repository, "course remove math101 --yes").unwrap_err().contains("2 名学生"));
+
+        execute(&mut repository, "remove 1 --yes").unwrap();
+        assert!(execute(&mut repository, "remove 1 --yes").is_err());
+        assert_eq!(
+            execute(&mut repository, "list --format csv").unwrap(),
+            "id,name,age,average,gpa\n2,\"Smith,J\",22,95.00,4.00\n"
+        );
+    }
+
+    #[test]
+    fn test_weighted_list() {
+        let mut repository = StudentManagement::new();
+        execute(&mut repository, "course add --code MATH --name 数学 --credits 4").unwrap();
+        execute(&mut repository, "course add --code ENG --name 英语 --credits 2 --max-score 150").unwrap();
+        execute(&mut repository, "course add --code PE --name 体育 --credits 1 --pass-fail").unwrap();
+        assert!(execute(&mut repository, "course list").unwrap().contains("| 1     | 100   | 通过制"));
+        for line in ["add --name 张三 --age 20", "add --name 李四 --age 21", "add --name 王五 --age 22"] {
+            execute(&mut repository, line).unwrap();
+        }
+        for code in ["MATH", "ENG", "PE"] {
+            execute(&mut repository, &format!("enroll 1 --course {}", code)).unwrap();
+            execute(&mut repository, &format!("enroll 2 --course {}", code)).unwrap();
+        }
+        execute(&mut repository, "enroll 3 --course PE").unwrap();
+        // 张三：数学 96（4 学分），英语 120/150 = 80（2 学分），体育不计入
+        execute(&mut repository, "grade 1 --course MATH --score 96").unwrap();
+        execute(&mut repository, "grade 1 --course ENG --score 120").unwrap();
+        execute(&mut repository, "grade 1 --course PE --score 10").unwrap();
+        // 李四：数学 85，英语 150
+        execute(&mut repository, "grade 2 --course MATH --score 85").unwrap();
+        execute(&mut repository, "grade 2 --course ENG --score 150").unwrap();
+        execute(&mut repository, "grade 3 --course PE --score 100").unwrap();
+
+        assert_eq!(
+            execute(&mut repository, "list --sort gpa --format csv").unwrap(),
+            "id,name,age,average,gpa\n1,张三,20,90.67,3.67\n2,李四,21,90.00,3.33\n3,王五,22,,\n"
+        );
+        assert_eq!(
+            execute(&mut repository, "list --sort gpa --format csv --scale 4.3").unwrap(),
+            "id,name,age,average,gpa\n2,李四,21,90.00,3.90\n1,张三,20,90.67,3.87\n3,王五,22,,\n"
+        );
+        assert!(execute(&mut repository, "list --scale 10").unwrap_err().contains("4.3"));
+        assert!(execute(&mut repository, "list").unwrap().contains("GPA（4.0）"));
+        assert!(execute(&mut repository, "scales").unwrap().contains("4.0 制（默认）"));
+    }
+
+    #[test]
+    fn test_assess_command() {
+        assert_eq!(
+            parse(&args("assess 1 --course MATH --name 作业1 --category 作业 --score 18 --out-of 20 --late 1"))
+                .unwrap()
+                .command,
+            Command::Assess {
+                id: 1,
+                course: String::from("MATH"),
+                assessment: Assessment::new("作业1", "作业", 18.0, 20.0, 1).unwrap()
+            }
+        );
+        assert!(parse(&args("assess 1 --course MATH --name 作业1 --category 作业 --score 21 --out-of 20")).is_err());
+        assert!(parse(&args("course add --code X --name 美术 --credits 1 --category 作业=50")).unwrap_err().contains("100"));
+
+        let mut repository = StudentManagement::new();
+        execute(
+            &mut repository,
+            "course add --code MATH --name 数学 --credits 4 --category 作业=30,drop=1,late=10 --category 期中=30 --category 期末=40",
+        )
+        .unwrap();
+        assert!(execute(&mut repository, "course list").unwrap().contains("\n  作业 30%，去掉最低 1 次，迟交每天扣 10 分\n  期中 30%"));
+        execute(&mut repository, "add --name 张三 --age 20").unwrap();
+        execute(&mut repository, "enroll 1 --course MATH").unwrap();
+        assert!(execute(&mut repository, "grade 1 --course MATH --score 90").unwrap_err().contains("assess"));
+
+        execute(&mut repository, "assess 1 --course MATH --name 作业1 --category 作业 --score 20 --out-of 20 --late 1").unwrap();
+        execute(&mut repository, "assess 1 --course MATH --name 作业2 --category 作业 --score 10 --out-of 20").unwrap();
+        execute(&mut repository, "assess 1 --course MATH --name 期中 --category 期中 --score 70").unwrap();
+        assert_eq!(
+            execute(&mut repository, "assess 1 --course MATH --name 期末 --category 期末 --score 80").unwrap(),
+            "已为学生ID 1 记录课程 数学 的 期末，课程成绩: 80.00\n"
+        );
+        assert!(execute(&mut repository, "assess 1 --course MATH --name 测验 --category 测验 --score 8").is_err());
+        assert_eq!(
+            execute(&mut repository, "list --format csv").unwrap(),
+            "id,name,age,average,gpa\n1,张三,20,80.00,3.00\n"
+        );
+    }
+
+    #[test]
+    fn test_grade_history() {
+        assert_eq!(
+            parse(&args("grade 1 --course 数学 --score 90 --reason 复核")).unwrap().command,
+            Command::Grade { id: 1, course: String::from("数学"), score: 90.0, reason: String::from("复核") }
+        );
+        assert_eq!(
+            parse(&args("grade-history 1 数学")).unwrap().command,
+            Command::GradeHistory { id: 1, course: String::from("数学") }
+        );
+        assert!(parse(&args("grade-history 1")).is_err());
+
+        let mut repository = StudentManagement::new();
+        let clock = Arc::new(ManualClock::new(1_709_281_800));
+        let context = Context {
+            grading: GradingConfig::builtin(),
+            clock: Box::new(clock.clone()),
+            audit_log: None,
+        };
+        let mut execute = |line: &str| -> Result<String, String> {
+            let mut out = Vec::new();
+            run(&parse(&args(line))?.command, &mut repository, &context, &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        };
+        execute("add --name 张三 --age 20").unwrap();
+        execute("course add --code MATH --name 数学 --credits 4").unwrap();
+        execute("enroll 1 --course MATH").unwrap();
+        assert!(execute("grade-history 1 数学").unwrap().ends_with("当前成绩: -\n没有修改记录\n"));
+
+        execute("grade 1 --course 数学 --score 58").unwrap();
+        // 分数没有变化时不记录
+        execute("grade 1 --course 数学 --score 58").unwrap();
+        clock.advance(86_400 + 60);
+        execute("grade 1 --course 数学 --score 75 --reason 补考").unwrap();
+        assert_eq!(
+            execute("grade-history 1 MATH").unwrap(),
+            [
+                "张三 的课程 MATH 数学 当前成绩: 75.00",
+                "时间                      | 原成绩      | 新成绩      | 原因",
+                &"-".repeat(60),
+                "2024-03-01 08:30:00 UTC | -        | 58.00    | 录入成绩",
+                "2024-03-02 08:31:00 UTC | 58.00    | 75.00    | 补考\n",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_import_command() {
+        assert_eq!(
+            parse(&args("import grades.csv")).unwrap().command,
+            Command::Import { path: PathBuf::from("grades.csv") }
+        );
+        assert!(parse(&args("import")).is_err());
+
+        let path = std::env::temp_dir().join(format!("student_cli_import_{}.csv", std::process::id()));
+        fs::write(&path, "name,age\n张三,20\n李四,0\n").unwrap();
+        let mut repository = StudentManagement::new();
+        let mut out = Vec::new();
+        let command = Command::Import { path: path.clone() };
+        let error = run(&command, &mut repository, &context(), &mut out).unwrap_err();
+        assert!(error.contains("1 行被拒绝"), "{}", error);
+        assert!(String::from_utf8(out).unwrap().contains("第 3 行: 李四,0"));
+        assert!(repository.list().unwrap().is_empty());
+
+        fs::write(&path, "name,age\n张三,20\n").unwrap();
+        run(&command, &mut repository, &context(), &mut Vec::new()).unwrap();
+        assert_eq!(repository.list().unwrap().len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_log() {
+        assert_eq!(parse(&args("verify-log")).unwrap().command, Command::VerifyLog { path: None });
+        let dir = std::env::temp_dir().join(format!("student_cli_audit_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("students.json.audit.jsonl");
+        let clock = ManualClock::new(1_709_281_800);
+        let mut repository =
+            AuditedRepository::open(Box::new(StudentManagement::new()), &path, "teacher", Box::new(clock)).unwrap();
+        let context = Context {
+            audit_log: Some(path.clone()),
+            ..context()
+        };
+        let mut execute = |line: &str| -> Result<String, String> {
+            let mut out = Vec::new();
+            run(&parse(&args(line))?.command, &mut repository, &context, &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        };
+        execute("add --name 张三 --age 20").unwrap();
+        execute("course add --code MATH --name 数学 --credits 4").unwrap();
+        execute("enroll 1 --course MATH").unwrap();
+        execute("grade 1 --course 数学 --score 90").unwrap();
+        assert!(execute("verify-log").unwrap().contains("完整：4 条记录"));
+
+        // 把日志中的成绩从 90 改成 99
+        let text = fs::read_to_string(&path).unwrap();
+        fs::write(&path, text.replace("\"MATH\":90.0", "\"MATH\":99.0")).unwrap();
+        assert!(execute("verify-log").unwrap_err().contains("第 4 条记录的内容与哈希不符"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut memory = StudentManagement::new();
+        assert!(self::execute(&mut memory, "verify-log").unwrap_err().contains("内存后端"));
+    }
+
+    #[test]
+    fn test_list_filter_sort_and_page() {
+        assert_eq!(
+            SortField::parse_list("-avg, name,+gpa").unwrap(),
+            vec![
+                SortField { key: SortKey::Average, descending: true },
+                SortField { key: SortKey::Name, descending: false },
+                SortField { key: SortKey::Gpa, descending: false },
+            ]
+        );
+        assert!(SortField::parse_list("avg,height").unwrap_err().contains("height"));
+        assert!(parse(&[String::from("list"), String::from("--where"), String::from("age >")]).is_err());
+        assert!(parse(&args("list --limit -1")).is_err());
+
+        let mut repository = StudentManagement::new();
+        execute(&mut repository, "course add --code MATH --name 数学 --credits 4").unwrap();
+        for (name, age, score) in [("赵", 17, Some(50.0)), ("钱", 19, Some(55.0)), ("孙", 20, None), ("李", 18, Some(55.0)), ("周", 22, Some(90.0))] {
+            let id = execute(&mut repository, &format!("add --name {} --age {}", name, age)).unwrap();
+            if let Some(score) = score {
+                execute(&mut repository, &format!("enroll {} --course MATH", id.trim())).unwrap();
+                execute(&mut repository, &format!("grade {} --course MATH --score {}", id.trim(), score)).unwrap();
+            }
+        }
+        // 筛选条件中有空格，不能用 args 按空格拆分
+        let run_list = |repository: &mut StudentManagement, options: &[&str]| -> Result<String, String> {
+            let mut line = vec![String::from("list")];
+            line.extend(options.iter().map(|option| option.to_string()));
+            let mut out = Vec::new();
+            run(&parse(&line)?.command, repository, &context(), &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        };
+        let names = |repository: &mut StudentManagement, options: &[&str]| -> Vec<String> {
+            let mut options = options.to_vec();
+            options.extend(["--format", "csv"]);
+            let output = run_list(repository, &options).unwrap();
+            output.lines().skip(1).map(|line| line.split(',').nth(1).unwrap().to_string()).collect()
+        };
+        assert_eq!(names(&mut repository, &["--where", "age >= 18 and avg < 60 and has(数学)"]), vec!["钱", "李"]);
+        // 平均分从低到高，相同时按姓名，没有平均分的排在最后
+        assert_eq!(names(&mut repository, &["--sort", "+avg,name"]), vec!["赵", "李", "钱", "周", "孙"]);
+        assert_eq!(names(&mut repository, &["--sort", "-avg,-age"]), vec!["周", "钱", "李", "赵", "孙"]);
+        assert_eq!(names(&mut repository, &["--sort", "-age", "--offset", "1", "--limit", "2"]), vec!["孙", "钱"]);
+        assert_eq!(names(&mut repository, &["--offset", "10"]), Vec::<String>::new());
+
+        let table = run_list(&mut repository, &["--where", "not has(数学)", "--limit", "5"]).unwrap();
+        assert!(table.contains("孙") && table.ends_with("第 1-1 名，共 1 名学生符合条件\n"));
+        assert_eq!(run_list(&mut repository, &["--where", "age > 30"]).unwrap(), "没有符合条件的学生\n");
+        assert!(run_list(&mut repository, &["--where", "has(化学)"]).unwrap_err().contains("化学"));
+    }
+
+    #[test]
+    fn test_stats_command() {
+        assert_eq!(parse(&args("stats")).unwrap().command, Command::Stats { course: None });
+        let mut repository = StudentManagement::new();
+        assert_eq!(execute(&mut repository, "stats").unwrap(), "尚未登记课程\n");
+        execute(&mut repository, "course add --code MATH --name 数学 --credits 4").unwrap();
+        execute(&mut repository, "course add --code ART --name 美术 --credits 1").unwrap();
+        for (name, score) in [("张三", 55), ("李四", 95), ("王五", 72), ("赵六", 88)] {
+            let id = execute(&mut repository, &format!("add --name {} --age 20", name)).unwrap();
+            execute(&mut repository, &format!("enroll {} --course MATH", id.trim())).unwrap();
+            execute(&mut repository, &format!("grade {} --course MATH --score {}", id.trim(), score)).unwrap();
+        }
+
+        let all = execute(&mut repository, "stats").unwrap();
+        assert!(all.starts_with("ART 美术（满分 100）\n还没有成绩\n\nMATH 数学"));
+        assert!(all.contains("人数: 4  平均分: 77.50"));
+        assert!(all.contains("中位数: 80.00"));
+        assert!(all.contains("及格率: 75.0%"));
+        assert!(all.contains("  A 90-100   | ############################## 1"));
+        assert!(!all.contains("百分位"));
+
+        let math = execute(&mut repository, "stats 数学").unwrap();
+        let ranks: Vec<&str> = math.lines().skip_while(|line| !line.contains("百分位")).skip(2).collect();
+        assert_eq!(ranks.len(), 4);
+        assert!(ranks[0].starts_with("2     | 李四") && ranks[0].ends_with("| 87.5"));
+        assert!(ranks[3].starts_with("1     | 张三") && ranks[3].ends_with("| 12.5"));
+        assert!(execute(&mut repository, "stats 化学").is_err());
+    }
+
+    #[test]
+    fn test_rank_command() {
+        assert_eq!(
+            parse(&args("rank --class 一班 --dense --top 3")).unwrap().command,
+            Command::Rank {
+                course: None,
+                class: Some(String::from("一班")),
+                style: RankStyle::Dense,
+                limit: Some(Limit::Top(3)),
+            }
+        );
+        assert!(parse(&args("rank --top 3 --bottom 2")).is_err());
+        assert!(parse(&args("rank --top 0")).is_err());
+
+        let mut repository = StudentManagement::new();
+        execute(&mut repository, "course add --code MATH --name 数学 --credits 4").unwrap();
+        for (name, class, score) in [("张三", "一班", 80), ("李四", "一班", 95), ("王五", "二班", 80), ("赵六", "二班", 60)] {
+            let id = execute(&mut repository, &format!("add --name {} --age 20 --class {}", name, class)).unwrap();
+            execute(&mut repository, &format!("enroll {} --course MATH", id.trim())).unwrap();
+            execute(&mut repository, &format!("grade {} --course MATH --score {}", id.trim(), score)).unwrap();
+        }
+        let id = execute(&mut repository, "add --name 孙七 --age 19").unwrap();
+        execute(&mut repository, &format!("class {} 二班", id.trim())).unwrap();
+        assert_eq!(repository.get_student(5).unwrap().class.as_deref(), Some("二班"));
+
+        let ranks = |output: &str| -> Vec<String> {
+            output
+                .lines()
+                .skip(3)
+                .filter(|line| line.contains('|'))
+                .map(|line| line.split('|').take(3).map(str::trim).collect::<Vec<_>>().join(" "))
+                .collect()
+        };
+        let overall = execute(&mut repository, "rank").unwrap();
+        assert!(overall.starts_with("平均分排名
+"));
+        assert_eq!(ranks(&overall), vec!["1 2 李四", "2 1 张三", "2 3 王五", "4 4 赵六"]);
+        // 没有成绩的学生不参与排名，而不是按 0 分排在最后
+        assert!(overall.ends_with("1 名学生没有成绩，未参与排名: 孙七（ID 5）\n"));
+
+        let dense = execute(&mut repository, "rank --course 数学 --dense --bottom 1").unwrap();
+        assert!(dense.starts_with("MATH 数学 成绩排名，后 1 名\n"));
+        assert_eq!(ranks(&dense), vec!["3 4 赵六"]);
+
+        let class = execute(&mut repository, "rank --class 二班").unwrap();
+        assert_eq!(ranks(&class), vec!["1 3 王五", "2 4 赵六"]);
+        assert!(execute(&mut repository, "rank --class 三班").unwrap_err().contains("三班"));
+
+        execute(&mut repository, "class 5 --clear").unwrap();
+        assert_eq!(repository.get_student(5).unwrap().class, None);
+    }
+
+    #[test]
+    fn test_report_command() {
+        assert_eq!(
+            parse(&args("report 3 --format md")).unwrap().command,
+            Command::Report {
+                target: ReportTarget::Student { id: 3, output: None },
+                format: ReportFormat::Markdown,
+                template: None,
+            }
+        );
+        assert!(parse(&args("report --all")).unwrap_err().contains("--dir"));
+        assert!(parse(&args("report 3 --format pdf")).is_err());
+
+        let mut repository = StudentManagement::new();
+        execute(&mut repository, "course add --code MATH --name 数学 --credits 4").unwrap();
+        for (name, score) in [("张三", 80), ("李四", 95)] {
+            let id = execute(&mut repository, &format!("add --name {} --age 20 --class 一班", name)).unwrap();
+            execute(&mut repository, &format!("enroll {} --course MATH", id.trim())).unwrap();
+            execute(&mut repository, &format!("grade {} --course MATH --score {}", id.trim(), score)).unwrap();
+        }
+        let markdown = execute(&mut repository, "report 1 --format md").unwrap();
+        assert!(markdown.starts_with("# 成绩单\n"));
+        assert!(markdown.contains("| MATH | 数学 | 4 | 80.0 / 100 | B |"));
+        assert!(markdown.contains("- 班级排名：第 2 名（共 2 人）"));
+        assert!(execute(&mut repository, "report 9").unwrap_err().contains("9"));
+
+        let dir = std::env::temp_dir().join(format!("student_cli_report_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let reports = dir.join("reports");
+        let output = execute(&mut repository, &format!("report --all --dir {}", reports.display())).unwrap();
+        assert!(output.starts_with("已为 2 名学生生成成绩单"));
+        let html = fs::read_to_string(reports.join("2-李四.html")).unwrap();
+        assert!(html.contains("<title>成绩单 - 李四</title>") && html.contains("第 1 名（共 2 人）"));
+
+        let template = dir.join("school.md");
+        fs::write(&template, "{{name}}，{{average}}").unwrap();
+        let line = format!("report 2 --format md --template {} --output {}", template.display(), dir.join("李四.md").display());
+        execute(&mut repository, &line).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("李四.md")).unwrap(), "李四，95.00");
+        fs::write(&template, "{{school}}").unwrap();
+        assert!(execute(&mut repository, &format!("report 2 --template {}", template.display())).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+// 课程登记表：课程代码、名称、学分和满分
+//
+// 成绩和选课都只记录课程代码，显示时再从登记表中查出课程名称，
+// 这样 "数学"、"数学 " 和 "Math" 不会变成三门不同的课程。
+
+use serde::{Deserialize, Serialize};
+
+use crate::gradebook::{self, Category};
+use crate::student::Student;
+
+pub const DEFAULT_MAX_SCORE: f32 = 100.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Course {
+    // 课程代码，去掉首尾空白并转成大写，例如 MATH101
+    pub code: String,
+    pub name: String,
+    // 学分
+    pub credits: f32,
+    pub max_score: f32,
+    // 通过制课程只记通过或不通过，不计入加权平均分和 GPA
+    #[serde(default)]
+    pub pass_fail: bool,
+    // 评分类别；不为空时课程成绩由作业、考试等评估计算，不能直接记录
+    #[serde(default)]
+    pub categories: Vec<Category>,
+}
+
+impl Course {
+    // 创建课程并检查各项是否合法
+    pub fn new(code: &str, name: &str, credits: f32, max_score: f32) -> Result<Course, String> {
+        let code = normalize_code(code);
+        let name = name.trim().to_string();
+        if code.is_empty() {
+            return Err(String::from("课程代码不能为空"));
+        }
+        if name.is_empty() {
+            return Err(String::from("课程名称不能为空"));
+        }
+        if !(credits.is_finite() && credits > 0.0) {
+            return Err(format!("学分必须大于0: {}", credits));
+        }
+        if !(max_score.is_finite() && max_score > 0.0) {
+            return Err(format!("满分必须大于0: {}", max_score));
+        }
+        Ok(Course {
+            code,
+            name,
+            credits,
+            max_score,
+            pass_fail: false,
+            categories: Vec::new(),
+        })
+    }
+
+    // 设置评分类别，权重合计必须是 100
+    pub fn with_categories(mut self, categories: Vec<Category>) -> Result<Course, String> {
+        gradebook::check_categories(&categories)?;
+        self.categories = categories;
+        Ok(self)
+    }
+
+    pub fn category(&self, name: &str) -> Option<&Category> {
+        self.categories.iter().find(|category| category.name == name.trim())
+    }
+
+    // 有评分类别的课程，成绩只能通过记录评估计算
+    pub fn check_direct_grade(&self) -> Result<(), String> {
+        if self.categories.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "课程 {} 的成绩由作业和考试计算，请记录评估成绩（assess）",
+                self.code
+            ))
+        }
+    }
+
+    // 检查分数是否在 0 到满分之间
+    pub fn check_score(&self, score: f32) -> Result<(), String> {
+        if (0.0..=self.max_score).contains(&score) {
+            Ok(())
+        } else {
+            Err(format!("{} 的分数必须在0到{}之间: {}", self.name, self.max_score, score))
+        }
+    }
+}
+
+// 课程代码不区分大小写，也忽略首尾空白
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+// 按课程代码（不区分大小写）或课程名称查找课程
+pub fn find<'a>(courses: &'a [Course], text: &str) -> Option<&'a Course> {
+    let code = normalize_code(text);
+    courses
+        .iter()
+        .find(|course| course.code == code)
+        .or_else(|| courses.iter().find(|course| course.name == text.trim()))
+}
+
+// 新课程的代码和名称都不能与已有课程重复，按名称查找时才不会有歧义
+pub fn check_new_course(courses: &[Course], course: &Course) -> Result<(), String> {
+    gradebook::check_categories(&course.categories)?;
+    for existing in courses {
+        if existing.code == course.code {
+            return Err(format!("课程代码 {} 已经存在", course.code));
+        }
+        if existing.name == course.name {
+            return Err(format!("课程名称 {} 已被 {} 使用", course.name, existing.code));
+        }
+    }
+    Ok(())
+}
+
+// 还有学生选修的课程不能删除
+pub fn check_unused(code: &str, students: &[Student]) -> Result<(), String> {
+    let count = students.iter().filter(|student| student.is_enrolled(code)).count();
+    if count > 0 {
+        return Err(format!("课程 {} 还有 {} 名学生选修，不能删除", code, count));
+    }
+    Ok(())
+}
+
+// 检查学生记录与课程登记表是否一致：只能选修登记过的课程，只能为选修的课程记录成绩
+pub fn check_student(student: &Student, courses: &[Course]) -> Result<(), String> {
+    for code in &student.enrollments {
+        if !courses.iter().any(|course| &course.code == code) {
+            return Err(format!("课程 {} 不存在", code));
+        }
+    }
+    for (code, score) in &student.grades {
+        if !student.enrollments.contains(code) {
+            return Err(format!("学生 {} 没有选修课程 {}，不能记录成绩", student.id, code));
+        }
+        if let Some(course) = courses.iter().find(|course| &course.code == code) {
+            course.check_score(*score)?;
+        }
+    }
+    for (code, assessments) in &student.assessments {
+        if !student.enrollments.contains(code) {
+            return Err(format!("学生 {} 没有选修课程 {}，不能记录评估成绩", student.id, code));
+        }
+        let course = courses
+            .iter()
+            .find(|course| &course.code == code)
+            .ok_or_else(|| format!("课程 {} 不存在", code))?;
+        for (index, assessment) in assessments.iter().enumerate() {
+            if course.category(&assessment.category).is_none() {
+                return Err(format!("课程 {} 没有评分类别 {}", code, assessment.category));
+            }
+            if assessments[..index].iter().any(|other| other.name == assessment.name) {
+                return Err(format!("课程 {} 的评估 {} 重复", code, assessment.name));
+            }
+        }
+        // 课程成绩必须与评估成绩一致
+        let expected = gradebook::course_percent(&course.categories, assessments)
+            .map(|percent| percent * course.max_score / 100.0);
+        let matches = match (expected, student.grades.get(code)) {
+            (Some(expected), Some(score)) => (expected - score).abs() < 0.01,
+            (None, None) => true,
+            _ => false,
+        };
+        if !matches {
+            return Err(format!("学生 {} 的课程 {} 成绩与评估成绩不一致", student.id, code));
+        }
+    }
+    Ok(())
+}
+
+// 一条成绩：(学生 ID, 课程, 分数)
+pub type GradeRow = (u32, String, f32);
+
+// 旧版本的成绩按输入的课程名称保存，转换时每个不同的名称（去掉首尾空白）登记为一门课程。
+// 旧数据没有学分和满分，使用 1 学分、满分 100；已有分数超过 100 时满分取最高分。
+// 返回的成绩使用课程代码
+pub fn courses_from_subjects(grades: &[GradeRow]) -> Result<(Vec<Course>, Vec<GradeRow>), String> {
+    let mut courses: Vec<Course> = Vec::new();
+    let mut converted = Vec::new();
+    for (id, subject, score) in grades {
+        let code = normalize_code(subject);
+        if code.is_empty() {
+            return Err(format!("学生 {} 有一门没有名称的课程", id));
+        }
+        match courses.iter_mut().find(|course| course.code == code) {
+            Some(course) => course.max_score = course.max_score.max(*score),
+            None => courses.push(Course::new(&code, subject, 1.0, DEFAULT_MAX_SCORE.max(*score))?),
+        }
+        converted.push((*id, code, *score));
+    }
+    courses.sort_by(|a, b| a.code.cmp(&b.code));
+    Ok((courses, converted))
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_course() {
+        let course = Course::new(" math101 ", " 数学 ", 4.0, 100.0).unwrap();
+        assert_eq!((course.code.as_str(), course.name.as_str()), ("MATH101", "数学"));
+        assert!(Course::new(" ", "数学", 4.0, 100.0).is_err());
+        assert!(Course::new("MATH", "", 4.0, 100.0).is_err());
+        assert!(Course::new("MATH", "数学", 0.0, 100.0).is_err());
+        assert!(Course::new("MATH", "数学", 4.0, f32::NAN).is_err());
+        assert!(course.check_score(100.0).is_ok());
+        assert!(course.check_score(100.5).unwrap_err().contains("0到100"));
+    }
+
+    #[test]
+    fn test_find_and_duplicates() {
+        let courses = vec![
+            Course::new("MATH101", "数学", 4.0, 100.0).unwrap(),
+            Course::new("ENG", "英语", 2.0, 150.0).unwrap(),
+        ];
+        assert_eq!(find(&courses, "math101").unwrap().name, "数学");
+        assert_eq!(find(&courses, "数学 ").unwrap().code, "MATH101");
+        assert!(find(&courses, "Math").is_none());
+
+        let same_name = Course::new("MATH102", "数学", 3.0, 100.0).unwrap();
+        assert!(check_new_course(&courses, &same_name).unwrap_err().contains("MATH101"));
+        let same_code = Course::new("eng", "English", 3.0, 100.0).unwrap();
+        assert!(check_new_course(&courses, &same_code).is_err());
+    }
+
+    #[test]
+    fn test_check_student() {
+        let courses = vec![Course::new("ENG", "英语", 2.0, 150.0).unwrap()];
+        let mut student = Student::new(1, String::from("张三"), 20);
+        student.enroll(String::from("ENG"));
+        student.add_grade(String::from("ENG"), 140.0);
+        assert!(check_student(&student, &courses).is_ok());
+
+        student.add_grade(String::from("ENG"), 151.0);
+        assert!(check_student(&student, &courses).is_err());
+
+        let mut unenrolled = Student::new(2, String::from("李四"), 20);
+        unenrolled.add_grade(String::from("ENG"), 90.0);
+        assert!(check_student(&unenrolled, &courses).unwrap_err().contains("没有选修"));
+
+        let mut unknown = Student::new(3, String::from("王五"), 20);
+        unknown.enroll(String::from("ART"));
+        assert!(check_student(&unknown, &courses).unwrap_err().contains("ART"));
+    }
+}
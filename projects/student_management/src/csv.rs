@@ -0,0 +1,105 @@
+// 简单的 CSV 读写：逗号分隔，字段可以用双引号包起来，引号内可以包含逗号、换行和两个连续的引号
+
+// 包含逗号、引号或换行的字段需要用引号包起来，内部的引号写两次
+pub fn escape(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// 一条 CSV 记录和它开始的行号（从 1 开始）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+// 解析整个 CSV 文本，跳过空行；引号没有闭合时返回错误
+pub fn parse(text: &str) -> Result<Vec<Record>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                push_record(&mut records, record_line, std::mem::take(&mut fields));
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("第 {} 行: 引号没有闭合", record_line));
+    }
+    fields.push(field);
+    push_record(&mut records, record_line, fields);
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<Record>, line: usize, fields: Vec<String>) {
+    // 空行只有一个空字段
+    if fields.len() == 1 && fields[0].trim().is_empty() {
+        return;
+    }
+    records.push(Record { line, fields });
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("张三"), "张三");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_parse() {
+        let records = parse("name,age\r\n\"Smith, J\",20\n\n\"多\n行\",\"2\"\"1\"\n").unwrap();
+        let fields: Vec<Vec<&str>> = records
+            .iter()
+            .map(|r| r.fields.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(fields, vec![vec!["name", "age"], vec!["Smith, J", "20"], vec!["多\n行", "2\"1"]]);
+        assert_eq!(records.iter().map(|r| r.line).collect::<Vec<usize>>(), vec![1, 2, 4]);
+
+        // 转义后再解析得到原文
+        let name = "a \"quoted\", name";
+        assert_eq!(parse(&escape(name)).unwrap()[0].fields, vec![name]);
+        assert!(parse("name\n\"unterminated").unwrap_err().contains("第 2 行"));
+    }
+}
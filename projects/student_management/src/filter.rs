@@ -0,0 +1,476 @@
+// 学生列表的筛选条件，例如
+//
+//     age >= 18 and avg < 60 and has(数学)
+//     name ~ 张 or (gpa >= 3.5 and not has(体育))
+//     grade(MATH101) != 100
+//
+// 可用的字段：
+// - id、age：整数
+// - name：姓名，支持 = 、!= 和 ~（包含，不区分大小写），文本可以用引号包起来
+// - class：班级，比较方式和 name 相同，没有分班的学生不满足任何关于班级的比较
+// - avg：学分加权的百分制平均分，gpa：默认换算表的 GPA
+// - grade(课程)：这门课程的原始分数，课程可以是代码或名称
+// - has(课程)：有这门课程的成绩
+//
+// 比较运算符有 = (或 ==)、!=、<、<=、>、>=；条件用 and、or、not 和括号组合，and 优先于 or。
+// 没有平均分、GPA 或课程成绩的学生不满足任何关于它们的比较。
+
+use std::fmt;
+
+use crate::course::{self, Course};
+use crate::student::Student;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    // 课程代码或名称，resolve 后为课程代码
+    Has(String),
+    Compare { field: Field, op: Op, value: Value },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Id,
+    Name,
+    Class,
+    Age,
+    Average,
+    Gpa,
+    Grade(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f32),
+    Text(String),
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Filter, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let filter = parser.or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("筛选条件中多余的内容: {}", token)),
+        }
+    }
+
+    // 把 has() 和 grade() 中的课程名称换成课程代码，课程没有登记时返回错误
+    pub fn resolve(self, courses: &[Course]) -> Result<Filter, String> {
+        let code = |text: String| -> Result<String, String> {
+            course::find(courses, &text)
+                .map(|course| course.code.clone())
+                .ok_or_else(|| format!("筛选条件中的课程 {} 没有登记", text))
+        };
+        Ok(match self {
+            Filter::And(left, right) => Filter::And(Box::new(left.resolve(courses)?), Box::new(right.resolve(courses)?)),
+            Filter::Or(left, right) => Filter::Or(Box::new(left.resolve(courses)?), Box::new(right.resolve(courses)?)),
+            Filter::Not(inner) => Filter::Not(Box::new(inner.resolve(courses)?)),
+            Filter::Has(text) => Filter::Has(code(text)?),
+            Filter::Compare { field: Field::Grade(text), op, value } => Filter::Compare {
+                field: Field::Grade(code(text)?),
+                op,
+                value,
+            },
+            compare => compare,
+        })
+    }
+
+    // average 和 gpa 由调用者按课程登记表和换算表计算好传入
+    pub fn matches(&self, student: &Student, average: Option<f32>, gpa: Option<f32>) -> bool {
+        match self {
+            Filter::And(left, right) => left.matches(student, average, gpa) && right.matches(student, average, gpa),
+            Filter::Or(left, right) => left.matches(student, average, gpa) || right.matches(student, average, gpa),
+            Filter::Not(inner) => !inner.matches(student, average, gpa),
+            Filter::Has(code) => student.grades.contains_key(code),
+            Filter::Compare { field, op, value } => {
+                let actual = match field {
+                    Field::Name => return compare_text(&student.name, *op, value),
+                    Field::Class => {
+                        return student.class.as_ref().is_some_and(|class| compare_text(class, *op, value));
+                    }
+                    Field::Id => Some(student.id as f32),
+                    Field::Age => Some(student.age as f32),
+                    Field::Average => average,
+                    Field::Gpa => gpa,
+                    Field::Grade(code) => student.current_grade(code),
+                };
+                match (actual, value) {
+                    (Some(actual), Value::Number(expected)) => compare_numbers(actual, *op, *expected),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn compare_numbers(actual: f32, op: Op, expected: f32) -> bool {
+    // 分数按 f32 保存，比较相等时允许很小的误差
+    let equal = (actual - expected).abs() < 1e-4;
+    match op {
+        Op::Eq => equal,
+        Op::Ne => !equal,
+        Op::Lt => actual < expected && !equal,
+        Op::Le => actual < expected || equal,
+        Op::Gt => actual > expected && !equal,
+        Op::Ge => actual > expected || equal,
+        Op::Contains => false,
+    }
+}
+
+fn compare_text(actual: &str, op: Op, expected: &Value) -> bool {
+    let expected = match expected {
+        Value::Text(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+    };
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Contains => actual.to_lowercase().contains(&expected.to_lowercase()),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    // 引号中的文本，不会被当作关键字或数字
+    Quoted(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Op(op) => write!(f, "{}", op_symbol(*op)),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+fn op_symbol(op: Op) -> &'static str {
+    match op {
+        Op::Eq => "=",
+        Op::Ne => "!=",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        Op::Gt => ">",
+        Op::Ge => ">=",
+        Op::Contains => "~",
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Contains),
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '"' | '\'' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(other) => quoted.push(other),
+                        None => return Err(format!("筛选条件中的引号没有结束: {}{}", c, quoted)),
+                    }
+                }
+                Token::Quoted(quoted)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(next) = chars.next_if(|next| !next.is_whitespace() && !"()<>=!~\"'".contains(*next)) {
+                    word.push(next);
+                }
+                if word == "!" {
+                    return Err(String::from("筛选条件中的 ! 只能用在 != 中，取反请用 not"));
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+// 把一串 and 或 or 连接的条件组合成平衡的树：从左到右的求值顺序不变，
+// 很长的一串条件也只有对数层深，求值时不会耗尽栈空间
+fn balanced(mut filters: Vec<Filter>, combine: fn(Box<Filter>, Box<Filter>) -> Filter) -> Filter {
+    if filters.len() == 1 {
+        return filters.remove(0);
+    }
+    let right = filters.split_off(filters.len() / 2);
+    combine(Box::new(balanced(filters, combine)), Box::new(balanced(right, combine)))
+}
+
+// 递归下降解析：or → and → not → 括号、has() 或比较
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    // 当前所在的 not 和括号的层数
+    depth: usize,
+}
+
+// not 和括号最多嵌套的层数，更深的条件在解析时会耗尽栈空间
+const MAX_DEPTH: usize = 256;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("筛选条件中应为 {}，实际是 {}", expected, token)),
+            None => Err(format!("筛选条件不完整，缺少 {}", expected)),
+        }
+    }
+
+    // 进入一层 not 或括号
+    fn nested(&mut self, parse: fn(&mut Parser) -> Result<Filter, String>) -> Result<Filter, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(String::from("筛选条件嵌套过深"));
+        }
+        self.depth += 1;
+        let filter = parse(self);
+        self.depth -= 1;
+        filter
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filters = vec![self.and()?];
+        while self.keyword("or") {
+            filters.push(self.and()?);
+        }
+        Ok(balanced(filters, Filter::Or))
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filters = vec![self.not()?];
+        while self.keyword("and") {
+            filters.push(self.not()?);
+        }
+        Ok(balanced(filters, Filter::And))
+    }
+
+    fn not(&mut self) -> Result<Filter, String> {
+        if self.keyword("not") {
+            return Ok(Filter::Not(Box::new(self.nested(Parser::not)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Filter, String> {
+        let name = match self.next() {
+            Some(Token::Open) => {
+                let filter = self.nested(Parser::or)?;
+                self.expect(Token::Close)?;
+                return Ok(filter);
+            }
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(token) => return Err(format!("筛选条件中应为字段名，实际是 {}", token)),
+            None => return Err(String::from("筛选条件不完整，缺少字段名")),
+        };
+        let field = match name.as_str() {
+            "has" => return Ok(Filter::Has(self.course_argument()?)),
+            "grade" => Field::Grade(self.course_argument()?),
+            "id" => Field::Id,
+            "name" => Field::Name,
+            "class" => Field::Class,
+            "age" => Field::Age,
+            "avg" => Field::Average,
+            "gpa" => Field::Gpa,
+            _ => return Err(format!("未知的字段: {}（可选 id、name、class、age、avg、gpa、grade(课程)、has(课程)）", name)),
+        };
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(token) => return Err(format!("{} 后面应为比较运算符，实际是 {}", name, token)),
+            None => return Err(format!("{} 后面缺少比较运算符", name)),
+        };
+        let value = match self.next() {
+            Some(Token::Quoted(text)) => Value::Text(text),
+            Some(Token::Word(word)) => match word.parse() {
+                Ok(number) => Value::Number(number),
+                Err(_) => Value::Text(word),
+            },
+            Some(token) => return Err(format!("{} {} 后面应为数值，实际是 {}", name, op_symbol(op), token)),
+            None => return Err(format!("{} {} 后面缺少数值", name, op_symbol(op))),
+        };
+        match (&field, op, &value) {
+            (Field::Name | Field::Class, Op::Eq | Op::Ne | Op::Contains, _) => {}
+            (Field::Name | Field::Class, _, _) => {
+                return Err(format!("{} 只能用 =、!= 或 ~ 比较，不能用 {}", name, op_symbol(op)));
+            }
+            (_, Op::Contains, _) => return Err(format!("~ 只能用于 name 和 class，不能用于 {}", name)),
+            (_, _, Value::Text(text)) => return Err(format!("{} 只能和数字比较: {}", name, text)),
+            _ => {}
+        }
+        Ok(Filter::Compare { field, op, value })
+    }
+
+    // has(课程) 和 grade(课程) 括号中的课程代码或名称
+    fn course_argument(&mut self) -> Result<String, String> {
+        self.expect(Token::Open)?;
+        let mut words = Vec::new();
+        loop {
+            match self.next() {
+                Some(Token::Close) => break,
+                Some(Token::Word(word)) | Some(Token::Quoted(word)) => words.push(word),
+                Some(token) => return Err(format!("课程名称中不能有 {}", token)),
+                None => return Err(String::from("筛选条件不完整，缺少 )")),
+            }
+        }
+        if words.is_empty() {
+            return Err(String::from("has() 和 grade() 中需要课程代码或名称"));
+        }
+        Ok(words.join(" "))
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(id: u32, name: &str, age: u8, grades: &[(&str, f32)]) -> Student {
+        let mut student = Student::new(id, String::from(name), age);
+        for (code, score) in grades {
+            student.enroll(code.to_string());
+            student.add_grade(code.to_string(), *score);
+        }
+        student
+    }
+
+    fn courses() -> Vec<Course> {
+        vec![
+            Course::new("MATH101", "数学", 4.0, 100.0).unwrap(),
+            Course::new("PE", "体育", 1.0, 100.0).unwrap(),
+        ]
+    }
+
+    fn matches(text: &str, student: &Student, average: Option<f32>) -> bool {
+        Filter::parse(text).unwrap().resolve(&courses()).unwrap().matches(student, average, None)
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let filter = Filter::parse("age >= 18 or age < 10 and has(数学)").unwrap();
+        let Filter::Or(_, right) = filter else { panic!("and 应该优先于 or: {:?}", filter) };
+        assert!(matches!(*right, Filter::And(_, _)));
+        assert_eq!(
+            Filter::parse("NOT (avg==60)").unwrap(),
+            Filter::Not(Box::new(Filter::Compare { field: Field::Average, op: Op::Eq, value: Value::Number(60.0) }))
+        );
+        assert_eq!(
+            Filter::parse("name ~ '张 三'").unwrap(),
+            Filter::Compare { field: Field::Name, op: Op::Contains, value: Value::Text(String::from("张 三")) }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Filter::parse("").unwrap_err().contains("缺少字段名"));
+        assert!(Filter::parse("height > 1").unwrap_err().contains("未知的字段"));
+        assert!(Filter::parse("age >").unwrap_err().contains("缺少数值"));
+        assert!(Filter::parse("age 18").unwrap_err().contains("比较运算符"));
+        assert!(Filter::parse("age > abc").unwrap_err().contains("只能和数字比较"));
+        assert!(Filter::parse("name > 张").unwrap_err().contains("name 只能用"));
+        assert!(Filter::parse("(age > 1").unwrap_err().contains("缺少 )"));
+        assert!(Filter::parse("age > 1 age").unwrap_err().contains("多余"));
+        assert!(Filter::parse("name = \"张").unwrap_err().contains("引号"));
+        assert!(Filter::parse("has()").is_err());
+        assert!(Filter::parse("has(化学)").unwrap().resolve(&courses()).unwrap_err().contains("化学"));
+
+        // 嵌套过深时报错，而不是耗尽栈空间
+        let deep = format!("{}age > 1{}", "(".repeat(50_000), ")".repeat(50_000));
+        assert_eq!(Filter::parse(&deep).unwrap_err(), "筛选条件嵌套过深");
+        assert_eq!(Filter::parse(&"not ".repeat(30_000)).unwrap_err(), "筛选条件嵌套过深");
+        let nested = format!("{}age > 1{}", "not (".repeat(128), ")".repeat(128));
+        assert!(Filter::parse(&nested).is_ok());
+
+        // 很长的一串 and 仍然可以解析和求值
+        let chain = vec!["age > 1"; 100_000].join(" and ");
+        let filter = Filter::parse(&chain).unwrap();
+        assert!(filter.matches(&Student::new(1, String::from("张三"), 20), None, None));
+    }
+
+    #[test]
+    fn test_matches() {
+        let zhang = student(1, "张三", 20, &[("MATH101", 55.0)]);
+        let li = student(2, "Li Si", 17, &[]);
+        assert!(matches("age >= 18 and avg < 60 and has(数学)", &zhang, Some(55.0)));
+        assert!(!matches("age >= 18 and avg < 60 and has(数学)", &li, None));
+        // 没有平均分时不满足关于平均分的任何比较
+        assert!(!matches("avg < 60", &li, None));
+        assert!(!matches("avg >= 60", &li, None));
+        assert!(matches("not has(math101)", &li, None));
+        assert!(matches("grade(MATH101) = 55 and not has(体育)", &zhang, Some(55.0)));
+        assert!(matches("name ~ si or id = 1", &li, None));
+        assert!(matches("name = 张三", &zhang, Some(55.0)));
+        assert!(matches("name != 张三", &li, None));
+        assert!(matches("(id = 1 or id = 2) and age <= 17", &li, None));
+        let mut wang = student(3, "王五", 19, &[]);
+        wang.class = Some(String::from("一班"));
+        assert!(matches("class = 一班", &wang, None));
+        assert!(matches("class ~ 一", &wang, None));
+        // 没有分班的学生不满足关于班级的任何比较
+        assert!(!matches("class = 一班", &li, None));
+        assert!(!matches("class != 一班", &li, None));
+    }
+}
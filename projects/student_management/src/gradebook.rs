@@ -0,0 +1,243 @@
+// 成绩册：课程按作业、测验、考试等类别分别记分，课程成绩由各类别的成绩加权计算
+//
+// 每个类别有权重（占课程成绩的百分比，所有类别合计 100）、去掉最低几次成绩的规则和迟交扣分。
+// 学生的每次作业或考试记为一条评估（Assessment），记录后重新计算课程成绩并写入 Student::grades，
+// 所以平均分、GPA 和查询都按课程的评分规则计算。
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// 课程的一个评分类别
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category {
+    pub name: String,
+    // 占课程成绩的百分比
+    pub weight: f32,
+    // 去掉最低的几次成绩，至少保留一次
+    #[serde(default)]
+    pub drop_lowest: usize,
+    // 每迟交一天扣的分数（百分制）
+    #[serde(default)]
+    pub late_penalty: f32,
+}
+
+impl Category {
+    pub fn new(name: &str, weight: f32, drop_lowest: usize, late_penalty: f32) -> Result<Category, String> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(String::from("评分类别的名称不能为空"));
+        }
+        if !(weight.is_finite() && weight > 0.0 && weight <= 100.0) {
+            return Err(format!("{} 的权重必须在0到100之间: {}", name, weight));
+        }
+        if !((0.0..=100.0).contains(&late_penalty)) {
+            return Err(format!("{} 的迟交扣分必须在0到100之间: {}", name, late_penalty));
+        }
+        Ok(Category {
+            name,
+            weight,
+            drop_lowest,
+            late_penalty,
+        })
+    }
+
+    // 解析 "作业=30,drop=1,late=10" 形式的类别：名称=权重，可选去掉最低几次和每天迟交扣分
+    pub fn parse(text: &str) -> Result<Category, String> {
+        let mut parts = text.split(',');
+        let (name, weight) = parts
+            .next()
+            .and_then(|part| part.split_once('='))
+            .ok_or_else(|| format!("无效的评分类别: {}（例如 作业=30,drop=1,late=10）", text))?;
+        let weight = parse_number(weight, "权重")?;
+        let mut drop_lowest = 0;
+        let mut late_penalty = 0.0;
+        for part in parts {
+            match part.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+                Some(("drop", value)) => {
+                    drop_lowest = value.parse().map_err(|_| format!("无效的去掉次数: {}", value))?
+                }
+                Some(("late", value)) => late_penalty = parse_number(value, "迟交扣分")?,
+                _ => return Err(format!("无法识别的评分规则: {}（可选 drop=N、late=扣分）", part)),
+            }
+        }
+        Category::new(name, weight, drop_lowest, late_penalty)
+    }
+
+    // 扣除迟交分数后的百分制分数，最低为 0
+    pub fn percent(&self, assessment: &Assessment) -> f32 {
+        let percent = assessment.score / assessment.max_score * 100.0;
+        (percent - self.late_penalty * assessment.days_late as f32).max(0.0)
+    }
+
+    // 类别的平均百分制分数：去掉最低的 drop_lowest 次后取平均，没有成绩时返回 None
+    pub fn average(&self, assessments: &[Assessment]) -> Option<f32> {
+        let mut percents: Vec<f32> = assessments
+            .iter()
+            .filter(|assessment| assessment.category == self.name)
+            .map(|assessment| self.percent(assessment))
+            .collect();
+        if percents.is_empty() {
+            return None;
+        }
+        percents.sort_by(|a, b| a.total_cmp(b));
+        let dropped = self.drop_lowest.min(percents.len() - 1);
+        let kept = &percents[dropped..];
+        Some(kept.iter().sum::<f32>() / kept.len() as f32)
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}%", self.name, self.weight)?;
+        if self.drop_lowest > 0 {
+            write!(f, "，去掉最低 {} 次", self.drop_lowest)?;
+        }
+        if self.late_penalty > 0.0 {
+            write!(f, "，迟交每天扣 {} 分", self.late_penalty)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(text: &str, what: &str) -> Result<f32, String> {
+    text.trim().parse().map_err(|_| format!("无效{}: {}", what, text.trim()))
+}
+
+// 一次作业、测验或考试的成绩
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assessment {
+    // 在同一门课程中唯一，例如 "作业3"、"期中考试"
+    pub name: String,
+    pub category: String,
+    pub score: f32,
+    // 这次评估的满分，与课程满分无关
+    pub max_score: f32,
+    #[serde(default)]
+    pub days_late: u32,
+}
+
+impl Assessment {
+    pub fn new(name: &str, category: &str, score: f32, max_score: f32, days_late: u32) -> Result<Assessment, String> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(String::from("评估名称不能为空"));
+        }
+        if !(max_score.is_finite() && max_score > 0.0) {
+            return Err(format!("满分必须大于0: {}", max_score));
+        }
+        if !(0.0..=max_score).contains(&score) {
+            return Err(format!("{} 的分数必须在0到{}之间: {}", name, max_score, score));
+        }
+        Ok(Assessment {
+            name,
+            category: category.trim().to_string(),
+            score,
+            max_score,
+            days_late,
+        })
+    }
+}
+
+// 检查课程的评分类别：名称不能重复，权重合计必须是 100；没有类别表示直接记录课程成绩
+pub fn check_categories(categories: &[Category]) -> Result<(), String> {
+    if categories.is_empty() {
+        return Ok(());
+    }
+    for (index, category) in categories.iter().enumerate() {
+        if categories[..index].iter().any(|other| other.name == category.name) {
+            return Err(format!("评分类别 {} 重复", category.name));
+        }
+    }
+    let total: f32 = categories.iter().map(|category| category.weight).sum();
+    if (total - 100.0).abs() > 0.01 {
+        return Err(format!("评分类别的权重合计必须是 100，现在是 {}", total));
+    }
+    Ok(())
+}
+
+// 课程的百分制成绩：各类别平均分按权重加权。还没有成绩的类别不计入，
+// 权重按已有成绩的类别重新分配，学期中途看到的就是目前的成绩。没有任何成绩时返回 None
+pub fn course_percent(categories: &[Category], assessments: &[Assessment]) -> Option<f32> {
+    let mut total = 0.0;
+    let mut weights = 0.0;
+    for category in categories {
+        if let Some(average) = category.average(assessments) {
+            total += average * category.weight;
+            weights += category.weight;
+        }
+    }
+    if weights > 0.0 {
+        Some(total / weights)
+    } else {
+        None
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Vec<Category> {
+        vec![
+            Category::parse("作业=30,drop=1,late=10").unwrap(),
+            Category::parse("期中=30").unwrap(),
+            Category::parse("期末=40").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_parse_category() {
+        let category = Category::parse(" 作业 = 30 , drop=1, late = 5").unwrap();
+        assert_eq!(category, Category::new("作业", 30.0, 1, 5.0).unwrap());
+        assert_eq!(category.to_string(), "作业 30%，去掉最低 1 次，迟交每天扣 5 分");
+        assert!(Category::parse("作业").is_err());
+        assert!(Category::parse("作业=abc").is_err());
+        assert!(Category::parse("作业=0").is_err());
+        assert!(Category::parse("作业=30,drop=-1").is_err());
+        assert!(Category::parse("作业=30,bonus=1").unwrap_err().contains("bonus"));
+
+        assert!(check_categories(&policy()).is_ok());
+        assert!(check_categories(&policy()[..2]).unwrap_err().contains("60"));
+        let mut duplicated = policy();
+        duplicated[1].name = String::from("作业");
+        assert!(check_categories(&duplicated).unwrap_err().contains("重复"));
+    }
+
+    #[test]
+    fn test_drop_lowest_and_late_penalty() {
+        let homework = &policy()[0];
+        let assessments = vec![
+            Assessment::new("作业1", "作业", 18.0, 20.0, 0).unwrap(),
+            Assessment::new("作业2", "作业", 10.0, 20.0, 0).unwrap(),
+            // 100 分迟交 2 天，扣 20 分
+            Assessment::new("作业3", "作业", 50.0, 50.0, 2).unwrap(),
+        ];
+        assert_eq!(homework.percent(&assessments[2]), 80.0);
+        // 去掉最低的 50 分，剩下 90 和 80
+        assert_eq!(homework.average(&assessments), Some(85.0));
+        // 只有一次成绩时不会被去掉
+        assert_eq!(homework.average(&assessments[1..2]), Some(50.0));
+        assert_eq!(homework.average(&[]), None);
+
+        let very_late = Assessment::new("作业4", "作业", 5.0, 20.0, 9).unwrap();
+        assert_eq!(homework.percent(&very_late), 0.0);
+        assert!(Assessment::new("作业5", "作业", 21.0, 20.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_course_percent() {
+        let categories = policy();
+        let mut assessments = vec![
+            Assessment::new("作业1", "作业", 90.0, 100.0, 0).unwrap(),
+            Assessment::new("期中", "期中", 70.0, 100.0, 0).unwrap(),
+        ];
+        // 期末还没有成绩，作业和期中各占一半
+        assert_eq!(course_percent(&categories, &assessments), Some(80.0));
+        assessments.push(Assessment::new("期末", "期末", 80.0, 100.0, 0).unwrap());
+        let percent = course_percent(&categories, &assessments).unwrap();
+        assert!((percent - (90.0 * 0.3 + 70.0 * 0.3 + 80.0 * 0.4)).abs() < 1e-4);
+        assert_eq!(course_percent(&categories, &[]), None);
+    }
+}
@@ -0,0 +1,261 @@
+// 成绩计算：学分加权平均分、GPA 和等级
+//
+// 分数先按课程满分换算成百分制，再按学分加权。通过制（pass/fail）课程只判断是否通过，
+// 不计入加权平均分和 GPA。
+//
+// 绩点换算表来自配置文件（格式见 grading_scales.json），每个学校可以提供自己的分数线；
+// 没有配置文件时使用内置的 4.0、4.3 和 5.0 三种换算表。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::Course;
+use crate::storage;
+use crate::student::Student;
+
+// 内置的换算表，与项目目录下的 grading_scales.json 相同
+const BUILTIN: &str = include_str!("../grading_scales.json");
+
+// 设置这个环境变量可以指定换算表配置文件
+pub const GRADING_PATH_VARIABLE: &str = "STUDENT_GRADING_FILE";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradingConfig {
+    // 不指定换算表时使用的换算表名称
+    pub default_scale: String,
+    // 通过制课程及格的百分制分数线
+    pub pass_percent: f32,
+    pub scales: Vec<GradingScale>,
+}
+
+// 一种绩点换算表，分数段按最低分从高到低排列
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradingScale {
+    pub name: String,
+    pub bands: Vec<Band>,
+}
+
+// 百分制分数不低于 min 时得到的等级和绩点
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Band {
+    pub min: f32,
+    pub letter: String,
+    pub points: f32,
+}
+
+impl GradingConfig {
+    pub fn builtin() -> GradingConfig {
+        GradingConfig::from_json(BUILTIN).expect("内置的换算表必须有效")
+    }
+
+    pub fn from_json(text: &str) -> Result<GradingConfig, String> {
+        let mut config: GradingConfig =
+            serde_json::from_str(text).map_err(|e| format!("换算表配置不正确（{}）", e))?;
+        config.validate()?;
+        for scale in &mut config.scales {
+            scale.bands.sort_by(|a, b| b.min.total_cmp(&a.min));
+        }
+        Ok(config)
+    }
+
+    // 读取配置文件，文件不存在时使用内置换算表
+    pub fn load_or_builtin(path: &Path) -> Result<GradingConfig, String> {
+        if !path.exists() {
+            return Ok(GradingConfig::builtin());
+        }
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取换算表 {}: {}", path.display(), e))?;
+        GradingConfig::from_json(&text).map_err(|e| format!("换算表 {} 无法使用: {}", path.display(), e))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=100.0).contains(&self.pass_percent) {
+            return Err(format!("及格线必须在0到100之间: {}", self.pass_percent));
+        }
+        for (index, scale) in self.scales.iter().enumerate() {
+            if self.scales[..index].iter().any(|other| other.name == scale.name) {
+                return Err(format!("换算表 {} 重复", scale.name));
+            }
+            for band in &scale.bands {
+                if !((0.0..=100.0).contains(&band.min) && band.points.is_finite() && band.points >= 0.0) {
+                    return Err(format!("换算表 {} 的等级 {} 不正确", scale.name, band.letter));
+                }
+            }
+            // 必须有一档从 0 分开始，所有分数才都有对应的等级
+            if !scale.bands.iter().any(|band| band.min == 0.0) {
+                return Err(format!("换算表 {} 缺少最低分为 0 的一档", scale.name));
+            }
+        }
+        self.scale(None).map(|_| ())
+    }
+
+    // 按名称选择换算表，None 表示默认换算表
+    pub fn scale(&self, name: Option<&str>) -> Result<&GradingScale, String> {
+        let name = name.unwrap_or(&self.default_scale);
+        self.scales.iter().find(|scale| scale.name == name).ok_or_else(|| {
+            let names: Vec<&str> = self.scales.iter().map(|scale| scale.name.as_str()).collect();
+            format!("没有名为 {} 的换算表（可选 {}）", name, names.join("、"))
+        })
+    }
+
+    // 换成另一个默认换算表
+    pub fn with_default_scale(mut self, name: &str) -> Result<GradingConfig, String> {
+        self.scale(Some(name))?;
+        self.default_scale = name.to_string();
+        Ok(self)
+    }
+
+    pub fn default(&self) -> &GradingScale {
+        self.scale(None).expect("默认换算表在加载时已经检查过")
+    }
+
+    // 课程的等级：通过制课程为 P/F，其余课程按默认换算表
+    pub fn letter(&self, course: &Course, score: f32) -> String {
+        let percent = percent(course, score);
+        if course.pass_fail {
+            String::from(if percent >= self.pass_percent { "P" } else { "F" })
+        } else {
+            self.default().band(percent).letter.clone()
+        }
+    }
+
+    // 学分加权的 GPA，没有计分课程的成绩时返回 None
+    pub fn gpa(&self, student: &Student, courses: &[Course]) -> Option<f32> {
+        let scale = self.default();
+        weighted(student, courses, |course, score| scale.band(percent(course, score)).points)
+    }
+}
+
+impl GradingScale {
+    // 百分制分数所在的分数段
+    pub fn band(&self, percent: f32) -> &Band {
+        self.bands
+            .iter()
+            .find(|band| percent >= band.min)
+            .unwrap_or_else(|| &self.bands[self.bands.len() - 1])
+    }
+
+    // 最高绩点，用于显示 "3.50 / 4.0" 中的分母
+    pub fn max_points(&self) -> f32 {
+        self.bands.iter().map(|band| band.points).fold(0.0, f32::max)
+    }
+}
+
+// 按课程满分换算成百分制
+pub fn percent(course: &Course, score: f32) -> f32 {
+    score / course.max_score * 100.0
+}
+
+// 学分加权的百分制平均分，通过制课程不计入，没有计分课程的成绩时返回 None
+pub fn weighted_average(student: &Student, courses: &[Course]) -> Option<f32> {
+    weighted(student, courses, percent)
+}
+
+// 对有成绩的计分课程按学分加权平均
+fn weighted(student: &Student, courses: &[Course], value: impl Fn(&Course, f32) -> f32) -> Option<f32> {
+    let mut total = 0.0;
+    let mut credits = 0.0;
+    for (code, score) in &student.grades {
+        if let Some(course) = courses.iter().find(|course| &course.code == code && !course.pass_fail) {
+            total += value(course, *score) * course.credits;
+            credits += course.credits;
+        }
+    }
+    if credits > 0.0 {
+        Some(total / credits)
+    } else {
+        None
+    }
+}
+
+// 换算表配置文件的默认位置：环境变量 STUDENT_GRADING_FILE，否则是数据文件旁边的 grading_scales.json
+pub fn default_path() -> PathBuf {
+    match std::env::var_os(GRADING_PATH_VARIABLE) {
+        Some(path) => PathBuf::from(path),
+        None => storage::default_path().with_file_name("grading_scales.json"),
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn courses() -> Vec<Course> {
+        let mut pe = Course::new("PE", "体育", 1.0, 100.0).unwrap();
+        pe.pass_fail = true;
+        vec![
+            Course::new("MATH", "数学", 4.0, 100.0).unwrap(),
+            Course::new("ENG", "英语", 2.0, 150.0).unwrap(),
+            pe,
+        ]
+    }
+
+    fn student(grades: &[(&str, f32)]) -> Student {
+        let mut student = Student::new(1, String::from("张三"), 20);
+        for (code, score) in grades {
+            student.enroll(code.to_string());
+            student.add_grade(code.to_string(), *score);
+        }
+        student
+    }
+
+    #[test]
+    fn test_builtin_scales() {
+        let config = GradingConfig::builtin();
+        assert_eq!(config.default().name, "4.0");
+        assert_eq!(config.scale(Some("4.3")).unwrap().band(96.0).letter, "A+");
+        assert_eq!(config.scale(Some("4.3")).unwrap().max_points(), 4.3);
+        assert_eq!(config.scale(Some("5.0")).unwrap().band(85.0).points, 4.0);
+        assert_eq!(config.default().band(59.9).letter, "F");
+        assert!(config.scale(Some("10")).unwrap_err().contains("4.0、4.3、5.0"));
+    }
+
+    #[test]
+    fn test_weighted_average_and_gpa() {
+        let courses = courses();
+        // 数学 90 分（4 学分），英语 120/150 = 80 分（2 学分），体育是通过制，不计入
+        let student = student(&[("MATH", 90.0), ("ENG", 120.0), ("PE", 30.0)]);
+        let average = weighted_average(&student, &courses).unwrap();
+        assert!((average - (90.0 * 4.0 + 80.0 * 2.0) / 6.0).abs() < 1e-4);
+
+        let config = GradingConfig::builtin();
+        let gpa = config.gpa(&student, &courses).unwrap();
+        assert!((gpa - (4.0 * 4.0 + 3.0 * 2.0) / 6.0).abs() < 1e-4);
+        let gpa = config.clone().with_default_scale("5.0").unwrap().gpa(&student, &courses).unwrap();
+        assert!((gpa - (5.0 * 4.0 + 4.0 * 2.0) / 6.0).abs() < 1e-4);
+
+        assert_eq!(config.letter(&courses[1], 120.0), "B");
+        assert_eq!(config.letter(&courses[2], 30.0), "F");
+        assert_eq!(config.letter(&courses[2], 60.0), "P");
+
+        // 只有通过制课程的成绩时没有 GPA
+        let only_pe = self::student(&[("PE", 90.0)]);
+        assert_eq!(weighted_average(&only_pe, &courses), None);
+        assert_eq!(config.gpa(&only_pe, &courses), None);
+    }
+
+    #[test]
+    fn test_custom_config() {
+        let text = r#"{ "default_scale": "school", "pass_percent": 50, "scales": [
+            { "name": "school", "bands": [
+                { "min": 0, "letter": "E", "points": 0 },
+                { "min": 85, "letter": "优", "points": 4 },
+                { "min": 50, "letter": "合格", "points": 2 }
+            ] }
+        ] }"#;
+        let config = GradingConfig::from_json(text).unwrap();
+        // 分数段不需要按顺序书写
+        assert_eq!(config.default().band(86.0).letter, "优");
+        assert_eq!(config.default().band(50.0).letter, "合格");
+        assert_eq!(config.default().band(49.0).letter, "E");
+
+        let missing_zero = r#"{ "default_scale": "s", "pass_percent": 60, "scales": [
+            { "name": "s", "bands": [ { "min": 60, "letter": "P", "points": 1 } ] } ] }"#;
+        assert!(GradingConfig::from_json(missing_zero).unwrap_err().contains("最低分为 0"));
+        let unknown_default = r#"{ "default_scale": "x", "pass_percent": 60, "scales": [] }"#;
+        assert!(GradingConfig::from_json(unknown_default).is_err());
+        assert!(GradingConfig::from_json("{").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// 成绩修改历史：每次课程成绩变化都记下时间、原来的分数、新的分数和原因
+//
+// 时间来自 Clock，程序中使用系统时间，测试中可以换成 ManualClock 得到固定的时间。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// 提供当前时间（Unix 时间戳，秒），可以在多个线程中共用
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+// 共享的时钟，测试中可以一边把时钟交给被测代码，一边调整时间
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> u64 {
+        self.as_ref().now()
+    }
+}
+
+// 系统时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+// 手动设置的时间，只有调用 set 或 advance 时才会变化，主要用于测试
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> ManualClock {
+        ManualClock { now: AtomicU64::new(now) }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, seconds: u64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+// 一次成绩修改；old 为 None 表示第一次录入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradeChange {
+    pub time: u64,
+    pub old: Option<f32>,
+    pub new: Option<f32>,
+    pub reason: String,
+}
+
+// 没有填写原因时使用的默认原因
+pub fn default_reason(old: Option<f32>, new: Option<f32>) -> &'static str {
+    match (old, new) {
+        (None, _) => "录入成绩",
+        (Some(_), None) => "删除成绩",
+        (Some(_), Some(_)) => "修改成绩",
+    }
+}
+
+// 把 Unix 时间戳格式化为 UTC 时间，例如 2024-03-01 08:30:00 UTC
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // 从 1970-01-01 起的天数换算为公历日期（Howard Hinnant 的 civil_from_days 算法）
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(100);
+        assert_eq!(clock.now(), 100);
+        clock.advance(60);
+        assert_eq!(clock.now(), 160);
+        clock.set(5);
+        assert_eq!(clock.now(), 5);
+        assert!(SystemClock.now() > 1_700_000_000);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_281_800), "2024-03-01 08:30:00 UTC");
+    }
+}
@@ -0,0 +1,419 @@
+// CSV 批量导入学生、选课和成绩
+//
+// 根据表头判断文件类型（列的顺序不限）：
+// - 学生: name,age
+// - 选课: id,course
+// - 成绩: id,course,score（course 也可以写成 subject）
+//
+// course 列可以是课程代码或课程名称，课程必须已经登记；成绩只能导入已经选修的课程。
+//
+// 先检查所有行，只要有一行被拒绝就不导入任何记录；全部通过后在一次 batch 中写入存储后端，
+// 写入中途失败时同样不留下任何记录。
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::course::{self, Course};
+use crate::csv::{self, Record};
+use crate::history::Clock;
+use crate::repository::StudentRepository;
+use crate::student::{Student, AGE_RANGE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    Students,
+    Enrollments,
+    Grades,
+}
+
+// 导入结果：通过和被拒绝的行，以及是否已经写入
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub kind: ImportKind,
+    // (行号, 内容描述)
+    pub accepted: Vec<(usize, String)>,
+    // (行号, 内容描述, 原因)
+    pub rejected: Vec<(usize, String, String)>,
+    pub committed: bool,
+}
+
+// 通过检查、等待写入的一行
+enum Row {
+    Student { name: String, age: u8 },
+    Enrollment { id: u32, code: String },
+    Grade { id: u32, code: String, score: f32 },
+}
+
+// 导入 CSV 文本；文件整体无法识别（格式错误、表头不对）时返回 Err，逐行的问题记录在报告中
+// 成绩的修改以 "CSV 导入" 为原因记入历史，时间来自 clock
+pub fn import_csv(repository: &mut dyn StudentRepository, text: &str, clock: &dyn Clock) -> Result<ImportReport, String> {
+    let mut records = csv::parse(text)?.into_iter();
+    let header = records.next().ok_or_else(|| String::from("文件是空的"))?;
+    let header: Vec<String> = header
+        .fields
+        .iter()
+        .map(|h| match h.trim().to_lowercase().as_str() {
+            // 旧的成绩文件使用 subject 列
+            "subject" => String::from("course"),
+            other => other.to_string(),
+        })
+        .collect();
+
+    let (kind, columns) = if let Some(columns) = column_indexes(&header, &["name", "age"]) {
+        (ImportKind::Students, columns)
+    } else if let Some(columns) = column_indexes(&header, &["id", "course"]) {
+        (ImportKind::Enrollments, columns)
+    } else if let Some(columns) = column_indexes(&header, &["id", "course", "score"]) {
+        (ImportKind::Grades, columns)
+    } else {
+        return Err(format!(
+            "无法识别的表头: {}（学生文件应为 name,age，选课文件应为 id,course，成绩文件应为 id,course,score）",
+            header.join(",")
+        ));
+    };
+
+    let existing = repository.list()?;
+    let courses = repository.list_courses()?;
+    let mut validator = Validator::new(&existing, &courses);
+    let mut report = ImportReport {
+        kind,
+        accepted: Vec::new(),
+        rejected: Vec::new(),
+        committed: false,
+    };
+    let mut rows = Vec::new();
+
+    for record in records {
+        let description = record.fields.join(",");
+        let result = if record.fields.len() != header.len() {
+            Err(format!("应有 {} 列，实际有 {} 列", header.len(), record.fields.len()))
+        } else {
+            match kind {
+                ImportKind::Students => validator.student(&record, &columns),
+                ImportKind::Enrollments => validator.enrollment(&record, &columns),
+                ImportKind::Grades => validator.grade(&record, &columns),
+            }
+        };
+        match result {
+            Ok(row) => {
+                report.accepted.push((record.line, description));
+                rows.push(row);
+            }
+            Err(reason) => report.rejected.push((record.line, description, reason)),
+        }
+    }
+
+    if report.rejected.is_empty() && !rows.is_empty() {
+        let ids = commit(repository, rows, clock)?;
+        // 新学生的描述中加上分配到的 ID
+        for ((_, description), id) in report.accepted.iter_mut().zip(ids) {
+            if let Some(id) = id {
+                description.push_str(&format!(" → ID {}", id));
+            }
+        }
+        report.committed = true;
+    }
+    Ok(report)
+}
+
+// 按表头找到每个需要的列，列名必须完全一致（顺序不限）
+fn column_indexes(header: &[String], names: &[&str]) -> Option<Vec<usize>> {
+    if header.len() != names.len() {
+        return None;
+    }
+    names.iter().map(|name| header.iter().position(|h| h == name)).collect()
+}
+
+// 逐行检查，同时记住已经出现过的记录用来发现重复
+struct Validator<'a> {
+    students: HashMap<u32, &'a Student>,
+    courses: &'a [Course],
+    // (姓名, 年龄) -> 第一次出现的描述
+    names: HashMap<(String, u8), String>,
+    // (学生 ID, 课程代码) -> 第一次出现的行号
+    seen: HashMap<(u32, String), usize>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(existing: &'a [Student], courses: &'a [Course]) -> Validator<'a> {
+        let mut names = HashMap::new();
+        for student in existing {
+            names
+                .entry((student.name.clone(), student.age))
+                .or_insert_with(|| format!("与已有学生 ID {} 重复", student.id));
+        }
+        Validator {
+            students: existing.iter().map(|student| (student.id, student)).collect(),
+            courses,
+            names,
+            seen: HashMap::new(),
+        }
+    }
+
+    fn student(&mut self, record: &Record, columns: &[usize]) -> Result<Row, String> {
+        let name = record.fields[columns[0]].trim().to_string();
+        let age = record.fields[columns[1]].trim();
+        if name.is_empty() {
+            return Err(String::from("姓名不能为空"));
+        }
+        let age = parse_age(age)?;
+
+        let key = (name.clone(), age);
+        if let Some(duplicate) = self.names.get(&key) {
+            return Err(duplicate.clone());
+        }
+        self.names.insert(key, format!("与第 {} 行重复", record.line));
+        Ok(Row::Student { name, age })
+    }
+
+    // 检查学生 ID 和课程，返回学生和课程
+    fn student_and_course(&self, id: &str, course: &str) -> Result<(&'a Student, &'a Course), String> {
+        let id: u32 = id.trim().parse().map_err(|_| format!("无效ID: {}", id.trim()))?;
+        let student = *self.students.get(&id).ok_or_else(|| format!("未找到ID为 {} 的学生", id))?;
+        let course = course::find(self.courses, course).ok_or_else(|| format!("课程 {} 没有登记", course.trim()))?;
+        Ok((student, course))
+    }
+
+    // 同一个学生和课程在文件中只能出现一次
+    fn check_duplicate(&mut self, record: &Record, id: u32, code: &str) -> Result<(), String> {
+        if let Some(line) = self.seen.get(&(id, code.to_string())) {
+            return Err(format!("与第 {} 行重复", line));
+        }
+        self.seen.insert((id, code.to_string()), record.line);
+        Ok(())
+    }
+
+    fn enrollment(&mut self, record: &Record, columns: &[usize]) -> Result<Row, String> {
+        let (student, course) = self.student_and_course(&record.fields[columns[0]], &record.fields[columns[1]])?;
+        if student.is_enrolled(&course.code) {
+            return Err(format!("学生 {} 已经选修了课程 {}", student.id, course.code));
+        }
+        self.check_duplicate(record, student.id, &course.code)?;
+        Ok(Row::Enrollment {
+            id: student.id,
+            code: course.code.clone(),
+        })
+    }
+
+    fn grade(&mut self, record: &Record, columns: &[usize]) -> Result<Row, String> {
+        let (student, course) = self.student_and_course(&record.fields[columns[0]], &record.fields[columns[1]])?;
+        if !student.is_enrolled(&course.code) {
+            return Err(format!("学生 {} 没有选修课程 {}（{}）", student.id, course.code, course.name));
+        }
+        let score = parse_score(record.fields[columns[2]].trim())?;
+        course.check_direct_grade()?;
+        course.check_score(score)?;
+        self.check_duplicate(record, student.id, &course.code)?;
+        Ok(Row::Grade {
+            id: student.id,
+            code: course.code.clone(),
+            score,
+        })
+    }
+}
+
+pub fn parse_age(text: &str) -> Result<u8, String> {
+    match text.parse::<u8>() {
+        Ok(age) if AGE_RANGE.contains(&age) => Ok(age),
+        _ => Err(format!(
+            "无效年龄: {}（应为 {}-{} 之间的整数）",
+            text,
+            AGE_RANGE.start(),
+            AGE_RANGE.end()
+        )),
+    }
+}
+
+// 分数的范围取决于课程满分，这里只检查是不是一个数
+pub fn parse_score(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(score) if score.is_finite() => Ok(score),
+        _ => Err(format!("无效分数: {}", text)),
+    }
+}
+
+// 在一次 batch 中写入所有行，返回每行新建学生的 ID；中途失败时一行都不写入
+fn commit(repository: &mut dyn StudentRepository, rows: Vec<Row>, clock: &dyn Clock) -> Result<Vec<Option<u32>>, String> {
+    let mut ids = Vec::new();
+    repository
+        .batch(&mut |repository| {
+            ids.clear();
+            for row in &rows {
+                let id = match row {
+                    Row::Student { name, age } => Some(repository.insert(name.clone(), *age)?),
+                    Row::Enrollment { id, code } => {
+                        repository.modify(*id, &mut |student| {
+                            student.enroll(code.clone());
+                            Ok(())
+                        })?;
+                        None
+                    }
+                    Row::Grade { id, code, score } => {
+                        repository.modify(*id, &mut |student| {
+                            student.set_grade(code, *score, "CSV 导入", clock);
+                            Ok(())
+                        })?;
+                        None
+                    }
+                };
+                ids.push(id);
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("写入失败，未导入任何记录: {}", e))?;
+    Ok(ids)
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ImportKind::Students => "学生",
+            ImportKind::Enrollments => "选课",
+            ImportKind::Grades => "成绩",
+        };
+        writeln!(f, "{:-^50}", format!(" 导入{} ", kind))?;
+        writeln!(f, "通过 {} 行:", self.accepted.len())?;
+        for (line, description) in &self.accepted {
+            writeln!(f, "  第 {} 行: {}", line, description)?;
+        }
+        writeln!(f, "拒绝 {} 行:", self.rejected.len())?;
+        for (line, description, reason) in &self.rejected {
+            writeln!(f, "  第 {} 行: {} —— {}", line, description, reason)?;
+        }
+        if self.committed {
+            write!(f, "已导入 {} 条{}记录", self.accepted.len(), kind)
+        } else if self.rejected.is_empty() {
+            write!(f, "文件中没有数据，未导入任何记录")
+        } else {
+            write!(f, "存在被拒绝的行，未导入任何记录")
+        }
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+    use crate::student::StudentManagement;
+    use crate::undo::Undoable;
+
+    #[test]
+    fn test_import_students() {
+        let mut repository = StudentManagement::new();
+        let report = import_csv(&mut repository, "age,name\n20,张三\n21,\"Smith, J\"\n", &ManualClock::default()).unwrap();
+        assert!(report.committed);
+        assert_eq!(report.kind, ImportKind::Students);
+        assert_eq!(
+            report.accepted,
+            vec![(2, String::from("20,张三 → ID 1")), (3, String::from("21,Smith, J → ID 2"))]
+        );
+        assert_eq!(repository.get(2).unwrap().unwrap().name, "Smith, J");
+    }
+
+    #[test]
+    fn test_rejected_rows_import_nothing() {
+        let mut repository = StudentManagement::new();
+        repository.insert(String::from("王五"), 19).unwrap();
+        let text = "name,age\n张三,20\n李四,200\n,20\n张三,20\n王五,19\n赵六\n";
+        let report = import_csv(&mut repository, text, &ManualClock::default()).unwrap();
+
+        assert!(!report.committed);
+        assert_eq!(report.accepted.len(), 1);
+        let reasons: Vec<(usize, &str)> = report
+            .rejected
+            .iter()
+            .map(|(line, _, reason)| (*line, reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, "无效年龄: 200（应为 1-150 之间的整数）"),
+                (4, "姓名不能为空"),
+                (5, "与第 2 行重复"),
+                (6, "与已有学生 ID 1 重复"),
+                (7, "应有 2 列，实际有 1 列"),
+            ]
+        );
+        assert_eq!(repository.list().unwrap().len(), 1);
+        assert!(report.to_string().ends_with("未导入任何记录"));
+    }
+
+    #[test]
+    fn test_failed_write_imports_nothing() {
+        // 检查之后学生被删除，写到第二行时失败，第一行也不能留下
+        let mut repository = Undoable::new(Box::new(StudentManagement::new()));
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        repository.insert_course(math.clone()).unwrap();
+        let rows = vec![
+            Row::Student { name: String::from("张三"), age: 20 },
+            Row::Enrollment { id: 9, code: math.code.clone() },
+        ];
+        let error = commit(&mut repository, rows, &ManualClock::default()).unwrap_err();
+        assert_eq!(error, "写入失败，未导入任何记录: 未找到ID为 9 的学生");
+        assert!(repository.list().unwrap().is_empty());
+        assert_eq!(repository.undo_count(), 1);
+
+        // 成功的导入作为一个操作整批撤销
+        let rows = vec![
+            Row::Student { name: String::from("张三"), age: 20 },
+            Row::Student { name: String::from("李四"), age: 21 },
+        ];
+        assert_eq!(commit(&mut repository, rows, &ManualClock::default()).unwrap(), vec![Some(1), Some(2)]);
+        assert_eq!(repository.undo().unwrap().unwrap(), "批量修改（2 项）");
+        assert!(repository.list().unwrap().is_empty());
+        assert_eq!(repository.redo().unwrap().unwrap(), "批量修改（2 项）");
+        assert_eq!(repository.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_import_enrollments_and_grades() {
+        let mut repository = StudentManagement::new();
+        repository.insert_course(Course::new("MATH101", "数学", 4.0, 100.0).unwrap()).unwrap();
+        repository.insert_course(Course::new("ENG", "英语", 2.0, 150.0).unwrap()).unwrap();
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+
+        // 成绩只能导入已经选修的课程
+        let early = format!("id,course,score\n{},数学,90\n", id);
+        let report = import_csv(&mut repository, &early, &ManualClock::default()).unwrap();
+        assert!(report.rejected[0].2.contains("没有选修"));
+
+        let bad = format!("id,course\n{0},math101\n{0},美术\n9,ENG\n{0},MATH101\n", id);
+        let report = import_csv(&mut repository, &bad, &ManualClock::default()).unwrap();
+        assert_eq!(report.kind, ImportKind::Enrollments);
+        let reasons: Vec<&str> = report.rejected.iter().map(|(_, _, reason)| reason.as_str()).collect();
+        assert_eq!(reasons, vec!["课程 美术 没有登记", "未找到ID为 9 的学生", "与第 2 行重复"]);
+        assert!(repository.get(id).unwrap().unwrap().enrollments.is_empty());
+
+        let good = format!("course,id\nMATH101,{0}\n英语,{0}\n", id);
+        assert!(import_csv(&mut repository, &good, &ManualClock::default()).unwrap().committed);
+        let again = format!("id,course\n{},ENG\n", id);
+        assert!(import_csv(&mut repository, &again, &ManualClock::default()).unwrap().rejected[0].2.contains("已经选修"));
+
+        let bad = format!("id,subject,score\n{0},数学,90\n{0},英语,151\n{0},math101,70\nx,数学,1\n", id);
+        let report = import_csv(&mut repository, &bad, &ManualClock::default()).unwrap();
+        assert!(!report.committed);
+        let lines: Vec<usize> = report.rejected.iter().map(|(line, _, _)| *line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert!(repository.get(id).unwrap().unwrap().grades.is_empty());
+
+        // 英语满分是 150
+        let good = format!("course,id,score\n数学,{0},90\nENG,{0},140\n", id);
+        let report = import_csv(&mut repository, &good, &ManualClock::new(1_709_281_800)).unwrap();
+        assert!(report.committed);
+        let student = repository.get(id).unwrap().unwrap();
+        assert_eq!(student.average_grade(), 115.0);
+        assert_eq!(student.grade_history("ENG")[0].reason, "CSV 导入");
+        assert_eq!(student.grade_history("ENG")[0].time, 1_709_281_800);
+    }
+
+    #[test]
+    fn test_unrecognized_files() {
+        let mut repository = StudentManagement::new();
+        assert!(import_csv(&mut repository, "", &ManualClock::default()).unwrap_err().contains("空"));
+        assert!(import_csv(&mut repository, "name,height\n张三,180\n", &ManualClock::default()).unwrap_err().contains("表头"));
+        let report = import_csv(&mut repository, "name,age\n", &ManualClock::default()).unwrap();
+        assert!(!report.committed);
+        assert!(report.to_string().ends_with("没有数据，未导入任何记录"));
+    }
+}
@@ -0,0 +1,53 @@
+//! 学生管理系统库：examples/02_student_management.rs 使用的数据模型和持久化
+//!
+//! 数据通过 [`StudentRepository`] 读写，可以选择内存、JSON 文件或 SQLite 后端。
+//! 成绩只能记录在课程登记表中登记过、并且学生已经选修的课程上，
+//! 学分加权平均分和 GPA 的计算见 [`grading`]。
+//! 多个线程同时读写同一份数据时使用 [`SharedStudents`]，在现有记录上修改学生时使用
+//! [`StudentRepository::modify`]，不会覆盖其他线程或程序同时进行的修改。
+//!
+//! ```
+//! use student_management::history::SystemClock;
+//! use student_management::{Course, StudentManagement, StudentRepository};
+//!
+//! let mut repository = StudentManagement::new();
+//! let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+//! repository.insert_course(math.clone()).unwrap();
+//!
+//! let id = repository.insert(String::from("张三"), 20).unwrap();
+//! let mut student = repository.get(id).unwrap().unwrap();
+//! student.enroll(math.code.clone());
+//! student.record_grade(&math, 92.0, "期末考试", &SystemClock).unwrap();
+//! repository.update(student).unwrap();
+//! assert_eq!(repository.get(id).unwrap().unwrap().average_grade(), 92.0);
+//! ```
+
+pub mod audit;
+pub mod cli;
+pub mod course;
+pub mod csv;
+pub mod filter;
+pub mod gradebook;
+pub mod grading;
+pub mod history;
+pub mod import;
+pub mod lock;
+pub mod ranking;
+pub mod report;
+pub mod repository;
+pub mod server;
+pub mod shared;
+pub mod sqlite;
+pub mod stats;
+pub mod storage;
+pub mod student;
+pub mod tui;
+pub mod undo;
+
+pub use course::Course;
+pub use grading::GradingConfig;
+pub use repository::{Backend, Query, StudentRepository};
+pub use shared::SharedStudents;
+pub use sqlite::SqliteRepository;
+pub use storage::JsonFileRepository;
+pub use student::{Student, StudentManagement};
@@ -0,0 +1,82 @@
+// 跨进程的文件锁：命令行、API 服务器和终端界面可能是几个同时运行的程序，
+// 它们修改同一个数据文件或审计日志之前先锁住旁边的 <文件名>.lock，依次进行
+//
+// 锁由操作系统管理（Linux 和 macOS 上是 flock，Windows 上是 LockFileEx），
+// 程序异常退出时自动释放。锁文件本身一直保留，删除它会让正在等待的程序锁住不同的文件。
+// 同一个进程中分别打开的两个锁也会互相等待，所以测试可以用几个线程模拟几个程序。
+
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+// 持有期间其他程序拿不到同一个锁，离开作用域时释放
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    // 锁住 path 旁边的锁文件，其他程序持有锁时等待
+    pub fn exclusive(path: &Path) -> Result<FileLock, String> {
+        let lock_path = lock_path(path);
+        if let Some(parent) = lock_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建目录 {}: {}", parent.display(), e))?;
+        }
+        let error = |e: std::io::Error| format!("无法锁定 {}: {}", lock_path.display(), e);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(error)?;
+        file.lock().map_err(error)?;
+        Ok(FileLock { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // 关闭文件时操作系统也会释放锁，这里只是尽早释放
+        let _ = self.file.unlock();
+    }
+}
+
+// 数据文件对应的锁文件，例如 students.json -> students.json.lock
+pub fn lock_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.lock", name))
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = std::env::temp_dir().join(format!("student_lock_{}", std::process::id()));
+        let path = dir.join("students.json");
+        assert_eq!(lock_path(&path), dir.join("students.json.lock"));
+
+        let lock = FileLock::exclusive(&path).unwrap();
+        let acquired = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let (path, acquired) = (path.clone(), Arc::clone(&acquired));
+            thread::spawn(move || {
+                let _lock = FileLock::exclusive(&path).unwrap();
+                acquired.store(true, Ordering::SeqCst);
+            })
+        };
+        // 第一个锁释放之前第二个锁一直在等待
+        thread::sleep(Duration::from_millis(100));
+        assert!(!acquired.load(Ordering::SeqCst));
+        drop(lock);
+        waiter.join().unwrap();
+        assert!(acquired.load(Ordering::SeqCst));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
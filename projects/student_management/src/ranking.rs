@@ -0,0 +1,194 @@
+// 排名：按学分加权平均分或单门课程成绩从高到低排名，可以只在一个班级内排名
+//
+// 同分的学生名次相同。标准竞赛排名在同分之后跳过名次（1, 2, 2, 4），
+// 密集排名不跳过（1, 2, 2, 3）。没有成绩的学生不参与排名，单独列出，
+// 而不是像 average_grade 那样按 0 分排在最后。
+
+use crate::course::Course;
+use crate::grading;
+use crate::student::Student;
+
+// 分数相差小于这个值时视为同分，和筛选条件中比较相等的误差相同
+const TIE_EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankStyle {
+    // 1, 2, 2, 4
+    Competition,
+    // 1, 2, 2, 3
+    Dense,
+}
+
+// 排名依据
+#[derive(Debug, Clone, PartialEq)]
+pub enum Basis {
+    // 学分加权的百分制平均分
+    Overall,
+    // 一门课程的原始分数，保存课程代码
+    Course(String),
+}
+
+impl Basis {
+    fn score(&self, student: &Student, courses: &[Course]) -> Option<f32> {
+        match self {
+            Basis::Overall => grading::weighted_average(student, courses),
+            Basis::Course(code) => student.current_grade(code),
+        }
+    }
+
+    // 参与这种排名的学生：总排名是所有学生，课程排名是选修了或有这门课成绩的学生
+    fn includes(&self, student: &Student) -> bool {
+        match self {
+            Basis::Overall => true,
+            Basis::Course(code) => student.is_enrolled(code) || student.grades.contains_key(code),
+        }
+    }
+}
+
+// 只显示前 N 名或后 N 名，和第 N 名同分的学生一起显示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Top(usize),
+    Bottom(usize),
+}
+
+// 排名中的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranked {
+    pub rank: usize,
+    pub student: Student,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    // 按名次排列，同分的学生按 ID 排列
+    pub entries: Vec<Ranked>,
+    // 参与范围内但没有成绩的学生，按 ID 排列
+    pub ungraded: Vec<Student>,
+}
+
+impl Ranking {
+    // class 为 Some 时只在这个班级的学生中排名
+    pub fn compute(students: &[Student], courses: &[Course], basis: &Basis, class: Option<&str>, style: RankStyle) -> Ranking {
+        let mut scored = Vec::new();
+        let mut ungraded = Vec::new();
+        for student in students {
+            if !basis.includes(student) || class.is_some_and(|class| student.class.as_deref() != Some(class)) {
+                continue;
+            }
+            match basis.score(student, courses) {
+                Some(score) => scored.push((student.clone(), score)),
+                None => ungraded.push(student.clone()),
+            }
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.id.cmp(&b.0.id)));
+        ungraded.sort_by_key(|student| student.id);
+
+        let mut entries: Vec<Ranked> = Vec::with_capacity(scored.len());
+        for (index, (student, score)) in scored.into_iter().enumerate() {
+            let rank = match entries.last() {
+                Some(previous) if is_tie(previous.score, score) => previous.rank,
+                Some(previous) => match style {
+                    RankStyle::Competition => index + 1,
+                    RankStyle::Dense => previous.rank + 1,
+                },
+                None => 1,
+            };
+            entries.push(Ranked { rank, student, score });
+        }
+        Ranking { entries, ungraded }
+    }
+
+    // 前 N 名或后 N 名，边界上同分的学生都保留
+    pub fn limit(&self, limit: Limit) -> &[Ranked] {
+        let entries = &self.entries;
+        match limit {
+            Limit::Top(0) | Limit::Bottom(0) => &[],
+            Limit::Top(count) if count < entries.len() => {
+                let last = entries[count - 1].score;
+                let end = count + entries[count..].iter().take_while(|entry| is_tie(entry.score, last)).count();
+                &entries[..end]
+            }
+            Limit::Bottom(count) if count < entries.len() => {
+                let start = entries.len() - count;
+                let first = entries[start].score;
+                let ties = entries[..start].iter().rev().take_while(|entry| is_tie(entry.score, first)).count();
+                &entries[start - ties..]
+            }
+            _ => entries,
+        }
+    }
+}
+
+fn is_tie(a: f32, b: f32) -> bool {
+    (a - b).abs() < TIE_EPSILON
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(id: u32, class: &str, score: Option<f32>) -> Student {
+        let mut student = Student::new(id, format!("学生{}", id), 20);
+        if !class.is_empty() {
+            student.class = Some(String::from(class));
+        }
+        student.enroll(String::from("MATH"));
+        if let Some(score) = score {
+            student.add_grade(String::from("MATH"), score);
+        }
+        student
+    }
+
+    fn ranks(ranking: &[Ranked]) -> Vec<(u32, usize)> {
+        ranking.iter().map(|entry| (entry.student.id, entry.rank)).collect()
+    }
+
+    fn students() -> Vec<Student> {
+        vec![
+            student(1, "一班", Some(80.0)),
+            student(2, "一班", Some(95.0)),
+            student(3, "二班", Some(80.0)),
+            student(4, "二班", None),
+            student(5, "一班", Some(70.0)),
+            student(6, "", Some(80.0)),
+        ]
+    }
+
+    #[test]
+    fn test_competition_and_dense() {
+        let courses = vec![Course::new("MATH", "数学", 4.0, 100.0).unwrap()];
+        let students = students();
+        let competition = Ranking::compute(&students, &courses, &Basis::Overall, None, RankStyle::Competition);
+        assert_eq!(ranks(&competition.entries), vec![(2, 1), (1, 2), (3, 2), (6, 2), (5, 5)]);
+        // 没有成绩的学生不参与排名
+        assert_eq!(competition.ungraded.iter().map(|s| s.id).collect::<Vec<_>>(), vec![4]);
+
+        let dense = Ranking::compute(&students, &courses, &Basis::Course(String::from("MATH")), None, RankStyle::Dense);
+        assert_eq!(ranks(&dense.entries), vec![(2, 1), (1, 2), (3, 2), (6, 2), (5, 3)]);
+
+        let class = Ranking::compute(&students, &courses, &Basis::Overall, Some("一班"), RankStyle::Competition);
+        assert_eq!(ranks(&class.entries), vec![(2, 1), (1, 2), (5, 3)]);
+        assert!(class.ungraded.is_empty());
+
+        // 没有选修的课程不把学生算作没有成绩
+        let art = Ranking::compute(&students, &courses, &Basis::Course(String::from("ART")), None, RankStyle::Dense);
+        assert!(art.entries.is_empty() && art.ungraded.is_empty());
+    }
+
+    #[test]
+    fn test_limit_keeps_ties() {
+        let courses = vec![Course::new("MATH", "数学", 4.0, 100.0).unwrap()];
+        let ranking = Ranking::compute(&students(), &courses, &Basis::Overall, None, RankStyle::Competition);
+        assert_eq!(ranks(ranking.limit(Limit::Top(1))), vec![(2, 1)]);
+        // 第 2 名有三个人同分，全部显示
+        assert_eq!(ranks(ranking.limit(Limit::Top(2))).len(), 4);
+        assert_eq!(ranks(ranking.limit(Limit::Bottom(1))), vec![(5, 5)]);
+        assert_eq!(ranks(ranking.limit(Limit::Bottom(2))).len(), 4);
+        assert_eq!(ranking.limit(Limit::Top(10)).len(), 5);
+        assert!(ranking.limit(Limit::Top(0)).is_empty());
+        assert!(ranking.limit(Limit::Bottom(0)).is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+// 成绩单：把学生信息、课程成绩表、平均分、GPA 和班级排名填入 HTML 或 Markdown 模板
+//
+// 模板是普通文本，其中的 {{占位符}} 在生成时替换：
+//
+//     {{id}} {{name}} {{age}} {{class}} {{average}} {{gpa}} {{scale}} {{rank}} {{date}} {{courses}}
+//
+// {{courses}} 是按模板格式生成好的课程成绩表，其余占位符的值会按格式转义（HTML 转义 < > & 等，
+// Markdown 转义 * _ | 等），学生姓名中的特殊字符不会破坏版面。学校可以用 --template 换成自己的模板，
+// 模板中出现未知的占位符或没有闭合的 {{ 时报错，而不是原样输出。
+
+use std::fs;
+use std::path::Path;
+
+use crate::course::Course;
+use crate::grading::{self, GradingConfig};
+use crate::history::{self, Clock};
+use crate::ranking::{Basis, RankStyle, Ranking};
+use crate::student::Student;
+
+// 模板中可以使用的占位符
+pub const PLACEHOLDERS: [&str; 10] = ["id", "name", "age", "class", "average", "gpa", "scale", "rank", "date", "courses"];
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>成绩单 - {{name}}</title>
+<style>
+  body { font-family: sans-serif; max-width: 800px; margin: 2em auto; color: #222; }
+  h1 { text-align: center; }
+  table { border-collapse: collapse; width: 100%; margin: 1em 0; }
+  th, td { border: 1px solid #999; padding: 4px 8px; text-align: left; }
+  th { background: #eee; }
+  @media print { body { margin: 0; } }
+</style>
+</head>
+<body>
+<h1>成绩单</h1>
+<p>学号：{{id}}　姓名：{{name}}　年龄：{{age}}　班级：{{class}}</p>
+{{courses}}
+<p>平均分（学分加权）：{{average}}　GPA（{{scale}} 制）：{{gpa}}</p>
+<p>班级排名：{{rank}}</p>
+<p>生成日期：{{date}}</p>
+</body>
+</html>
+"#;
+
+const MARKDOWN_TEMPLATE: &str = "# 成绩单
+
+- 学号：{{id}}
+- 姓名：{{name}}
+- 年龄：{{age}}
+- 班级：{{class}}
+
+{{courses}}
+
+- 平均分（学分加权）：{{average}}
+- GPA（{{scale}} 制）：{{gpa}}
+- 班级排名：{{rank}}
+
+生成日期：{{date}}
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> Result<ReportFormat, String> {
+        match name {
+            "html" => Ok(ReportFormat::Html),
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            _ => Err(format!("未知的成绩单格式: {}（可选 html、md）", name)),
+        }
+    }
+
+    // 批量生成时的文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match (self, c) {
+                (ReportFormat::Html, '&') => escaped.push_str("&amp;"),
+                (ReportFormat::Html, '<') => escaped.push_str("&lt;"),
+                (ReportFormat::Html, '>') => escaped.push_str("&gt;"),
+                (ReportFormat::Html, '"') => escaped.push_str("&quot;"),
+                (ReportFormat::Html, '\'') => escaped.push_str("&#39;"),
+                (ReportFormat::Markdown, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#') => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+// 检查过占位符的模板
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    format: ReportFormat,
+    text: String,
+}
+
+impl Template {
+    // 内置模板
+    pub fn builtin(format: ReportFormat) -> Template {
+        let text = match format {
+            ReportFormat::Html => HTML_TEMPLATE,
+            ReportFormat::Markdown => MARKDOWN_TEMPLATE,
+        };
+        Template { format, text: text.to_string() }
+    }
+
+    pub fn parse(format: ReportFormat, text: &str) -> Result<Template, String> {
+        let template = Template { format, text: text.to_string() };
+        template.fill(|name| PLACEHOLDERS.contains(&name).then(String::new))?;
+        Ok(template)
+    }
+
+    pub fn load(format: ReportFormat, path: &Path) -> Result<Template, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取模板 {}: {}", path.display(), e))?;
+        Template::parse(format, &text).map_err(|e| format!("模板 {}: {}", path.display(), e))
+    }
+
+    pub fn format(&self) -> ReportFormat {
+        self.format
+    }
+
+    pub fn render(&self, card: &ReportCard) -> String {
+        self.fill(|name| card.value(name, self.format))
+            .expect("占位符在读取模板时已经检查过")
+    }
+
+    // 依次替换 {{占位符}}，value 对未知的占位符返回 None
+    fn fill(&self, value: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+        let mut output = String::with_capacity(self.text.len());
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| String::from("模板中有没有闭合的 {{"))?;
+            let name = after[..end].trim();
+            let text = value(name).ok_or_else(|| {
+                format!("模板中有未知的占位符 {{{{{}}}}}（可用的占位符: {}）", name, PLACEHOLDERS.join("、"))
+            })?;
+            output.push_str(&text);
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+// 成绩单上的一门课程
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseLine {
+    pub code: String,
+    pub name: String,
+    pub credits: f32,
+    pub max_score: f32,
+    pub pass_fail: bool,
+    pub score: Option<f32>,
+    // 等级，通过制课程为 P/F
+    pub letter: Option<String>,
+}
+
+// 生成成绩单需要的全部数据
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportCard {
+    pub student: Student,
+    pub courses: Vec<CourseLine>,
+    pub average: Option<f32>,
+    pub gpa: Option<f32>,
+    pub scale: String,
+    // 班级中的名次和参与排名的人数，未分班或没有成绩时为 None
+    pub rank: Option<(usize, usize)>,
+    // 生成时间（Unix 时间戳）
+    pub generated: u64,
+}
+
+impl ReportCard {
+    // students 是全部学生，用来计算班级排名
+    pub fn build(
+        student: &Student,
+        students: &[Student],
+        courses: &[Course],
+        grading: &GradingConfig,
+        clock: &dyn Clock,
+    ) -> ReportCard {
+        let lines = student
+            .enrollments
+            .iter()
+            .map(|code| {
+                let course = courses.iter().find(|course| &course.code == code);
+                let score = student.current_grade(code);
+                CourseLine {
+                    code: code.clone(),
+                    name: course.map(|course| course.name.clone()).unwrap_or_default(),
+                    credits: course.map(|course| course.credits).unwrap_or(0.0),
+                    max_score: course.map(|course| course.max_score).unwrap_or(0.0),
+                    pass_fail: course.is_some_and(|course| course.pass_fail),
+                    score,
+                    letter: course.zip(score).map(|(course, score)| grading.letter(course, score)),
+                }
+            })
+            .collect();
+        let rank = student.class.as_deref().and_then(|class| {
+            let ranking = Ranking::compute(students, courses, &Basis::Overall, Some(class), RankStyle::Competition);
+            ranking
+                .entries
+                .iter()
+                .find(|entry| entry.student.id == student.id)
+                .map(|entry| (entry.rank, ranking.entries.len()))
+        });
+        ReportCard {
+            student: student.clone(),
+            courses: lines,
+            average: grading::weighted_average(student, courses),
+            gpa: grading.gpa(student, courses),
+            scale: grading.default().name.clone(),
+            rank,
+            generated: clock.now(),
+        }
+    }
+
+    // 占位符的值，未知的占位符返回 None
+    fn value(&self, name: &str, format: ReportFormat) -> Option<String> {
+        let student = &self.student;
+        let text = match name {
+            "courses" => return Some(self.course_table(format)),
+            "id" => student.id.to_string(),
+            "name" => student.name.clone(),
+            "age" => student.age.to_string(),
+            "class" => student.class.clone().unwrap_or_else(|| String::from("未分班")),
+            "average" => optional(self.average),
+            "gpa" => optional(self.gpa),
+            "scale" => self.scale.clone(),
+            "rank" => match (&student.class, self.rank) {
+                (None, _) => String::from("未分班"),
+                (Some(_), None) => String::from("没有成绩，未参与排名"),
+                (Some(_), Some((rank, total))) => format!("第 {} 名（共 {} 人）", rank, total),
+            },
+            "date" => history::format_timestamp(self.generated)[..10].to_string(),
+            _ => return None,
+        };
+        Some(format.escape(&text))
+    }
+
+    fn course_table(&self, format: ReportFormat) -> String {
+        if self.courses.is_empty() {
+            return format.escape("尚未选修课程");
+        }
+        let header = ["课程代码", "课程名称", "学分", "成绩", "等级"];
+        let rows: Vec<[String; 5]> = self
+            .courses
+            .iter()
+            .map(|line| {
+                let credits = if line.pass_fail {
+                    format!("{}（通过制）", line.credits)
+                } else {
+                    line.credits.to_string()
+                };
+                let score = match line.score {
+                    Some(score) => format!("{:.1} / {}", score, line.max_score),
+                    None => String::from("尚无成绩"),
+                };
+                let letter = line.letter.clone().unwrap_or_else(|| String::from("-"));
+                [line.code.clone(), line.name.clone(), credits, score, letter].map(|cell| format.escape(&cell))
+            })
+            .collect();
+        match format {
+            ReportFormat::Html => {
+                let mut table = format!("<table>\n  <tr><th>{}</th></tr>", header.join("</th><th>"));
+                for row in rows {
+                    table.push_str(&format!("\n  <tr><td>{}</td></tr>", row.join("</td><td>")));
+                }
+                table.push_str("\n</table>");
+                table
+            }
+            ReportFormat::Markdown => {
+                let mut table = format!("| {} |\n|{}", header.join(" | "), " --- |".repeat(header.len()));
+                for row in rows {
+                    table.push_str(&format!("\n| {} |", row.join(" | ")));
+                }
+                table
+            }
+        }
+    }
+}
+
+fn optional(value: Option<f32>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => String::from("-"),
+    }
+}
+
+// 批量生成时的文件名："ID-姓名.扩展名"，姓名中不能用在文件名里的字符换成 _
+pub fn file_name(student: &Student, format: ReportFormat) -> String {
+    let name: String = student
+        .name
+        .chars()
+        .map(|c| if c.is_control() || c.is_whitespace() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    format!("{}-{}.{}", student.id, name, format.extension())
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+
+    fn class_of_three() -> (Vec<Course>, Vec<Student>) {
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        let mut pe = Course::new("PE", "体育", 1.0, 100.0).unwrap();
+        pe.pass_fail = true;
+        let students = [("张<三>", 85.0), ("李四", 95.0), ("王五", 70.0)]
+            .iter()
+            .enumerate()
+            .map(|(index, (name, score))| {
+                let mut student = Student::new(index as u32 + 1, name.to_string(), 20);
+                student.class = Some(String::from("一班"));
+                student.enroll(math.code.clone());
+                student.enroll(pe.code.clone());
+                student.add_grade(math.code.clone(), *score);
+                student
+            })
+            .collect();
+        (vec![math, pe], students)
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let (courses, students) = class_of_three();
+        let clock = ManualClock::new(1_709_281_800);
+        let card = ReportCard::build(&students[0], &students, &courses, &GradingConfig::builtin(), &clock);
+        assert_eq!(card.rank, Some((2, 3)));
+        let report = Template::builtin(ReportFormat::Markdown).render(&card);
+        assert!(report.contains("- 姓名：张\\<三\\>"));
+        assert!(report.contains("| MATH101 | 数学 | 4 | 85.0 / 100 | B |"));
+        assert!(report.contains("| PE | 体育 | 1（通过制） | 尚无成绩 | - |"));
+        assert!(report.contains("- 平均分（学分加权）：85.00"));
+        assert!(report.contains("- GPA（4.0 制）：3.00"));
+        assert!(report.contains("- 班级排名：第 2 名（共 3 人）"));
+        assert!(report.contains("生成日期：2024-03-01"));
+    }
+
+    #[test]
+    fn test_html_report_escapes_values() {
+        let (courses, mut students) = class_of_three();
+        students[2].class = None;
+        let clock = ManualClock::default();
+        let grading = GradingConfig::builtin();
+        let report = Template::builtin(ReportFormat::Html).render(&ReportCard::build(&students[0], &students, &courses, &grading, &clock));
+        assert!(report.contains("<title>成绩单 - 张&lt;三&gt;</title>"));
+        assert!(report.contains("<tr><td>MATH101</td><td>数学</td><td>4</td><td>85.0 / 100</td><td>B</td></tr>"));
+        // 王五不在班级中，张三排第 2 名（共 2 人）
+        assert!(report.contains("班级排名：第 2 名（共 2 人）"));
+        let wang = Template::builtin(ReportFormat::Html).render(&ReportCard::build(&students[2], &students, &courses, &grading, &clock));
+        assert!(wang.contains("班级排名：未分班"));
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let (courses, students) = class_of_three();
+        let template = Template::parse(ReportFormat::Markdown, "{{ name }}：{{average}}，{{rank}}").unwrap();
+        let card = ReportCard::build(&students[1], &students, &courses, &GradingConfig::builtin(), &ManualClock::default());
+        assert_eq!(template.render(&card), "李四：95.00，第 1 名（共 3 人）");
+
+        let error = Template::parse(ReportFormat::Html, "{{school}}").unwrap_err();
+        assert!(error.contains("{{school}}") && error.contains("可用的占位符"), "{}", error);
+        assert!(Template::parse(ReportFormat::Html, "{{name").unwrap_err().contains("没有闭合"));
+
+        assert_eq!(file_name(&students[0], ReportFormat::Html), "1-张_三_.html");
+    }
+}
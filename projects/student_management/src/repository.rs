@@ -0,0 +1,600 @@
+// 学生数据的存储接口
+//
+// 菜单流程只依赖 StudentRepository，数据具体保存在哪里由后端决定：
+// - memory: StudentManagement，只保存在内存中
+// - json:   JsonFileRepository，每次修改后原子地写入 JSON 文件
+// - sqlite: SqliteRepository，嵌入式 SQLite 数据库文件
+//
+// 多个线程共用一份内存数据时使用 SharedStudents（shared 模块），它同样实现了 StudentRepository。
+
+use std::path::{Path, PathBuf};
+
+use crate::course::{self, Course};
+use crate::sqlite::{self, SqliteRepository};
+use crate::storage::{self, JsonFileRepository};
+use crate::student::{Student, StudentManagement};
+
+// 学生和课程数据的增删改查；仓库可以交给其他线程，例如放在锁中由 API 服务器的多个线程共用
+pub trait StudentRepository: Send {
+    // 按 ID 查找学生，不存在时返回 None
+    fn get(&self, id: u32) -> Result<Option<Student>, String>;
+
+    // 添加学生并返回分配的 ID，删除过的 ID 不会被重新使用
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String>;
+
+    // 用新的记录替换同 ID 的学生（包括选课和全部成绩），学生不存在时返回错误；
+    // 选修了未登记的课程、为没有选修的课程记录成绩或分数超过满分时也返回错误
+    fn update(&mut self, student: Student) -> Result<(), String>;
+
+    // 读取学生、用 change 修改后写回，返回修改后的记录；学生不存在或 change 返回错误时不做任何修改。
+    // 先 get 再 update 时，其他线程或程序在这之间的修改会被覆盖；modify 作为一次不可分割的操作，
+    // 同时修改同一个学生时后一次修改在前一次的结果上进行。默认实现只适用于独占数据的后端
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        let mut student = self.get(id)?.ok_or_else(|| format!("未找到ID为 {} 的学生", id))?;
+        change(&mut student)?;
+        self.update(student.clone())?;
+        Ok(student)
+    }
+
+    // 删除学生，返回是否真的删除了记录
+    fn remove(&mut self, id: u32) -> Result<bool, String>;
+
+    // 按原来的 ID 放回删除过的学生（包括选课、成绩和历史），用于撤销删除；
+    // ID 正在使用时返回错误，检查规则与 update 相同
+    fn restore(&mut self, student: Student) -> Result<(), String>;
+
+    // 在一次不可分割的写入中执行 change 里的所有修改：change 返回错误时一项修改都不保留，
+    // 其他线程或程序也看不到只做了一半的结果。批量导入等要么全部写入、要么都不写入的操作使用
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String>;
+
+    // 按 ID 排序的所有学生
+    fn list(&self) -> Result<Vec<Student>, String>;
+
+    // 按 ID 排序的符合条件的学生
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        Ok(self.list()?.into_iter().filter(|student| query.matches(student)).collect())
+    }
+
+    // 登记新课程，课程代码或名称与已有课程重复时返回错误
+    fn insert_course(&mut self, course: Course) -> Result<(), String>;
+
+    // 删除课程，返回是否真的删除了；还有学生选修时返回错误
+    fn remove_course(&mut self, code: &str) -> Result<bool, String>;
+
+    // 按课程代码排序的所有课程
+    fn list_courses(&self) -> Result<Vec<Course>, String>;
+
+    // 按课程代码（不区分大小写）或课程名称查找课程
+    fn find_course(&self, text: &str) -> Result<Option<Course>, String> {
+        Ok(course::find(&self.list_courses()?, text).cloned())
+    }
+}
+
+// 查询条件，所有设置了的条件都必须满足
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    // 姓名包含的文本，不区分大小写
+    pub name_contains: Option<String>,
+    pub min_age: Option<u8>,
+    pub max_age: Option<u8>,
+    // 有这门课程（课程代码）的成绩
+    pub course: Option<String>,
+}
+
+impl Query {
+    pub fn matches(&self, student: &Student) -> bool {
+        if let Some(text) = &self.name_contains {
+            if !student.name.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+        if self.min_age.is_some_and(|min| student.age < min) {
+            return false;
+        }
+        if self.max_age.is_some_and(|max| student.age > max) {
+            return false;
+        }
+        if let Some(code) = &self.course {
+            if !student.grades.contains_key(code) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// 存储后端的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Memory,
+    Json,
+    Sqlite,
+}
+
+impl Backend {
+    pub fn parse(name: &str) -> Result<Backend, String> {
+        match name.to_lowercase().as_str() {
+            "memory" => Ok(Backend::Memory),
+            "json" => Ok(Backend::Json),
+            "sqlite" => Ok(Backend::Sqlite),
+            _ => Err(format!("未知的存储后端: {}（可选 memory、json、sqlite）", name)),
+        }
+    }
+
+    // 后端默认使用的数据文件：JSON 由 STUDENT_DATA_FILE 指定，SQLite 由 STUDENT_DATABASE_FILE 指定，
+    // 两种格式不会读写同一个文件
+    pub fn default_path(&self) -> Option<PathBuf> {
+        match self {
+            Backend::Memory => None,
+            Backend::Json => Some(storage::default_path()),
+            Backend::Sqlite => Some(sqlite::default_path()),
+        }
+    }
+
+    // 打开指定的数据文件，内存后端忽略路径
+    pub fn open(&self, path: &Path) -> Result<Box<dyn StudentRepository>, String> {
+        Ok(match self {
+            Backend::Memory => Box::new(StudentManagement::new()),
+            Backend::Json => Box::new(JsonFileRepository::open(path)?),
+            Backend::Sqlite => Box::new(SqliteRepository::open(path)?),
+        })
+    }
+
+    pub fn open_default(&self) -> Result<Box<dyn StudentRepository>, String> {
+        match self.default_path() {
+            Some(path) => self.open(&path),
+            None => Ok(Box::new(StudentManagement::new())),
+        }
+    }
+}
+
+// 所有后端共用的一致性测试，每个后端的测试模块用自己的构造函数调用 run
+#[cfg(test)]
+pub(crate) mod conformance {
+    use super::*;
+    use crate::gradebook::{Assessment, Category};
+    use crate::history::ManualClock;
+
+    // 依次在新建的空仓库上运行所有检查
+    pub(crate) fn run(open_empty: &mut dyn FnMut() -> Box<dyn StudentRepository>) {
+        insert_and_get(open_empty().as_mut());
+        ids_are_not_reused(open_empty().as_mut());
+        update_replaces_record(open_empty().as_mut());
+        update_missing_student_fails(open_empty().as_mut());
+        update_checks_courses(open_empty().as_mut());
+        modify_changes_current_record(open_empty().as_mut());
+        restore_keeps_id(open_empty().as_mut());
+        ids_run_out(open_empty().as_mut());
+        batch_is_all_or_nothing(open_empty().as_mut());
+        course_registry(open_empty().as_mut());
+        gradebook(open_empty().as_mut());
+        list_is_sorted_by_id(open_empty().as_mut());
+        query_filters(open_empty().as_mut());
+    }
+
+    // 文件后端：关闭后重新打开同一个文件，数据和下一个 ID 都应该保留
+    pub(crate) fn check_reopen(open: &mut dyn FnMut() -> Box<dyn StudentRepository>) {
+        let clock = ManualClock::default();
+        let mut repository = open();
+        let math = add_course(repository.as_mut(), "MATH101", "数学", 100.0);
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+        let mut student = repository.get(first).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        student.record_grade(&math, 92.5, "", &clock).unwrap();
+        repository.update(student.clone()).unwrap();
+        let second = repository.insert(String::from("李四"), 21).unwrap();
+        assert!(repository.remove(second).unwrap());
+        drop(repository);
+
+        let mut reopened = open();
+        assert_eq!(reopened.list().unwrap(), vec![student]);
+        assert_eq!(reopened.list_courses().unwrap(), vec![math]);
+        assert_eq!(reopened.insert(String::from("王五"), 19).unwrap(), second + 1);
+    }
+
+    // 几个写入者同时使用同一份数据：每个线程用 open 打开自己的仓库（对文件后端来说和几个程序同时运行一样），
+    // 各自添加学生，并在同一个学生的记录上追加修改；任何一次修改被覆盖都会让最后的结果对不上
+    pub(crate) fn check_concurrent_writers(open: &(dyn Fn() -> Box<dyn StudentRepository> + Sync), writers: usize, rounds: usize) {
+        let mut repository = open();
+        let math = add_course(repository.as_mut(), "MATH101", "数学", 100.0);
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        repository
+            .modify(id, &mut |student| {
+                student.enroll(math.code.clone());
+                Ok(())
+            })
+            .unwrap();
+        drop(repository);
+
+        std::thread::scope(|scope| {
+            for writer in 0..writers {
+                let math = &math;
+                scope.spawn(move || {
+                    let clock = ManualClock::default();
+                    let mut repository = open();
+                    let letter = char::from(b'a' + writer as u8);
+                    for round in 0..rounds {
+                        repository.insert(format!("{}{}", letter, round), 20).unwrap();
+                        repository
+                            .modify(id, &mut |student| {
+                                student.name.push(letter);
+                                // 分数和当前成绩不同，每次都会记入历史
+                                let score = if student.current_grade(&math.code) == Some(60.0) { 70.0 } else { 60.0 };
+                                student.record_grade(math, score, "", &clock)
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let repository = open();
+        let ids: Vec<u32> = repository.list().unwrap().iter().map(|student| student.id).collect();
+        let expected: Vec<u32> = (1..=(1 + writers * rounds) as u32).collect();
+        assert_eq!(ids, expected);
+        let student = repository.get(id).unwrap().unwrap();
+        for writer in 0..writers {
+            let letter = char::from(b'a' + writer as u8);
+            assert_eq!(student.name.chars().filter(|c| *c == letter).count(), rounds);
+        }
+        assert_eq!(student.grade_history(&math.code).len(), writers * rounds);
+    }
+
+    fn add_course(repository: &mut dyn StudentRepository, code: &str, name: &str, max_score: f32) -> Course {
+        let course = Course::new(code, name, 4.0, max_score).unwrap();
+        repository.insert_course(course.clone()).unwrap();
+        course
+    }
+
+    fn insert_and_get(repository: &mut dyn StudentRepository) {
+        assert_eq!(repository.get(1).unwrap(), None);
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(repository.get(id).unwrap(), Some(Student::new(id, String::from("张三"), 20)));
+    }
+
+    fn ids_are_not_reused(repository: &mut dyn StudentRepository) {
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+        let second = repository.insert(String::from("李四"), 21).unwrap();
+        assert!(repository.remove(second).unwrap());
+        assert!(!repository.remove(second).unwrap());
+        assert_eq!(repository.get(second).unwrap(), None);
+        assert_eq!(repository.insert(String::from("王五"), 19).unwrap(), second + 1);
+        assert!(repository.get(first).unwrap().is_some());
+    }
+
+    fn update_replaces_record(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let math = add_course(repository, "MATH101", "数学", 100.0);
+        let chinese = add_course(repository, "CHN101", "语文", 100.0);
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        let mut student = repository.get(id).unwrap().unwrap();
+        student.name = String::from("张三丰");
+        student.age = 21;
+        student.class = Some(String::from("一班"));
+        student.enroll(math.code.clone());
+        student.enroll(chinese.code.clone());
+        student.record_grade(&math, 90.0, "", &clock).unwrap();
+        student.record_grade(&chinese, 80.5, "", &clock).unwrap();
+        repository.update(student.clone()).unwrap();
+        assert_eq!(repository.get(id).unwrap(), Some(student.clone()));
+
+        // 修改成绩时原来的分数保存在历史中
+        clock.set(1_709_281_800);
+        student.record_grade(&math, 95.0, "复核", &clock).unwrap();
+        repository.update(student.clone()).unwrap();
+        let stored = repository.get(id).unwrap().unwrap();
+        assert_eq!(stored.current_grade(&math.code), Some(95.0));
+        let reasons: Vec<&str> = stored.grade_history(&math.code).iter().map(|c| c.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["录入成绩", "复核"]);
+        assert_eq!(stored.grade_history(&math.code)[1].old, Some(90.0));
+        assert_eq!(stored.grade_history(&math.code)[1].time, 1_709_281_800);
+
+        // 更新会替换全部成绩和选课，而不是合并；退选后成绩历史仍然保留
+        student.grades.remove(&math.code);
+        student.enrollments.remove(&math.code);
+        student.class = None;
+        repository.update(student.clone()).unwrap();
+        assert_eq!(repository.get(id).unwrap(), Some(student));
+    }
+
+    fn update_missing_student_fails(repository: &mut dyn StudentRepository) {
+        let error = repository.update(Student::new(42, String::from("张三"), 20)).unwrap_err();
+        assert!(error.contains("42"), "{}", error);
+        assert!(repository.list().unwrap().is_empty());
+    }
+
+    fn update_checks_courses(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let english = add_course(repository, "ENG", "英语", 150.0);
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        let original = repository.get(id).unwrap().unwrap();
+
+        // 没有选修的课程不能记录成绩
+        let mut student = original.clone();
+        student.add_grade(english.code.clone(), 90.0);
+        assert!(repository.update(student).is_err());
+
+        // 未登记的课程不能选修
+        let mut student = original.clone();
+        student.enroll(String::from("ART"));
+        assert!(repository.update(student).is_err());
+
+        // 分数不能超过课程满分
+        let mut student = original.clone();
+        student.enroll(english.code.clone());
+        student.add_grade(english.code.clone(), 151.0);
+        assert!(repository.update(student).is_err());
+        assert_eq!(repository.get(id).unwrap(), Some(original.clone()));
+
+        let mut student = original;
+        student.enroll(english.code.clone());
+        student.record_grade(&english, 140.0, "", &clock).unwrap();
+        repository.update(student.clone()).unwrap();
+        assert_eq!(repository.get(id).unwrap(), Some(student));
+    }
+
+    fn modify_changes_current_record(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let math = add_course(repository, "MATH101", "数学", 100.0);
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        let enrolled = repository.modify(id, &mut |student| {
+            student.enroll(math.code.clone());
+            Ok(())
+        });
+        assert!(enrolled.unwrap().is_enrolled(&math.code));
+
+        // 在已经保存的记录上修改，之前的选课不会丢失
+        let graded = repository.modify(id, &mut |student| student.record_grade(&math, 90.0, "", &clock)).unwrap();
+        assert_eq!(repository.get(id).unwrap(), Some(graded.clone()));
+        assert_eq!(graded.current_grade(&math.code), Some(90.0));
+
+        // change 失败或者修改后的记录不合格时不做任何修改
+        assert!(repository.modify(id, &mut |_| Err(String::from("取消"))).unwrap_err().contains("取消"));
+        let invalid = repository.modify(id, &mut |student| {
+            student.enroll(String::from("ART"));
+            Ok(())
+        });
+        assert!(invalid.is_err());
+        assert_eq!(repository.get(id).unwrap(), Some(graded));
+        assert!(repository.modify(42, &mut |_| Ok(())).unwrap_err().contains("42"));
+    }
+
+    fn restore_keeps_id(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let math = add_course(repository, "MATH101", "数学", 100.0);
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+        let second = repository.insert(String::from("李四"), 21).unwrap();
+        let mut student = repository.get(first).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        student.record_grade(&math, 88.0, "", &clock).unwrap();
+        repository.update(student.clone()).unwrap();
+
+        // 删除后按原来的 ID 放回，选课、成绩和历史都不变
+        assert!(repository.remove(first).unwrap());
+        repository.restore(student.clone()).unwrap();
+        assert_eq!(repository.get(first).unwrap(), Some(student.clone()));
+        assert!(repository.restore(student).unwrap_err().contains("已经被学生使用"));
+
+        // 放回最大的 ID 后，新学生的 ID 仍然在它之后
+        let removed = repository.get(second).unwrap().unwrap();
+        assert!(repository.remove(second).unwrap());
+        repository.restore(removed).unwrap();
+        assert_eq!(repository.insert(String::from("王五"), 19).unwrap(), second + 1);
+
+        // 放回最大的 ID 之后没有可以分配的 ID，所有后端都拒绝
+        let largest = Student::new(u32::MAX, String::from("钱七"), 23);
+        assert_eq!(repository.restore(largest).unwrap_err(), "学生 ID 超出范围");
+        assert_eq!(repository.get(u32::MAX).unwrap(), None);
+
+        // 课程不存在时不能放回
+        let mut orphan = Student::new(99, String::from("赵六"), 22);
+        orphan.enroll(String::from("ART"));
+        assert!(repository.restore(orphan).is_err());
+        assert_eq!(repository.get(99).unwrap(), None);
+    }
+
+    fn ids_run_out(repository: &mut dyn StudentRepository) {
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+        // 放回最大的学生 ID 后不再分配新的 ID，也不会绕回已有的 ID
+        repository.restore(Student::new(u32::MAX - 2, String::from("李四"), 21)).unwrap();
+        assert_eq!(repository.insert(String::from("王五"), 19).unwrap_err(), "学生 ID 已经用完");
+        assert_eq!(repository.insert(String::from("赵六"), 22).unwrap_err(), "学生 ID 已经用完");
+        let ids: Vec<u32> = repository.list().unwrap().iter().map(|student| student.id).collect();
+        assert_eq!(ids, vec![first, u32::MAX - 2]);
+        assert_eq!(repository.restore(Student::new(u32::MAX - 1, String::from("钱七"), 23)).unwrap_err(), "学生 ID 超出范围");
+        assert_eq!(repository.get(first).unwrap().unwrap().name, "张三");
+    }
+
+    fn batch_is_all_or_nothing(repository: &mut dyn StudentRepository) {
+        let math = add_course(repository, "MATH101", "高等数学", 100.0);
+        let first = repository.insert(String::from("张三"), 20).unwrap();
+
+        // 中途失败的批量修改不留下任何修改
+        let error = repository
+            .batch(&mut |repository| {
+                repository.insert(String::from("李四"), 21)?;
+                repository.modify(first, &mut |student| {
+                    student.enroll(math.code.clone());
+                    Ok(())
+                })?;
+                repository.remove_course("MATH101")?;
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(error.contains("选修"), "{}", error);
+        let students = repository.list().unwrap();
+        assert_eq!(students.len(), 1);
+        assert!(students[0].enrollments.is_empty());
+        assert_eq!(repository.list_courses().unwrap(), vec![math.clone()]);
+
+        // 成功的批量修改全部保留，批量修改中还可以再嵌套一次
+        let mut second = 0;
+        repository
+            .batch(&mut |repository| {
+                second = repository.insert(String::from("李四"), 21)?;
+                repository.modify(first, &mut |student| {
+                    student.enroll(math.code.clone());
+                    Ok(())
+                })?;
+                let _ = repository.batch(&mut |repository| {
+                    repository.insert(String::from("王五"), 19)?;
+                    Err(String::from("放弃"))
+                });
+                Ok(())
+            })
+            .unwrap();
+        let students = repository.list().unwrap();
+        assert_eq!(students.iter().map(|student| student.id).collect::<Vec<_>>(), vec![first, second]);
+        assert!(students[0].enrollments.contains(&math.code));
+    }
+
+    fn course_registry(repository: &mut dyn StudentRepository) {
+        let math = add_course(repository, "MATH101", "数学", 100.0);
+        let art = add_course(repository, "art", "美术", 50.0);
+        assert_eq!(art.code, "ART");
+        assert_eq!(repository.list_courses().unwrap(), vec![art.clone(), math.clone()]);
+        assert!(repository.insert_course(Course::new("math101", "高数", 4.0, 100.0).unwrap()).is_err());
+        assert!(repository.insert_course(Course::new("MATH102", "数学", 4.0, 100.0).unwrap()).is_err());
+        assert_eq!(repository.find_course(" math101").unwrap(), Some(math.clone()));
+        assert_eq!(repository.find_course("美术").unwrap(), Some(art.clone()));
+        assert_eq!(repository.find_course("Math").unwrap(), None);
+
+        // 通过制标记也要保存下来
+        let mut pe = Course::new("PE", "体育", 1.0, 100.0).unwrap();
+        pe.pass_fail = true;
+        repository.insert_course(pe.clone()).unwrap();
+        assert_eq!(repository.find_course("体育").unwrap(), Some(pe));
+        assert!(repository.remove_course("PE").unwrap());
+
+        // 有学生选修的课程不能删除
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        let mut student = repository.get(id).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        repository.update(student).unwrap();
+        assert!(repository.remove_course("MATH101").unwrap_err().contains("1 名学生"));
+        assert!(repository.remove_course("art").unwrap());
+        assert!(!repository.remove_course("ART").unwrap());
+
+        // 删除学生后课程可以删除
+        repository.remove(id).unwrap();
+        assert!(repository.remove_course("MATH101").unwrap());
+        assert!(repository.list_courses().unwrap().is_empty());
+    }
+
+    fn gradebook(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let categories = vec![
+            Category::parse("作业=40,drop=1,late=10").unwrap(),
+            Category::parse("考试=60").unwrap(),
+        ];
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap().with_categories(categories).unwrap();
+        repository.insert_course(math.clone()).unwrap();
+        assert_eq!(repository.find_course("数学").unwrap(), Some(math.clone()));
+        let unbalanced = vec![Category::parse("作业=40").unwrap()];
+        let mut art = Course::new("ART", "美术", 1.0, 100.0).unwrap();
+        art.categories = unbalanced;
+        assert!(repository.insert_course(art).unwrap_err().contains("100"));
+
+        let id = repository.insert(String::from("张三"), 20).unwrap();
+        let mut student = repository.get(id).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        for (name, category, score, days_late) in [("作业2", "作业", 9.0, 1), ("作业1", "作业", 6.0, 0), ("期中", "考试", 75.0, 0)] {
+            let max_score = if category == "作业" { 10.0 } else { 100.0 };
+            let assessment = Assessment::new(name, category, score, max_score, days_late).unwrap();
+            student.record_assessment(&math, assessment, &clock).unwrap();
+        }
+        repository.update(student.clone()).unwrap();
+        // 评估按记录顺序保存
+        assert_eq!(repository.get(id).unwrap(), Some(student.clone()));
+        assert!((student.grades["MATH101"] - (80.0 * 0.4 + 75.0 * 0.6)).abs() < 1e-3);
+
+        // 评估的类别必须属于课程，课程成绩必须与评估一致
+        let mut invalid = student.clone();
+        invalid.assessments.get_mut("MATH101").unwrap()[0].category = String::from("测验");
+        assert!(repository.update(invalid).is_err());
+        let mut invalid = student.clone();
+        invalid.add_grade(math.code.clone(), 100.0);
+        assert!(repository.update(invalid).is_err());
+
+        // 退选后评估和成绩一起删除
+        student.enrollments.clear();
+        student.grades.clear();
+        student.assessments.clear();
+        repository.update(student.clone()).unwrap();
+        assert_eq!(repository.get(id).unwrap(), Some(student));
+    }
+
+    fn list_is_sorted_by_id(repository: &mut dyn StudentRepository) {
+        for name in ["甲", "乙", "丙", "丁", "戊", "己", "庚", "辛", "壬", "癸", "子"] {
+            repository.insert(String::from(name), 20).unwrap();
+        }
+        repository.remove(3).unwrap();
+        let ids: Vec<u32> = repository.list().unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 4, 5, 6, 7, 8, 9, 10, 11]);
+    }
+
+    fn query_filters(repository: &mut dyn StudentRepository) {
+        let clock = ManualClock::default();
+        let math = add_course(repository, "MATH101", "数学", 100.0);
+        let alice = repository.insert(String::from("Alice"), 19).unwrap();
+        let bob = repository.insert(String::from("Bob"), 22).unwrap();
+        let alina = repository.insert(String::from("alina"), 25).unwrap();
+        let mut student = repository.get(bob).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        student.record_grade(&math, 75.0, "", &clock).unwrap();
+        repository.update(student).unwrap();
+
+        let ids = |query: Query| -> Vec<u32> {
+            repository.query(&query).unwrap().iter().map(|s| s.id).collect()
+        };
+        assert_eq!(ids(Query::default()), vec![alice, bob, alina]);
+        assert_eq!(
+            ids(Query { name_contains: Some(String::from("AL")), ..Query::default() }),
+            vec![alice, alina]
+        );
+        assert_eq!(
+            ids(Query { min_age: Some(20), max_age: Some(24), ..Query::default() }),
+            vec![bob]
+        );
+        assert_eq!(
+            ids(Query { course: Some(math.code.clone()), ..Query::default() }),
+            vec![bob]
+        );
+        assert_eq!(
+            ids(Query { name_contains: Some(String::from("a")), min_age: Some(20), ..Query::default() }),
+            vec![alina]
+        );
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(Backend::parse("SQLite").unwrap(), Backend::Sqlite);
+        assert_eq!(Backend::parse("json").unwrap(), Backend::Json);
+        assert!(Backend::parse("redis").is_err());
+        assert_eq!(Backend::Memory.default_path(), None);
+    }
+
+    #[test]
+    fn test_file_backends_use_different_paths() {
+        let json = Backend::Json.default_path().unwrap();
+        let sqlite = Backend::Sqlite.default_path().unwrap();
+        if std::env::var_os(storage::DATA_PATH_VARIABLE).is_none()
+            && std::env::var_os(sqlite::DATABASE_PATH_VARIABLE).is_none()
+        {
+            assert_eq!(json, storage::data_dir().join("students.json"));
+            assert_eq!(sqlite, storage::data_dir().join("students.db"));
+        }
+    }
+
+    #[test]
+    fn test_memory_backend_conformance() {
+        conformance::run(&mut || Box::new(StudentManagement::new()));
+    }
+}
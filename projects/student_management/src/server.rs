@@ -0,0 +1,657 @@
+// 本地 REST API：serve 子命令在 127.0.0.1 上用 JSON 提供学生、课程和成绩的增删改查，供网页前端使用
+//
+//     GET    /openapi.json                         OpenAPI 描述
+//     GET    /students                             所有学生
+//     POST   /students                             添加学生 {"name", "age", "class"}
+//     GET    /students/{id}                        一个学生（包括选课、成绩和历史）
+//     PUT    /students/{id}                        修改姓名、年龄和班级
+//     DELETE /students/{id}                        删除学生
+//     POST   /students/{id}/enrollments            选课 {"course"}
+//     GET    /students/{id}/grades                 选修课程的成绩和等级
+//     PUT    /students/{id}/grades/{course}        记录成绩 {"score", "reason"}
+//     GET    /courses                              所有课程
+//     POST   /courses                              登记课程 {"code", "name", "credits", "max_score", "pass_fail"}
+//     GET    /courses/{course}                     一门课程
+//     DELETE /courses/{course}                     删除没有学生选修的课程
+//
+// 课程可以写代码或名称（URL 中的中文按 UTF-8 百分号编码）。出错时返回 {"error": "说明"}：
+// 400 请求内容不是有效的 JSON，404 学生或课程不存在，405 路径不支持这个方法，
+// 409 与现有数据冲突（重复登记、没有选课、课程还有学生选修），413 请求内容超过 64 KiB，
+// 422 数值不合格（年龄或分数超出范围等）。
+//
+// 几个工作线程并行接收请求。普通的仓库放在一把锁中，读写时依次进行；用 bind_shared 启动时，
+// 每个请求拿一个 SharedStudents 句柄直接读写，只有修改同一个学生的请求需要互相等待。
+// 修改学生的请求都通过 modify 在最新的记录上进行，同时修改同一个学生不会丢失其中的某次修改。
+
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::cli::Context;
+use crate::course::{self, Course, DEFAULT_MAX_SCORE};
+use crate::repository::StudentRepository;
+use crate::shared::SharedStudents;
+use crate::student::{Student, AGE_RANGE};
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+// 同时处理请求的线程数
+const WORKERS: usize = 4;
+
+// 请求内容的长度上限，更长的请求不读入内存，直接返回 413
+const MAX_BODY: u64 = 64 * 1024;
+
+// 工作线程每隔这么久检查一次是否要停止
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const OPENAPI: &str = include_str!("openapi.json");
+
+// 出错时的状态码和说明
+#[derive(Debug, Clone, PartialEq)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> ApiError {
+        ApiError { status, message: message.into() }
+    }
+}
+
+// 仓库本身的错误（文件无法写入等）
+impl From<String> for ApiError {
+    fn from(message: String) -> ApiError {
+        ApiError::new(500, message)
+    }
+}
+
+// 成功时的状态码和 JSON 内容，204 没有内容
+struct Reply {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Reply {
+        Reply { status: 200, body: Some(body) }
+    }
+
+    fn created(body: Value) -> Reply {
+        Reply { status: 201, body: Some(body) }
+    }
+
+    fn no_content() -> Reply {
+        Reply { status: 204, body: None }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StudentBody {
+    name: String,
+    // 先按 u32 读取，超出范围时返回 422 而不是 400
+    age: u32,
+    #[serde(default)]
+    class: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnrollmentBody {
+    course: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GradeBody {
+    score: f32,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CourseBody {
+    code: String,
+    name: String,
+    credits: f32,
+    #[serde(default = "default_max_score")]
+    max_score: f32,
+    #[serde(default)]
+    pass_fail: bool,
+}
+
+fn default_max_score() -> f32 {
+    DEFAULT_MAX_SCORE
+}
+
+// 请求怎样共用数据
+enum Store {
+    // 普通的仓库放在一把锁中
+    Locked(Mutex<Box<dyn StudentRepository>>),
+    // 自己分细了锁的共用数据，每个请求用一个新的句柄
+    Shared(SharedStudents),
+}
+
+pub struct ApiServer {
+    http: tiny_http::Server,
+    store: Store,
+    context: Context,
+    stopping: AtomicBool,
+}
+
+impl ApiServer {
+    // 监听 127.0.0.1 上的端口，端口为 0 时由系统分配一个空闲端口
+    pub fn bind(port: u16, repository: Box<dyn StudentRepository>, context: Context) -> Result<ApiServer, String> {
+        ApiServer::listen(port, Store::Locked(Mutex::new(repository)), context)
+    }
+
+    // 与其他线程（例如后台生成成绩单的任务）共用同一份数据，请求之间不再整体加锁
+    pub fn bind_shared(port: u16, shared: SharedStudents, context: Context) -> Result<ApiServer, String> {
+        ApiServer::listen(port, Store::Shared(shared), context)
+    }
+
+    fn listen(port: u16, store: Store, context: Context) -> Result<ApiServer, String> {
+        let http = tiny_http::Server::http(("127.0.0.1", port)).map_err(|e| format!("无法监听端口 {}: {}", port, e))?;
+        Ok(ApiServer {
+            http,
+            store,
+            context,
+            stopping: AtomicBool::new(false),
+        })
+    }
+
+    // 实际监听的端口
+    pub fn port(&self) -> u16 {
+        self.http.server_addr().to_ip().map(|address| address.port()).unwrap_or(0)
+    }
+
+    // 处理请求直到调用 stop
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            for _ in 0..WORKERS {
+                scope.spawn(|| {
+                    while !self.stopping.load(Ordering::SeqCst) {
+                        match self.http.recv_timeout(POLL_INTERVAL) {
+                            Ok(Some(request)) => self.handle(request),
+                            Ok(None) => {}
+                            Err(e) => eprintln!("接收请求失败: {}", e),
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    // 让 run 在处理完正在进行的请求后返回
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    fn handle(&self, mut request: Request) {
+        let result = match read_body(&mut request) {
+            Ok(body) => match &self.store {
+                Store::Locked(repository) => {
+                    // 处理请求时出现 panic 不会让之后的请求都无法使用仓库
+                    let mut repository = repository.lock().unwrap_or_else(PoisonError::into_inner);
+                    route(request.method(), request.url(), &body, repository.as_mut(), &self.context)
+                }
+                Store::Shared(shared) => route(request.method(), request.url(), &body, &mut shared.clone(), &self.context),
+            },
+            Err(error) => Err(error),
+        };
+        let (status, body) = match result {
+            Ok(reply) => (reply.status, reply.body),
+            Err(error) => (error.status, Some(json!({ "error": error.message }))),
+        };
+        let response = match body {
+            Some(body) => Response::from_string(body.to_string())
+                .with_header(Header::from_bytes("Content-Type", "application/json; charset=utf-8").expect("有效的响应头")),
+            None => Response::from_string(String::new()),
+        };
+        if let Err(e) = request.respond(response.with_status_code(status)) {
+            eprintln!("发送响应失败: {}", e);
+        }
+    }
+}
+
+// 按方法和路径分发请求
+fn route(
+    method: &Method,
+    url: &str,
+    body: &str,
+    repository: &mut dyn StudentRepository,
+    context: &Context,
+) -> Result<Reply, ApiError> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(decode)
+        .collect::<Result<_, _>>()?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["openapi.json"]) => {
+            Ok(Reply::ok(serde_json::from_str(OPENAPI).expect("openapi.json 是有效的 JSON")))
+        }
+        (Method::Get, ["students"]) => Ok(Reply::ok(json!(repository.list()?))),
+        (Method::Post, ["students"]) => {
+            let body: StudentBody = parse_body(body)?;
+            let (name, age, class) = check_student_body(body)?;
+            let id = repository.insert(name, age)?;
+            let student = match class {
+                Some(class) => modify_student(repository, id, |student| {
+                    student.class = Some(class.clone());
+                    Ok(())
+                })?,
+                None => find_student(repository, id)?,
+            };
+            Ok(Reply::created(json!(student)))
+        }
+        (Method::Get, ["students", id]) => Ok(Reply::ok(json!(find_student(repository, parse_id(id)?)?))),
+        (Method::Put, ["students", id]) => {
+            let id = parse_id(id)?;
+            find_student(repository, id)?;
+            let (name, age, class) = check_student_body(parse_body(body)?)?;
+            let student = modify_student(repository, id, |student| {
+                student.name = name.clone();
+                student.age = age;
+                student.class = class.clone();
+                Ok(())
+            })?;
+            Ok(Reply::ok(json!(student)))
+        }
+        (Method::Delete, ["students", id]) => {
+            let id = parse_id(id)?;
+            if !repository.remove(id)? {
+                return Err(student_not_found(id));
+            }
+            Ok(Reply::no_content())
+        }
+        (Method::Post, ["students", id, "enrollments"]) => {
+            let id = parse_id(id)?;
+            find_student(repository, id)?;
+            let body: EnrollmentBody = parse_body(body)?;
+            let course = find_course(repository, &body.course)?;
+            modify_student(repository, id, |student| {
+                if !student.enroll(course.code.clone()) {
+                    return Err(ApiError::new(409, format!("学生 {} 已经选修了课程 {}", student.id, course.code)));
+                }
+                Ok(())
+            })?;
+            Ok(Reply::created(json!(course)))
+        }
+        (Method::Get, ["students", id, "grades"]) => {
+            let student = find_student(repository, parse_id(id)?)?;
+            let courses = repository.list_courses()?;
+            let grades: Vec<Value> = student
+                .enrollments
+                .iter()
+                .filter_map(|code| courses.iter().find(|course| &course.code == code))
+                .map(|course| grade_json(&student, course, context))
+                .collect();
+            Ok(Reply::ok(json!(grades)))
+        }
+        (Method::Put, ["students", id, "grades", course]) => {
+            let id = parse_id(id)?;
+            find_student(repository, id)?;
+            let course = find_course(repository, course)?;
+            let body: GradeBody = parse_body(body)?;
+            let student = modify_student(repository, id, |student| {
+                if !student.is_enrolled(&course.code) {
+                    return Err(ApiError::new(409, format!("学生 {} 没有选修课程 {}，请先选课", student.id, course.code)));
+                }
+                course.check_direct_grade().map_err(|e| ApiError::new(409, e))?;
+                if !body.score.is_finite() {
+                    return Err(ApiError::new(422, format!("无效分数: {}", body.score)));
+                }
+                course.check_score(body.score).map_err(|e| ApiError::new(422, e))?;
+                student.set_grade(&course.code, body.score, &body.reason, context.clock.as_ref());
+                Ok(())
+            })?;
+            Ok(Reply::ok(grade_json(&student, &course, context)))
+        }
+        (Method::Get, ["courses"]) => Ok(Reply::ok(json!(repository.list_courses()?))),
+        (Method::Post, ["courses"]) => {
+            let body: CourseBody = parse_body(body)?;
+            let mut course =
+                Course::new(&body.code, &body.name, body.credits, body.max_score).map_err(|e| ApiError::new(422, e))?;
+            course.pass_fail = body.pass_fail;
+            course::check_new_course(&repository.list_courses()?, &course).map_err(|e| ApiError::new(409, e))?;
+            repository.insert_course(course.clone())?;
+            Ok(Reply::created(json!(course)))
+        }
+        (Method::Get, ["courses", course]) => Ok(Reply::ok(json!(find_course(repository, course)?))),
+        (Method::Delete, ["courses", course]) => {
+            let course = find_course(repository, course)?;
+            course::check_unused(&course.code, &repository.list()?).map_err(|e| ApiError::new(409, e))?;
+            repository.remove_course(&course.code)?;
+            Ok(Reply::no_content())
+        }
+        (_, ["openapi.json"])
+        | (_, ["students" | "courses"])
+        | (_, ["students" | "courses", _])
+        | (_, ["students", _, "enrollments" | "grades"])
+        | (_, ["students", _, "grades", _]) => Err(ApiError::new(405, format!("{} 不支持 {} 方法", path, method))),
+        _ => Err(ApiError::new(404, format!("没有这个路径: {}", path))),
+    }
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, ApiError> {
+    serde_json::from_str(body).map_err(|e| ApiError::new(400, format!("请求内容不是有效的 JSON: {}", e)))
+}
+
+// 姓名不能为空，年龄在 1-150 之间，班级为空字符串时视为没有分班
+fn check_student_body(body: StudentBody) -> Result<(String, u8, Option<String>), ApiError> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::new(422, "姓名不能为空"));
+    }
+    let age = u8::try_from(body.age)
+        .ok()
+        .filter(|age| AGE_RANGE.contains(age))
+        .ok_or_else(|| {
+            ApiError::new(422, format!("无效年龄: {}（应为 {}-{} 之间的整数）", body.age, AGE_RANGE.start(), AGE_RANGE.end()))
+        })?;
+    let class = body.class.map(|class| class.trim().to_string()).filter(|class| !class.is_empty());
+    Ok((name, age, class))
+}
+
+fn parse_id(text: &str) -> Result<u32, ApiError> {
+    text.parse().map_err(|_| ApiError::new(404, format!("无效ID: {}", text)))
+}
+
+fn student_not_found(id: u32) -> ApiError {
+    ApiError::new(404, format!("未找到ID为 {} 的学生", id))
+}
+
+fn find_student(repository: &dyn StudentRepository, id: u32) -> Result<Student, ApiError> {
+    repository.get(id)?.ok_or_else(|| student_not_found(id))
+}
+
+// 在最新的记录上修改学生；change 返回的错误原样作为响应，仓库本身的错误为 500
+fn modify_student(
+    repository: &mut dyn StudentRepository,
+    id: u32,
+    mut change: impl FnMut(&mut Student) -> Result<(), ApiError>,
+) -> Result<Student, ApiError> {
+    let mut rejected = None;
+    let result = repository.modify(id, &mut |student| {
+        change(student).map_err(|error| {
+            let message = error.message.clone();
+            rejected = Some(error);
+            message
+        })
+    });
+    result.map_err(|message| rejected.take().unwrap_or_else(|| ApiError::from(message)))
+}
+
+fn find_course(repository: &dyn StudentRepository, text: &str) -> Result<Course, ApiError> {
+    repository
+        .find_course(text)?
+        .ok_or_else(|| ApiError::new(404, format!("课程 {} 没有登记", text.trim())))
+}
+
+fn grade_json(student: &Student, course: &Course, context: &Context) -> Value {
+    let score = student.current_grade(&course.code);
+    json!({
+        "course": course.code,
+        "name": course.name,
+        "score": score,
+        "max_score": course.max_score,
+        "letter": score.map(|score| context.grading.letter(course, score)),
+    })
+}
+
+// 读取请求内容，最多 MAX_BODY 字节
+fn read_body(request: &mut Request) -> Result<String, ApiError> {
+    let too_large = || ApiError::new(413, format!("请求内容超过 {} KiB", MAX_BODY / 1024));
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY) {
+        return Err(too_large());
+    }
+    // 没有声明长度（分块传输）时多读一个字节，判断是否超出上限
+    let mut bytes = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| ApiError::new(400, format!("无法读取请求内容: {}", e)))?;
+    if bytes.len() as u64 > MAX_BODY {
+        return Err(too_large());
+    }
+    String::from_utf8(bytes).map_err(|_| ApiError::new(400, "请求内容不是 UTF-8 文本"))
+}
+
+// URL 路径中的百分号编码，例如 %E6%95%B0%E5%AD%A6 -> 数学
+fn decode(segment: &str) -> Result<String, ApiError> {
+    let invalid = || ApiError::new(400, format!("无效的路径: {}", segment));
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = segment.get(index + 1..index + 3).ok_or_else(invalid)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+// 测试代码：在系统分配的端口上启动服务器，通过 TCP 发送真实的 HTTP 请求
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grading::GradingConfig;
+    use crate::history::ManualClock;
+    use crate::student::StudentManagement;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    // 发送一个请求，返回状态码和 JSON 内容（没有内容时为 Null）
+    fn request(port: u16, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, content) = response.split_once("\r\n\r\n").unwrap();
+        (status, if content.is_empty() { Value::Null } else { serde_json::from_str(content).unwrap() })
+    }
+
+    fn context() -> Context {
+        Context {
+            clock: Box::new(ManualClock::new(1_709_281_800)),
+            ..Context::new(GradingConfig::builtin())
+        }
+    }
+
+    fn with_server(test: impl FnOnce(u16)) {
+        run_server(ApiServer::bind(0, Box::new(StudentManagement::new()), context()).unwrap(), test);
+    }
+
+    // 启动服务器，运行测试，最后停止服务器
+    fn run_server(server: ApiServer, test: impl FnOnce(u16)) {
+        let port = server.port();
+        thread::scope(|scope| {
+            scope.spawn(|| server.run());
+            // 测试失败时也要停止服务器，否则 scope 会一直等待
+            let _stop = StopOnDrop(&server);
+            test(port);
+        });
+    }
+
+    struct StopOnDrop<'a>(&'a ApiServer);
+
+    impl Drop for StopOnDrop<'_> {
+        fn drop(&mut self) {
+            self.0.stop();
+        }
+    }
+
+    #[test]
+    fn test_student_crud() {
+        with_server(|port| {
+            let (status, student) = request(port, "POST", "/students", r#"{"name": "张三", "age": 20, "class": "一班"}"#);
+            assert_eq!(status, 201);
+            assert_eq!((student["id"].as_u64(), student["class"].as_str()), (Some(1), Some("一班")));
+
+            let (status, student) = request(port, "GET", "/students/1", "");
+            assert_eq!((status, student["name"].as_str()), (200, Some("张三")));
+            let (status, student) = request(port, "PUT", "/students/1", r#"{"name": "张三丰", "age": 21}"#);
+            assert_eq!((status, student["age"].as_u64(), &student["class"]), (200, Some(21), &Value::Null));
+            let (_, students) = request(port, "GET", "/students", "");
+            assert_eq!(students.as_array().unwrap().len(), 1);
+
+            assert_eq!(request(port, "DELETE", "/students/1", "").0, 204);
+            let (status, error) = request(port, "GET", "/students/1", "");
+            assert_eq!(status, 404);
+            assert!(error["error"].as_str().unwrap().contains("ID为 1"));
+            assert_eq!(request(port, "DELETE", "/students/1", "").0, 404);
+        });
+    }
+
+    #[test]
+    fn test_status_codes() {
+        with_server(|port| {
+            assert_eq!(request(port, "POST", "/students", r#"{"name": "张三", "age": 200}"#).0, 422);
+            assert_eq!(request(port, "POST", "/students", r#"{"name": " ", "age": 20}"#).0, 422);
+            assert_eq!(request(port, "POST", "/students", r#"{"name": "张三""#).0, 400);
+            assert_eq!(request(port, "POST", "/students", r#"{"name": "张三", "age": 20, "grade": 1}"#).0, 400);
+            assert_eq!(request(port, "GET", "/students/abc", "").0, 404);
+            assert_eq!(request(port, "GET", "/teachers", "").0, 404);
+            assert_eq!(request(port, "PATCH", "/students/1", "{}").0, 405);
+
+            let (status, openapi) = request(port, "GET", "/openapi.json", "");
+            assert_eq!(status, 200);
+            assert!(openapi["openapi"].as_str().unwrap().starts_with("3."));
+            assert!(openapi["paths"]["/students/{id}/grades/{course}"]["put"].is_object());
+
+            // 描述中列出服务器实际会返回的 400 和 413
+            for (path, method) in [("/students/{id}/enrollments", "post"), ("/students/{id}/grades/{course}", "put"), ("/courses", "post")] {
+                let responses = &openapi["paths"][path][method]["responses"];
+                assert!(responses["400"].is_object() && responses["413"].is_object(), "{} {}", method, path);
+            }
+            assert_eq!(request(port, "POST", "/courses", "{").0, 400);
+            assert_eq!(request(port, "GET", "/courses/%E6%95", "").0, 400);
+
+            // 超过上限的请求内容不会被读入
+            let large = format!(r#"{{"name": "{}", "age": 20}}"#, "张".repeat(30_000));
+            let (status, error) = request(port, "POST", "/students", &large);
+            assert_eq!((status, error["error"].as_str()), (413, Some("请求内容超过 64 KiB")));
+            assert!(request(port, "GET", "/students", "").1.as_array().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_courses_and_grades() {
+        with_server(|port| {
+            let math = r#"{"code": "math101", "name": "数学", "credits": 4}"#;
+            let (status, course) = request(port, "POST", "/courses", math);
+            assert_eq!((status, course["code"].as_str()), (201, Some("MATH101")));
+            assert_eq!(request(port, "POST", "/courses", math).0, 409);
+            assert_eq!(request(port, "POST", "/courses", r#"{"code": "PE", "name": "体育", "credits": -1}"#).0, 422);
+            request(port, "POST", "/students", r#"{"name": "张三", "age": 20}"#);
+
+            // 没有选课时不能记录成绩
+            assert_eq!(request(port, "PUT", "/students/1/grades/MATH101", r#"{"score": 90}"#).0, 409);
+            assert_eq!(request(port, "POST", "/students/1/enrollments", r#"{"course": "数学"}"#).0, 201);
+            assert_eq!(request(port, "POST", "/students/1/enrollments", r#"{"course": "数学"}"#).0, 409);
+            assert_eq!(request(port, "POST", "/students/1/enrollments", r#"{"course": "化学"}"#).0, 404);
+
+            assert_eq!(request(port, "PUT", "/students/1/grades/MATH101", r#"{"score": 120}"#).0, 422);
+            assert_eq!(request(port, "PUT", "/students/9/grades/MATH101", r#"{"score": 90}"#).0, 404);
+            // 课程名称按 UTF-8 百分号编码
+            let (status, grade) = request(port, "PUT", "/students/1/grades/%E6%95%B0%E5%AD%A6", r#"{"score": 92, "reason": "期末"}"#);
+            assert_eq!((status, grade["score"].as_f64(), grade["letter"].as_str()), (200, Some(92.0), Some("A")));
+            let (_, grades) = request(port, "GET", "/students/1/grades", "");
+            assert_eq!(grades[0]["course"].as_str(), Some("MATH101"));
+            let (_, student) = request(port, "GET", "/students/1", "");
+            assert_eq!(student["history"]["MATH101"][0]["reason"].as_str(), Some("期末"));
+            assert_eq!(student["history"]["MATH101"][0]["time"].as_u64(), Some(1_709_281_800));
+
+            assert_eq!(request(port, "DELETE", "/courses/MATH101", "").0, 409);
+            request(port, "DELETE", "/students/1", "");
+            assert_eq!(request(port, "DELETE", "/courses/MATH101", "").0, 204);
+            assert_eq!(request(port, "GET", "/courses/MATH101", "").0, 404);
+        });
+    }
+
+    #[test]
+    fn test_concurrent_requests() {
+        with_server(|port| {
+            thread::scope(|scope| {
+                for worker in 0..8 {
+                    scope.spawn(move || {
+                        for index in 0..10 {
+                            let body = format!(r#"{{"name": "学生{}-{}", "age": 20}}"#, worker, index);
+                            assert_eq!(request(port, "POST", "/students", &body).0, 201);
+                        }
+                    });
+                }
+            });
+            let (_, students) = request(port, "GET", "/students", "");
+            let mut ids: Vec<u64> = students.as_array().unwrap().iter().map(|s| s["id"].as_u64().unwrap()).collect();
+            ids.dedup();
+            assert_eq!(ids, (1..=80).collect::<Vec<u64>>());
+        });
+    }
+
+    #[test]
+    fn test_shared_store_loses_no_updates() {
+        // 每个线程为同一个学生选一门不同的课，再反复修改这门课的成绩；同时后台任务读取数据快照
+        let mut shared = SharedStudents::new();
+        for index in 0..8 {
+            shared.insert_course(Course::new(&format!("C{}", index), &format!("课程{}", index), 1.0, 100.0).unwrap()).unwrap();
+        }
+        let id = shared.insert(String::from("张三"), 20).unwrap();
+        let server = ApiServer::bind_shared(0, shared.clone(), context()).unwrap();
+        run_server(server, |port| {
+            thread::scope(|scope| {
+                for index in 0..8 {
+                    scope.spawn(move || {
+                        let body = format!(r#"{{"course": "C{}"}}"#, index);
+                        assert_eq!(request(port, "POST", "/students/1/enrollments", &body).0, 201);
+                        for round in 0..10 {
+                            let body = format!(r#"{{"score": {}}}"#, 60 + round);
+                            assert_eq!(request(port, "PUT", &format!("/students/1/grades/C{}", index), &body).0, 200);
+                        }
+                    });
+                }
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        let snapshot = shared.snapshot();
+                        let student = snapshot.get_student(id).unwrap();
+                        assert!(student.grades.keys().all(|code| student.is_enrolled(code)));
+                    }
+                });
+            });
+        });
+
+        let student = shared.get(id).unwrap().unwrap();
+        assert_eq!(student.enrollments.len(), 8);
+        for index in 0..8 {
+            let code = format!("C{}", index);
+            assert_eq!(student.current_grade(&code), Some(69.0));
+            assert_eq!(student.grade_history(&code).len(), 10);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+// 多个线程共用的内存数据：API 服务器的工作线程、命令行和后台生成成绩单的任务各拿一个句柄，同时读写同一份数据
+//
+// StudentManagement 的每次修改都需要 &mut self，只能整体放在一把锁里依次进行。SharedStudents 把锁分细：
+// - 每个学生有自己的读写锁，修改不同学生的线程不会互相等待，读取同一个学生的线程也不会；
+// - 学生表（ID -> 学生）只在添加和删除学生时短暂加写锁；
+// - 新学生的 ID 由原子计数器在学生表的写锁中分配，不会重复；
+// - 课程登记表有自己的读写锁，写入学生时持有读锁，检查过的课程在写入完成之前不会被删除；
+// - batch 持有课程登记表和学生表的写锁，整批修改完成之前其他线程都要等待。
+//
+// 加锁的顺序总是 课程登记表 -> 学生表 -> 单个学生，不会死锁。
+// 先 get 再 update 仍然会覆盖其他线程在这之间的修改，在现有记录上修改时使用 modify。
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::course::{self, Course};
+use crate::repository::StudentRepository;
+use crate::student::{self, Student, StudentManagement};
+
+type Record = Arc<RwLock<Student>>;
+
+// 共用数据的句柄，clone 得到指向同一份数据的新句柄，可以交给其他线程
+#[derive(Debug, Clone)]
+pub struct SharedStudents {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    // 课程代码 -> 课程
+    courses: RwLock<BTreeMap<String, Course>>,
+    students: RwLock<HashMap<u32, Record>>,
+    next_id: AtomicU32,
+}
+
+// 其他线程持有锁时 panic 不影响数据本身：每次写入都是整条记录替换，不会留下改了一半的记录
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl SharedStudents {
+    pub fn new() -> SharedStudents {
+        SharedStudents::from(StudentManagement::new())
+    }
+
+    // 下一个新学生将使用的 ID
+    pub fn next_id(&self) -> u32 {
+        self.inner.next_id.load(Ordering::SeqCst)
+    }
+
+    // 当前数据的副本，例如用来保存到文件或者生成报表；每个学生都是某次修改完成后的完整记录，
+    // 复制期间添加、删除学生和修改课程的线程会等待
+    pub fn snapshot(&self) -> StudentManagement {
+        let courses = read(&self.inner.courses).clone();
+        let students = read(&self.inner.students);
+        StudentManagement {
+            students: students.iter().map(|(id, record)| (*id, read(record).clone())).collect(),
+            next_id: self.next_id(),
+            courses,
+        }
+    }
+
+    // 课程登记表中的所有课程，调用者持有课程登记表的锁
+    fn course_list(courses: &BTreeMap<String, Course>) -> Vec<Course> {
+        courses.values().cloned().collect()
+    }
+
+    fn record(&self, id: u32) -> Option<Record> {
+        read(&self.inner.students).get(&id).cloned()
+    }
+}
+
+impl Default for SharedStudents {
+    fn default() -> SharedStudents {
+        SharedStudents::new()
+    }
+}
+
+// 把已有的数据（例如从文件加载的）交给多个线程共用
+impl From<StudentManagement> for SharedStudents {
+    fn from(system: StudentManagement) -> SharedStudents {
+        let students = system
+            .students
+            .into_iter()
+            .map(|(id, student)| (id, Arc::new(RwLock::new(student))))
+            .collect();
+        SharedStudents {
+            inner: Arc::new(Inner {
+                courses: RwLock::new(system.courses),
+                students: RwLock::new(students),
+                next_id: AtomicU32::new(system.next_id),
+            }),
+        }
+    }
+}
+
+impl StudentRepository for SharedStudents {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        Ok(self.record(id).map(|record| read(&record).clone()))
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        // 持有学生表的写锁分配 ID，batch 进行期间不会分配出它也要使用的 ID；
+        // 计数器停在最大的 ID 上，不会绕回已经用过的 ID
+        let mut students = write(&self.inner.students);
+        let id = self
+            .inner
+            .next_id
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, student::id_after)
+            .map_err(|_| String::from("学生 ID 已经用完"))?;
+        match students.entry(id) {
+            Entry::Occupied(_) => Err(format!("ID {} 已经被学生使用", id)),
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(RwLock::new(Student::new(id, name, age))));
+                Ok(id)
+            }
+        }
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        let courses = read(&self.inner.courses);
+        course::check_student(&student, &SharedStudents::course_list(&courses))?;
+        let record = self.record(student.id).ok_or_else(|| format!("未找到ID为 {} 的学生", student.id))?;
+        *write(&record) = student;
+        Ok(())
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        let courses = read(&self.inner.courses);
+        let record = self.record(id).ok_or_else(|| format!("未找到ID为 {} 的学生", id))?;
+        // 持有这个学生的写锁完成读取、修改和写回，同时修改同一个学生的线程依次进行
+        let mut current = write(&record);
+        let mut student = current.clone();
+        change(&mut student)?;
+        course::check_student(&student, &SharedStudents::course_list(&courses))?;
+        *current = student.clone();
+        Ok(student)
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        Ok(write(&self.inner.students).remove(&id).is_some())
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        let courses = read(&self.inner.courses);
+        course::check_student(&student, &SharedStudents::course_list(&courses))?;
+        let after = student::id_after(student.id).ok_or("学生 ID 超出范围")?;
+        let mut students = write(&self.inner.students);
+        if students.contains_key(&student.id) {
+            return Err(format!("ID {} 已经被学生使用", student.id));
+        }
+        self.inner.next_id.fetch_max(after, Ordering::SeqCst);
+        students.insert(student.id, Arc::new(RwLock::new(student)));
+        Ok(())
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        // 持有课程登记表和学生表的写锁，在副本上完成整批修改后一起写回；
+        // 期间其他线程的读写都要等待，看不到只做了一半的修改
+        let mut courses = write(&self.inner.courses);
+        let mut students = write(&self.inner.students);
+        let mut system = StudentManagement {
+            students: students.iter().map(|(id, record)| (*id, read(record).clone())).collect(),
+            next_id: self.next_id(),
+            courses: courses.clone(),
+        };
+        change(&mut system)?;
+
+        *courses = system.courses;
+        students.retain(|id, _| system.students.contains_key(id));
+        for (id, student) in system.students {
+            match students.entry(id) {
+                Entry::Occupied(entry) => *write(entry.get()) = student,
+                Entry::Vacant(entry) => {
+                    entry.insert(Arc::new(RwLock::new(student)));
+                }
+            }
+        }
+        self.inner.next_id.fetch_max(system.next_id, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        let mut records: Vec<Record> = read(&self.inner.students).values().cloned().collect();
+        records.sort_by_key(|record| read(record).id);
+        Ok(records.iter().map(|record| read(record).clone()).collect())
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        let mut courses = write(&self.inner.courses);
+        course::check_new_course(&SharedStudents::course_list(&courses), &course)?;
+        courses.insert(course.code.clone(), course);
+        Ok(())
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        // 持有课程登记表的写锁，检查期间没有学生能选修这门课程
+        let mut courses = write(&self.inner.courses);
+        let code = course::normalize_code(code);
+        if !courses.contains_key(&code) {
+            return Ok(false);
+        }
+        course::check_unused(&code, &self.list()?)?;
+        Ok(courses.remove(&code).is_some())
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        Ok(SharedStudents::course_list(&read(&self.inner.courses)))
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+    use crate::repository::conformance;
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 200;
+
+    #[test]
+    fn test_shared_backend_conformance() {
+        conformance::run(&mut || Box::new(SharedStudents::new()));
+    }
+
+    #[test]
+    fn test_concurrent_handles() {
+        let shared = SharedStudents::new();
+        conformance::check_concurrent_writers(&|| Box::new(shared.clone()), THREADS, ROUNDS);
+    }
+
+    #[test]
+    fn test_concurrent_inserts_get_unique_ids() {
+        let shared = SharedStudents::new();
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let mut handle = shared.clone();
+                thread::spawn(move || {
+                    (0..ROUNDS)
+                        .map(|round| handle.insert(format!("学生{}-{}", thread, round), 20).unwrap())
+                        .collect::<Vec<u32>>()
+                })
+            })
+            .collect();
+        let mut ids: Vec<u32> = handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+        ids.sort();
+        let expected: Vec<u32> = (1..=(THREADS * ROUNDS) as u32).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(shared.list().unwrap().len(), THREADS * ROUNDS);
+        assert_eq!(shared.next_id(), (THREADS * ROUNDS) as u32 + 1);
+    }
+
+    #[test]
+    fn test_concurrent_modify_loses_no_updates() {
+        let mut shared = SharedStudents::new();
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        shared.insert_course(math.clone()).unwrap();
+        let id = shared.insert(String::from("张三"), 20).unwrap();
+        shared.modify(id, &mut |student| {
+            student.enroll(math.code.clone());
+            Ok(())
+        })
+        .unwrap();
+
+        // 每个线程在同一个学生的姓名后面追加自己的字母，并记录一次成绩；
+        // 有任何一次修改被覆盖，姓名的长度或成绩历史的条数就会变少
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let mut handle = shared.clone();
+                let math = math.clone();
+                thread::spawn(move || {
+                    let clock = ManualClock::default();
+                    for _ in 0..ROUNDS {
+                        handle
+                            .modify(id, &mut |student| {
+                                student.name.push(char::from(b'a' + thread as u8));
+                                // 分数和当前成绩不同，每次都会记入历史
+                                let score = if student.current_grade(&math.code) == Some(60.0) { 70.0 } else { 60.0 };
+                                student.record_grade(&math, score, "", &clock)
+                            })
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        // 同时读取的线程总是看到某次修改完成后的完整记录
+        for _ in 0..ROUNDS {
+            let student = shared.get(id).unwrap().unwrap();
+            assert_eq!(student.name.chars().count() - 2, student.grade_history(&math.code).len());
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let student = shared.get(id).unwrap().unwrap();
+        for thread in 0..THREADS {
+            let letter = char::from(b'a' + thread as u8);
+            assert_eq!(student.name.chars().filter(|c| *c == letter).count(), ROUNDS);
+        }
+        assert_eq!(student.grade_history(&math.code).len(), THREADS * ROUNDS);
+    }
+
+    #[test]
+    fn test_course_removal_races_with_enrollment() {
+        // 选课和删除课程同时进行时，结果只能是"选上了且课程还在"或者"课程删掉了且没有人选修"
+        for _ in 0..50 {
+            let mut shared = SharedStudents::new();
+            let art = Course::new("ART", "美术", 2.0, 100.0).unwrap();
+            shared.insert_course(art.clone()).unwrap();
+            let id = shared.insert(String::from("张三"), 20).unwrap();
+
+            let mut enrolling = shared.clone();
+            let enroll = thread::spawn(move || {
+                enrolling.modify(id, &mut |student| {
+                    student.enroll(String::from("ART"));
+                    Ok(())
+                })
+            });
+            let removed = shared.remove_course("ART");
+            let enrolled = enroll.join().unwrap();
+
+            let courses = shared.list_courses().unwrap();
+            let student = shared.get(id).unwrap().unwrap();
+            course::check_student(&student, &courses).unwrap();
+            match removed {
+                Ok(true) => assert!(enrolled.is_err() && !student.is_enrolled("ART")),
+                _ => assert!(enrolled.is_ok() && courses == vec![art.clone()]),
+            }
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut system = StudentManagement::new();
+        system.insert_course(Course::new("MATH101", "数学", 4.0, 100.0).unwrap()).unwrap();
+        system.insert(String::from("张三"), 20).unwrap();
+        let removed = system.insert(String::from("李四"), 21).unwrap();
+        system.remove(removed).unwrap();
+
+        let mut shared = SharedStudents::from(system.clone());
+        assert_eq!(shared.snapshot(), system);
+        // 删除过的 ID 不会被重新使用
+        assert_eq!(shared.insert(String::from("王五"), 19).unwrap(), removed + 1);
+    }
+
+    #[test]
+    fn test_ids_run_out_without_overwriting() {
+        let mut system = StudentManagement::new();
+        system.next_id = u32::MAX - 2;
+        let mut shared = SharedStudents::from(system);
+        let last = shared.insert(String::from("张三"), 20).unwrap();
+        assert_eq!(last, u32::MAX - 2);
+        assert_eq!(shared.insert(String::from("李四"), 21).unwrap_err(), "学生 ID 已经用完");
+        assert_eq!(shared.next_id(), u32::MAX - 1);
+
+        // 计数器之前已经有人占用的 ID 不会被覆盖
+        let mut system = StudentManagement::new();
+        system.students.insert(1, Student::new(1, String::from("张三"), 20));
+        let mut shared = SharedStudents::from(system);
+        assert!(shared.insert(String::from("李四"), 21).is_err());
+        assert_eq!(shared.get(1).unwrap().unwrap().name, "张三");
+
+        assert_eq!(shared.restore(Student::new(u32::MAX, String::from("王五"), 19)).unwrap_err(), "学生 ID 超出范围");
+    }
+}
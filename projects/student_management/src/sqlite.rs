@@ -0,0 +1,764 @@
+// SQLite 后端：数据保存在嵌入式 SQLite 数据库文件中，不需要单独的数据库服务
+//
+// 表结构（PRAGMA user_version = 6）：
+// - students(id, name, age, class)：id 使用 AUTOINCREMENT，删除过的 ID 不会被重新使用
+// - courses(code, name, credits, max_score, pass_fail)：课程登记表
+// - enrollments(student_id, course_code)：选课关系
+// - grades(student_id, course_code, score)：只能为选修的课程记录成绩（外键指向 enrollments）
+// - categories(course_code, position, name, weight, drop_lowest, late_penalty)：课程的评分类别
+// - assessments(student_id, course_code, position, name, category, score, max_score, days_late)：
+//   作业和考试成绩，position 保存记录顺序
+// - grade_history(student_id, course_code, position, time, old, new, reason)：成绩修改历史，
+//   退选后仍然保留，删除学生时一起删除
+//
+// 旧版本的数据库（user_version = 0）的 grades 表按课程名称 subject 保存成绩，
+// 打开时会在一个事务中转换成新的表结构；版本 2 的 courses 表没有 pass_fail 列，打开时补上；
+// 版本 3 之前没有评分类别和评估成绩表，版本 4 之前没有成绩修改历史表，打开时创建；
+// 版本 6 之前 students 表没有 class 列，打开时补上。
+//
+// 几个程序可以同时打开同一个数据库文件，SQLite 自己负责文件锁；另一个程序正在写入时最多等待 BUSY_TIMEOUT。
+
+use std::env;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+
+use crate::course::{self, Course};
+use crate::gradebook::{Assessment, Category};
+use crate::history::GradeChange;
+use crate::repository::{Query, StudentRepository};
+use crate::storage;
+use crate::student::{self, Student};
+
+// 设置这个环境变量可以把数据库文件放到其他位置；JSON 数据文件使用 STUDENT_DATA_FILE，两者互不影响
+pub const DATABASE_PATH_VARIABLE: &str = "STUDENT_DATABASE_FILE";
+
+// 数据库被其他程序锁住时等待的最长时间，超过后返回错误
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 当前的数据库结构版本
+const SCHEMA_VERSION: i64 = 6;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS students (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        age INTEGER NOT NULL,
+        class TEXT
+    );
+    CREATE TABLE IF NOT EXISTS courses (
+        code TEXT PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        credits REAL NOT NULL,
+        max_score REAL NOT NULL,
+        pass_fail INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS enrollments (
+        student_id INTEGER NOT NULL REFERENCES students(id) ON DELETE CASCADE,
+        course_code TEXT NOT NULL REFERENCES courses(code),
+        PRIMARY KEY (student_id, course_code)
+    );
+    CREATE TABLE IF NOT EXISTS grades (
+        student_id INTEGER NOT NULL,
+        course_code TEXT NOT NULL,
+        score REAL NOT NULL,
+        PRIMARY KEY (student_id, course_code),
+        FOREIGN KEY (student_id, course_code)
+            REFERENCES enrollments(student_id, course_code) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS categories (
+        course_code TEXT NOT NULL REFERENCES courses(code) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        weight REAL NOT NULL,
+        drop_lowest INTEGER NOT NULL,
+        late_penalty REAL NOT NULL,
+        PRIMARY KEY (course_code, name)
+    );
+    CREATE TABLE IF NOT EXISTS assessments (
+        student_id INTEGER NOT NULL,
+        course_code TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        category TEXT NOT NULL,
+        score REAL NOT NULL,
+        max_score REAL NOT NULL,
+        days_late INTEGER NOT NULL,
+        PRIMARY KEY (student_id, course_code, name),
+        FOREIGN KEY (student_id, course_code)
+            REFERENCES enrollments(student_id, course_code) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS grade_history (
+        student_id INTEGER NOT NULL REFERENCES students(id) ON DELETE CASCADE,
+        course_code TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        time INTEGER NOT NULL,
+        old REAL,
+        new REAL,
+        reason TEXT NOT NULL,
+        PRIMARY KEY (student_id, course_code, position)
+    );
+";
+
+// 数据库文件的默认位置：环境变量 STUDENT_DATABASE_FILE，否则是与 JSON 数据文件同一目录下的 students.db
+pub fn default_path() -> PathBuf {
+    match env::var_os(DATABASE_PATH_VARIABLE) {
+        Some(path) => PathBuf::from(path),
+        None => storage::data_dir().join("students.db"),
+    }
+}
+
+pub struct SqliteRepository {
+    connection: Connection,
+}
+
+impl SqliteRepository {
+    // 打开（必要时创建）数据库文件
+    pub fn open(path: &Path) -> Result<SqliteRepository, String> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建目录 {}: {}", parent.display(), e))?;
+        }
+        let connection =
+            Connection::open(path).map_err(|e| format!("无法打开数据库 {}: {}", path.display(), e))?;
+        SqliteRepository::with_connection(connection).map_err(|e| {
+            format!(
+                "数据库 {} 无法使用: {}\n文件没有被修改。可以把它改名备份后重新启动程序，从空数据开始。",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    // 只存在于内存中的数据库，主要用于测试
+    pub fn open_in_memory() -> Result<SqliteRepository, String> {
+        let connection = Connection::open_in_memory().map_err(sql_error)?;
+        SqliteRepository::with_connection(connection)
+    }
+
+    fn with_connection(mut connection: Connection) -> Result<SqliteRepository, String> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;").map_err(sql_error)?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(sql_error)?;
+        let version: i64 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(sql_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(format!(
+                "数据库结构版本是 {}，本程序只支持版本 {}",
+                version, SCHEMA_VERSION
+            ));
+        }
+        if version < SCHEMA_VERSION {
+            migrate(&mut connection)?;
+        }
+        Ok(SqliteRepository { connection })
+    }
+
+    // 读取满足 WHERE 条件的学生及其选课和成绩，按 ID 排序
+    fn select(&self, condition: &str, values: &[&dyn rusqlite::ToSql]) -> Result<Vec<Student>, String> {
+        let sql = format!("SELECT id, name, age, class FROM students WHERE {} ORDER BY id", condition);
+        let mut statement = self.connection.prepare(&sql).map_err(sql_error)?;
+        let mut students = statement
+            .query_map(values, |row| {
+                let mut student = Student::new(row.get(0)?, row.get(1)?, row.get(2)?);
+                student.class = row.get(3)?;
+                Ok(student)
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<Student>, _>>())
+            .map_err(sql_error)?;
+
+        let mut enrollments = self
+            .connection
+            .prepare("SELECT course_code FROM enrollments WHERE student_id = ?1")
+            .map_err(sql_error)?;
+        let mut grades = self
+            .connection
+            .prepare("SELECT course_code, score FROM grades WHERE student_id = ?1")
+            .map_err(sql_error)?;
+        let mut assessments = self
+            .connection
+            .prepare(
+                "SELECT course_code, name, category, score, max_score, days_late FROM assessments
+                 WHERE student_id = ?1 ORDER BY course_code, position",
+            )
+            .map_err(sql_error)?;
+        let mut history = self
+            .connection
+            .prepare(
+                "SELECT course_code, time, old, new, reason FROM grade_history
+                 WHERE student_id = ?1 ORDER BY course_code, position",
+            )
+            .map_err(sql_error)?;
+        for student in &mut students {
+            let codes = enrollments
+                .query_map([student.id], |row| row.get::<_, String>(0))
+                .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+                .map_err(sql_error)?;
+            for code in codes {
+                student.enroll(code);
+            }
+
+            let rows = grades
+                .query_map([student.id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<(String, f64)>, _>>())
+                .map_err(sql_error)?;
+            for (code, score) in rows {
+                student.add_grade(code, score as f32);
+            }
+
+            let rows = assessments
+                .query_map([student.id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        Assessment {
+                            name: row.get(1)?,
+                            category: row.get(2)?,
+                            score: row.get::<_, f64>(3)? as f32,
+                            max_score: row.get::<_, f64>(4)? as f32,
+                            days_late: row.get(5)?,
+                        },
+                    ))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<(String, Assessment)>, _>>())
+                .map_err(sql_error)?;
+            for (code, assessment) in rows {
+                student.assessments.entry(code).or_default().push(assessment);
+            }
+
+            let rows = history
+                .query_map([student.id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        GradeChange {
+                            time: row.get::<_, i64>(1)? as u64,
+                            old: row.get::<_, Option<f64>>(2)?.map(|score| score as f32),
+                            new: row.get::<_, Option<f64>>(3)?.map(|score| score as f32),
+                            reason: row.get(4)?,
+                        },
+                    ))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<(String, GradeChange)>, _>>())
+                .map_err(sql_error)?;
+            for (code, change) in rows {
+                student.history.entry(code).or_default().push(change);
+            }
+        }
+        Ok(students)
+    }
+}
+
+// 创建表结构，旧版本的 grades(student_id, subject, score) 转换成课程、选课和新的成绩表，
+// 版本 2 的 courses 表加上 pass_fail 列，版本 6 之前的 students 表加上 class 列
+fn migrate(connection: &mut Connection) -> Result<(), String> {
+    let transaction = connection.transaction().map_err(sql_error)?;
+    let has_subject_column: bool = transaction
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('grades') WHERE name = 'subject'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(sql_error)?;
+
+    if has_subject_column {
+        transaction
+            .execute_batch("ALTER TABLE grades RENAME TO grades_v1;")
+            .map_err(sql_error)?;
+    }
+    transaction.execute_batch(SCHEMA).map_err(sql_error)?;
+
+    let has_pass_fail_column: bool = transaction
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('courses') WHERE name = 'pass_fail'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(sql_error)?;
+    if !has_pass_fail_column {
+        transaction
+            .execute_batch("ALTER TABLE courses ADD COLUMN pass_fail INTEGER NOT NULL DEFAULT 0;")
+            .map_err(sql_error)?;
+    }
+    let has_class_column: bool = transaction
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('students') WHERE name = 'class'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(sql_error)?;
+    if !has_class_column {
+        transaction.execute_batch("ALTER TABLE students ADD COLUMN class TEXT;").map_err(sql_error)?;
+    }
+
+    if has_subject_column {
+        let old_grades = transaction
+            .prepare("SELECT student_id, subject, score FROM grades_v1 ORDER BY student_id, subject")
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)? as f32)))?
+                    .collect::<Result<Vec<(u32, String, f32)>, _>>()
+            })
+            .map_err(sql_error)?;
+        let (courses, grades) = course::courses_from_subjects(&old_grades)?;
+
+        for course in &courses {
+            transaction
+                .execute(
+                    "INSERT INTO courses (code, name, credits, max_score) VALUES (?1, ?2, ?3, ?4)",
+                    params![course.code, course.name, course.credits as f64, course.max_score as f64],
+                )
+                .map_err(sql_error)?;
+        }
+        // "数学" 和 "数学 " 会转换成同一门课程，保留后出现的成绩
+        for (id, code, score) in grades {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO enrollments (student_id, course_code) VALUES (?1, ?2)",
+                    params![id, code],
+                )
+                .and_then(|_| {
+                    transaction.execute(
+                        "INSERT OR REPLACE INTO grades (student_id, course_code, score) VALUES (?1, ?2, ?3)",
+                        params![id, code, score as f64],
+                    )
+                })
+                .map_err(sql_error)?;
+        }
+        transaction.execute_batch("DROP TABLE grades_v1;").map_err(sql_error)?;
+    }
+
+    transaction
+        .execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))
+        .and_then(|_| transaction.commit())
+        .map_err(sql_error)
+}
+
+impl StudentRepository for SqliteRepository {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        Ok(self.select("id = ?1", &[&id])?.pop())
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        // AUTOINCREMENT 分配的 ID 超出 u32 时撤销插入，不能截断成其他学生的 ID；
+        // 和其他后端一样，之后没有下一个 ID 的 ID 也不分配
+        let write = Write::begin(&self.connection, false)?;
+        write
+            .execute("INSERT INTO students (name, age) VALUES (?1, ?2)", params![name, age])
+            .map_err(sql_error)?;
+        let id = u32::try_from(write.last_insert_rowid())
+            .ok()
+            .filter(|id| student::id_after(*id).is_some())
+            .ok_or("学生 ID 已经用完")?;
+        write.commit()?;
+        Ok(id)
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        course::check_student(&student, &self.list_courses()?)?;
+
+        // 学生信息、选课、成绩、评估和修改历史在同一个事务中替换，失败时不会只更新一半
+        let write = Write::begin(&self.connection, false)?;
+        replace_student(&write, &student)?;
+        write.commit()
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        // IMMEDIATE 事务在读取之前就取得写锁，其他程序的修改要等这次读取、修改、写回全部完成
+        let write = Write::begin(&self.connection, true)?;
+        let mut student = self.get(id)?.ok_or_else(|| format!("未找到ID为 {} 的学生", id))?;
+        change(&mut student)?;
+        course::check_student(&student, &self.list_courses()?)?;
+        replace_student(&write, &student)?;
+        write.commit()?;
+        Ok(student)
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        // 和内存后端一致：放回最大的 ID 之后没有可以分配的 ID
+        if student::id_after(student.id).is_none() {
+            return Err(String::from("学生 ID 超出范围"));
+        }
+        course::check_student(&student, &self.list_courses()?)?;
+        let write = Write::begin(&self.connection, false)?;
+        let exists: bool = write
+            .query_row("SELECT COUNT(*) > 0 FROM students WHERE id = ?1", [student.id], |row| row.get(0))
+            .map_err(sql_error)?;
+        if exists {
+            return Err(format!("ID {} 已经被学生使用", student.id));
+        }
+        // 显式指定 ID 插入；AUTOINCREMENT 记录的最大 ID 不会变小，以后的新学生仍然不会重用 ID
+        write
+            .execute(
+                "INSERT INTO students (id, name, age, class) VALUES (?1, ?2, ?3, ?4)",
+                params![student.id, student.name, student.age, student.class],
+            )
+            .map_err(sql_error)?;
+        write_details(&write, &student)?;
+        write.commit()
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        // 选课和成绩通过外键 ON DELETE CASCADE 一起删除
+        let changed = self
+            .connection
+            .execute("DELETE FROM students WHERE id = ?1", [id])
+            .map_err(sql_error)?;
+        Ok(changed > 0)
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        // 整批修改在一个 IMMEDIATE 事务中进行，其中的每次写入改用保存点；
+        // 已经在另一次 batch 中时整批修改本身也是一个保存点
+        let nested = begin(&self.connection, true)?;
+        match change(self) {
+            Ok(()) => finish(&self.connection, nested, true),
+            Err(e) => {
+                finish(&self.connection, nested, false)?;
+                Err(e)
+            }
+        }
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.select("1", &[])
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        // 年龄和课程条件交给数据库筛选；姓名需要与其他后端一致地忽略 Unicode 大小写，
+        // 所以最后再统一用 Query::matches 检查一遍
+        let min_age = query.min_age.unwrap_or(u8::MIN);
+        let max_age = query.max_age.unwrap_or(u8::MAX);
+        let students = match &query.course {
+            Some(code) => self.select(
+                "age BETWEEN ?1 AND ?2 AND id IN (SELECT student_id FROM grades WHERE course_code = ?3)",
+                &[&min_age, &max_age, code],
+            )?,
+            None => self.select("age BETWEEN ?1 AND ?2", &[&min_age, &max_age])?,
+        };
+        Ok(students.into_iter().filter(|student| query.matches(student)).collect())
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        course::check_new_course(&self.list_courses()?, &course)?;
+        let write = Write::begin(&self.connection, false)?;
+        write
+            .execute(
+                "INSERT INTO courses (code, name, credits, max_score, pass_fail) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![course.code, course.name, course.credits as f64, course.max_score as f64, course.pass_fail],
+            )
+            .map_err(sql_error)?;
+        for (position, category) in course.categories.iter().enumerate() {
+            write
+                .execute(
+                    "INSERT INTO categories (course_code, position, name, weight, drop_lowest, late_penalty)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        course.code,
+                        position as i64,
+                        category.name,
+                        category.weight as f64,
+                        category.drop_lowest as i64,
+                        category.late_penalty as f64
+                    ],
+                )
+                .map_err(sql_error)?;
+        }
+        write.commit()
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        let code = course::normalize_code(code);
+        let enrolled: u32 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM enrollments WHERE course_code = ?1", [&code], |row| row.get(0))
+            .map_err(sql_error)?;
+        if enrolled > 0 {
+            return Err(format!("课程 {} 还有 {} 名学生选修，不能删除", code, enrolled));
+        }
+        let changed = self
+            .connection
+            .execute("DELETE FROM courses WHERE code = ?1", [&code])
+            .map_err(sql_error)?;
+        Ok(changed > 0)
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT code, name, credits, max_score, pass_fail FROM courses ORDER BY code")
+            .map_err(sql_error)?;
+        let mut courses = statement
+            .query_map([], |row| {
+                Ok(Course {
+                    code: row.get(0)?,
+                    name: row.get(1)?,
+                    credits: row.get::<_, f64>(2)? as f32,
+                    max_score: row.get::<_, f64>(3)? as f32,
+                    pass_fail: row.get(4)?,
+                    categories: Vec::new(),
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<Course>, _>>())
+            .map_err(sql_error)?;
+
+        let mut categories = self
+            .connection
+            .prepare(
+                "SELECT name, weight, drop_lowest, late_penalty FROM categories
+                 WHERE course_code = ?1 ORDER BY position",
+            )
+            .map_err(sql_error)?;
+        for course in &mut courses {
+            course.categories = categories
+                .query_map([&course.code], |row| {
+                    Ok(Category {
+                        name: row.get(0)?,
+                        weight: row.get::<_, f64>(1)? as f32,
+                        drop_lowest: row.get::<_, i64>(2)? as usize,
+                        late_penalty: row.get::<_, f64>(3)? as f32,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<Category>, _>>())
+                .map_err(sql_error)?;
+        }
+        Ok(courses)
+    }
+}
+
+// 一次写入：连接不在事务中时开始一个事务；已经在 batch 的事务中时改用保存点，
+// 失败时只撤销这一次写入，整批修改由 batch 最后一起提交或撤销。没有提交就丢弃时撤销
+struct Write<'a> {
+    connection: &'a Connection,
+    nested: bool,
+    finished: bool,
+}
+
+impl<'a> Write<'a> {
+    // immediate 为 true 时在读取之前就取得写锁
+    fn begin(connection: &'a Connection, immediate: bool) -> Result<Write<'a>, String> {
+        let nested = begin(connection, immediate)?;
+        Ok(Write {
+            connection,
+            nested,
+            finished: false,
+        })
+    }
+
+    fn commit(mut self) -> Result<(), String> {
+        finish(self.connection, self.nested, true)?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Deref for Write<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection
+    }
+}
+
+impl Drop for Write<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = finish(self.connection, self.nested, false);
+        }
+    }
+}
+
+// 开始事务或保存点，返回是不是嵌套在已有的事务中
+fn begin(connection: &Connection, immediate: bool) -> Result<bool, String> {
+    let nested = !connection.is_autocommit();
+    let statement = match (nested, immediate) {
+        (true, _) => "SAVEPOINT write",
+        (false, true) => "BEGIN IMMEDIATE",
+        (false, false) => "BEGIN",
+    };
+    connection.execute_batch(statement).map_err(sql_error)?;
+    Ok(nested)
+}
+
+// 提交或撤销 begin 开始的事务或保存点
+fn finish(connection: &Connection, nested: bool, commit: bool) -> Result<(), String> {
+    let statement = match (nested, commit) {
+        (true, true) => "RELEASE write",
+        (true, false) => "ROLLBACK TO write; RELEASE write",
+        (false, true) => "COMMIT",
+        (false, false) => "ROLLBACK",
+    };
+    connection.execute_batch(statement).map_err(sql_error)
+}
+
+// 替换已有学生的记录，学生不存在时返回错误
+fn replace_student(connection: &Connection, student: &Student) -> Result<(), String> {
+    let changed = connection
+        .execute(
+            "UPDATE students SET name = ?1, age = ?2, class = ?3 WHERE id = ?4",
+            params![student.name, student.age, student.class, student.id],
+        )
+        .map_err(sql_error)?;
+    if changed == 0 {
+        return Err(format!("未找到ID为 {} 的学生", student.id));
+    }
+    write_details(connection, student)
+}
+
+// 写入学生的选课、成绩、评估和修改历史，替换原来的记录
+fn write_details(connection: &Connection, student: &Student) -> Result<(), String> {
+    // 成绩和评估通过外键随选课一起删除
+    connection
+        .execute("DELETE FROM enrollments WHERE student_id = ?1", [student.id])
+        .map_err(sql_error)?;
+    for code in &student.enrollments {
+        connection
+            .execute(
+                "INSERT INTO enrollments (student_id, course_code) VALUES (?1, ?2)",
+                params![student.id, code],
+            )
+            .map_err(sql_error)?;
+    }
+    for (code, score) in &student.grades {
+        connection
+            .execute(
+                "INSERT INTO grades (student_id, course_code, score) VALUES (?1, ?2, ?3)",
+                params![student.id, code, *score as f64],
+            )
+            .map_err(sql_error)?;
+    }
+    for (code, assessments) in &student.assessments {
+        for (position, assessment) in assessments.iter().enumerate() {
+            connection
+                .execute(
+                    "INSERT INTO assessments
+                         (student_id, course_code, position, name, category, score, max_score, days_late)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        student.id,
+                        code,
+                        position as i64,
+                        assessment.name,
+                        assessment.category,
+                        assessment.score as f64,
+                        assessment.max_score as f64,
+                        assessment.days_late
+                    ],
+                )
+                .map_err(sql_error)?;
+        }
+    }
+    connection
+        .execute("DELETE FROM grade_history WHERE student_id = ?1", [student.id])
+        .map_err(sql_error)?;
+    for (code, changes) in &student.history {
+        for (position, change) in changes.iter().enumerate() {
+            connection
+                .execute(
+                    "INSERT INTO grade_history (student_id, course_code, position, time, old, new, reason)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        student.id,
+                        code,
+                        position as i64,
+                        change.time as i64,
+                        change.old.map(|score| score as f64),
+                        change.new.map(|score| score as f64),
+                        change.reason
+                    ],
+                )
+                .map_err(sql_error)?;
+        }
+    }
+    Ok(())
+}
+
+fn sql_error(error: rusqlite::Error) -> String {
+    format!("数据库错误: {}", error)
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::conformance;
+    use std::env;
+
+    #[test]
+    fn test_sqlite_backend_conformance() {
+        conformance::run(&mut || Box::new(SqliteRepository::open_in_memory().unwrap()));
+    }
+
+    #[test]
+    fn test_sqlite_file_reopen() {
+        let dir = env::temp_dir().join(format!("student_sqlite_{}", std::process::id()));
+        let path = dir.join("students.db");
+        conformance::check_reopen(&mut || Box::new(SqliteRepository::open(&path).unwrap()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_connections() {
+        let dir = env::temp_dir().join(format!("student_sqlite_concurrent_{}", std::process::id()));
+        let path = dir.join("students.db");
+        conformance::check_concurrent_writers(&|| Box::new(SqliteRepository::open(&path).unwrap()), 4, 20);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_subject_grades() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE students (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL, age INTEGER NOT NULL);
+                 CREATE TABLE grades (student_id INTEGER NOT NULL REFERENCES students(id) ON DELETE CASCADE,
+                     subject TEXT NOT NULL, score REAL NOT NULL, PRIMARY KEY (student_id, subject));
+                 INSERT INTO students (name, age) VALUES ('张三', 20), ('李四', 21);
+                 INSERT INTO grades VALUES (1, '数学', 90), (2, '数学 ', 80), (2, 'math', 70);",
+            )
+            .unwrap();
+        migrate(&mut connection).unwrap();
+        let repository = SqliteRepository { connection };
+
+        let codes: Vec<String> = repository.list_courses().unwrap().into_iter().map(|c| c.code).collect();
+        assert_eq!(codes, vec!["MATH", "数学"]);
+        let student = repository.get(2).unwrap().unwrap();
+        assert!(student.is_enrolled("数学") && student.is_enrolled("MATH"));
+        assert_eq!(student.grades["数学"], 80.0);
+        assert_eq!(repository.get(1).unwrap().unwrap().grades["数学"], 90.0);
+        // 旧的 students 表补上 class 列，原有学生没有分班
+        assert_eq!(student.class, None);
+    }
+
+    #[test]
+    fn test_migrate_version_2_courses() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE courses (code TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE,
+                     credits REAL NOT NULL, max_score REAL NOT NULL);
+                 INSERT INTO courses VALUES ('MATH', '数学', 4, 100);
+                 PRAGMA user_version = 2;",
+            )
+            .unwrap();
+        let mut repository = SqliteRepository::with_connection(connection).unwrap();
+        assert!(!repository.list_courses().unwrap()[0].pass_fail);
+
+        let mut pe = Course::new("PE", "体育", 1.0, 100.0).unwrap();
+        pe.pass_fail = true;
+        repository.insert_course(pe).unwrap();
+        assert!(repository.find_course("PE").unwrap().unwrap().pass_fail);
+    }
+
+    #[test]
+    fn test_not_a_database() {
+        let dir = env::temp_dir().join(format!("student_sqlite_bad_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("students.db");
+        fs::write(&path, "这不是数据库文件，但长度要足够让 SQLite 读取文件头。。。。。。。。。。。。").unwrap();
+
+        let error = SqliteRepository::open(&path).err().unwrap();
+        assert!(error.contains("改名备份"), "{}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
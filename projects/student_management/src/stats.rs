@@ -0,0 +1,184 @@
+// 课程统计：人数、平均分、中位数、标准差、最高最低分、四分位数、及格率和分数段分布，
+// 以及每个学生在课程中的百分位
+//
+// 统计使用学生 grades 中记录的原始分数；及格按课程满分换算成百分制后与换算表配置中的及格线比较，
+// 分数段使用默认换算表的等级（通过制课程只有 P 和 F 两段）。
+
+use crate::course::Course;
+use crate::grading::{self, GradingConfig};
+use crate::student::Student;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseStats {
+    pub count: usize,
+    pub mean: f32,
+    pub median: f32,
+    // 总体标准差（除以人数）
+    pub std_dev: f32,
+    pub min: f32,
+    pub max: f32,
+    // 下四分位数和上四分位数
+    pub q1: f32,
+    pub q3: f32,
+    // 及格人数占总人数的百分比
+    pub pass_rate: f32,
+    // 从高到低的分数段
+    pub histogram: Vec<Bucket>,
+}
+
+// 一个分数段和其中的人数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub label: String,
+    pub count: usize,
+}
+
+impl CourseStats {
+    // 课程还没有任何成绩时返回 None
+    pub fn compute(course: &Course, students: &[Student], grading: &GradingConfig) -> Option<CourseStats> {
+        let mut scores = scores(&course.code, students);
+        if scores.is_empty() {
+            return None;
+        }
+        scores.sort_by(|a, b| a.total_cmp(b));
+        let count = scores.len();
+        let mean = scores.iter().sum::<f32>() / count as f32;
+        let variance = scores.iter().map(|score| (score - mean).powi(2)).sum::<f32>() / count as f32;
+        let passed = scores
+            .iter()
+            .filter(|score| grading::percent(course, **score) >= grading.pass_percent)
+            .count();
+        Some(CourseStats {
+            count,
+            mean,
+            median: quantile(&scores, 0.5),
+            std_dev: variance.sqrt(),
+            min: scores[0],
+            max: scores[count - 1],
+            q1: quantile(&scores, 0.25),
+            q3: quantile(&scores, 0.75),
+            pass_rate: passed as f32 / count as f32 * 100.0,
+            histogram: histogram(course, &scores, grading),
+        })
+    }
+}
+
+// 课程所有成绩，按学生 ID 的顺序
+pub fn scores(code: &str, students: &[Student]) -> Vec<f32> {
+    students.iter().filter_map(|student| student.current_grade(code)).collect()
+}
+
+// 已排序的分数中第 q（0 到 1）分位的值，位置在两个分数之间时线性插值
+pub fn quantile(sorted: &[f32], q: f32) -> f32 {
+    let position = (sorted.len() - 1) as f32 * q;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f32)
+}
+
+// 百分位：分数低于 score 的人数加上同分人数的一半，占总人数的百分比
+pub fn percentile_rank(scores: &[f32], score: f32) -> f32 {
+    let below = scores.iter().filter(|other| **other < score).count();
+    let equal = scores.iter().filter(|other| **other == score).count();
+    (below as f32 + equal as f32 / 2.0) / scores.len() as f32 * 100.0
+}
+
+fn histogram(course: &Course, scores: &[f32], grading: &GradingConfig) -> Vec<Bucket> {
+    let percents: Vec<f32> = scores.iter().map(|score| grading::percent(course, *score)).collect();
+    if course.pass_fail {
+        let passed = percents.iter().filter(|percent| **percent >= grading.pass_percent).count();
+        return vec![
+            Bucket { label: format!("P {}-100", grading.pass_percent), count: passed },
+            Bucket { label: format!("F 0-{}", grading.pass_percent), count: percents.len() - passed },
+        ];
+    }
+    let bands = &grading.default().bands;
+    bands
+        .iter()
+        .enumerate()
+        .map(|(index, band)| {
+            let upper = if index == 0 { 100.0 } else { bands[index - 1].min };
+            let count = percents
+                .iter()
+                .filter(|percent| grading.default().band(**percent).letter == band.letter)
+                .count();
+            Bucket { label: format!("{} {}-{}", band.letter, band.min, upper), count }
+        })
+        .collect()
+}
+
+// ASCII 柱状图的一行，最多的分数段占满 width 个字符
+pub fn bar(count: usize, max_count: usize, width: usize) -> String {
+    if max_count == 0 {
+        return String::new();
+    }
+    let length = (count * width).div_ceil(max_count);
+    "#".repeat(length)
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn students(scores: &[f32]) -> Vec<Student> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(index, score)| {
+                let mut student = Student::new(index as u32 + 1, format!("学生{}", index + 1), 20);
+                student.enroll(String::from("MATH"));
+                student.add_grade(String::from("MATH"), *score);
+                student
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_course_stats() {
+        let math = Course::new("MATH", "数学", 4.0, 100.0).unwrap();
+        let mut students = students(&[55.0, 95.0, 72.0, 88.0, 60.0]);
+        students.push(Student::new(9, String::from("未选课"), 20));
+        let stats = CourseStats::compute(&math, &students, &GradingConfig::builtin()).unwrap();
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.mean, 74.0);
+        assert_eq!(stats.median, 72.0);
+        assert_eq!((stats.min, stats.max), (55.0, 95.0));
+        assert_eq!((stats.q1, stats.q3), (60.0, 88.0));
+        // 方差 = (19² + 21² + 2² + 14² + 14²) / 5 = 239.6
+        assert!((stats.std_dev - 239.6f32.sqrt()).abs() < 1e-4);
+        assert_eq!(stats.pass_rate, 80.0);
+        let counts: Vec<(&str, usize)> = stats.histogram.iter().map(|b| (b.label.as_str(), b.count)).collect();
+        assert_eq!(counts, vec![("A 90-100", 1), ("B 80-90", 1), ("C 70-80", 1), ("D 60-70", 1), ("F 0-60", 1)]);
+
+        let art = Course::new("ART", "美术", 1.0, 100.0).unwrap();
+        assert_eq!(CourseStats::compute(&art, &students, &GradingConfig::builtin()), None);
+    }
+
+    #[test]
+    fn test_quantile_and_percentile() {
+        assert_eq!(quantile(&[10.0, 20.0, 30.0, 40.0], 0.5), 25.0);
+        assert_eq!(quantile(&[10.0, 20.0, 30.0, 40.0], 0.25), 17.5);
+        assert_eq!(quantile(&[42.0], 0.75), 42.0);
+
+        let scores = [50.0, 60.0, 60.0, 90.0];
+        assert_eq!(percentile_rank(&scores, 90.0), 87.5);
+        assert_eq!(percentile_rank(&scores, 60.0), 50.0);
+        assert_eq!(percentile_rank(&scores, 50.0), 12.5);
+
+        assert_eq!(bar(3, 6, 10), "#####");
+        assert_eq!(bar(1, 6, 10), "##");
+        assert_eq!(bar(0, 6, 10), "");
+    }
+
+    #[test]
+    fn test_pass_fail_histogram() {
+        let mut pe = Course::new("MATH", "体育", 1.0, 50.0).unwrap();
+        pe.pass_fail = true;
+        let stats = CourseStats::compute(&pe, &students(&[20.0, 30.0, 45.0]), &GradingConfig::builtin()).unwrap();
+        // 满分 50，及格线 60% 即 30 分
+        assert_eq!(stats.histogram[0], Bucket { label: String::from("P 60-100"), count: 2 });
+        assert_eq!(stats.histogram[1].count, 1);
+        assert!((stats.pass_rate - 200.0 / 3.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,501 @@
+// 数据持久化：把学生管理系统保存为带版本号的 JSON 文件
+//
+// 文件格式：
+//
+//     {
+//       "version": 2,
+//       "next_id": 4,
+//       "courses": [
+//         { "code": "MATH101", "name": "数学", "credits": 4.0, "max_score": 100.0 }
+//       ],
+//       "students": [
+//         { "id": 1, "name": "张三", "age": 20, "enrollments": ["MATH101"], "grades": { "MATH101": 92.0 } }
+//       ]
+//     }
+//
+// 版本 1 的文件没有课程登记表，成绩按输入的课程名称保存；加载时会自动转换，
+// 每个不同的课程名称（去掉首尾空白）登记为一门课程，下次保存时写成版本 2。
+//
+// 保存时先写入同目录下的临时文件再重命名，中途退出也不会留下写了一半的数据文件。
+//
+// 几个程序可以同时使用同一个数据文件：每次修改都先锁住 <数据文件名>.lock，重新读取文件，
+// 在最新的数据上修改后保存再释放锁，不会覆盖其他程序的修改；读取时发现文件变了就重新加载。
+
+use std::cell::{Ref, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::{self, Course};
+use crate::lock::FileLock;
+use crate::repository::StudentRepository;
+use crate::student::{self, Student, StudentManagement};
+
+// 当前的数据文件格式版本，格式变化时递增
+pub const FORMAT_VERSION: u32 = 2;
+
+// 设置这个环境变量可以把数据文件放到其他位置
+pub const DATA_PATH_VARIABLE: &str = "STUDENT_DATA_FILE";
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    next_id: u32,
+    // 版本 1 没有这一项
+    #[serde(default)]
+    courses: Vec<Course>,
+    students: Vec<Student>,
+}
+
+impl StudentManagement {
+    // 原子地保存到文件：写入临时文件、刷新到磁盘，再替换原文件
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let document = Document {
+            version: FORMAT_VERSION,
+            next_id: self.next_id,
+            courses: self.courses.values().cloned().collect(),
+            students: self.students().into_iter().cloned().collect(),
+        };
+        let json = serde_json::to_string_pretty(&document).map_err(|e| format!("无法生成 JSON: {}", e))?;
+        write_atomically(path, json.as_bytes())
+    }
+
+    // 从文件加载，文件损坏或版本不支持时返回说明如何恢复的错误，不会修改文件
+    pub fn load(path: &Path) -> Result<StudentManagement, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取数据文件 {}: {}", path.display(), e))?;
+        StudentManagement::from_json(&text).map_err(|e| {
+            format!(
+                "数据文件 {} 无法加载: {}\n文件没有被修改。可以手动修复它，或者把它改名备份后重新启动程序，从空数据开始。",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    // 文件不存在时返回空的系统，其余错误与 load 相同
+    pub fn load_or_new(path: &Path) -> Result<StudentManagement, String> {
+        if path.exists() {
+            StudentManagement::load(path)
+        } else {
+            Ok(StudentManagement::new())
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<StudentManagement, String> {
+        // 先只读取版本号，新版本的文件可能有当前程序不认识的结构
+        let value: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("不是有效的 JSON（{}）", e))?;
+        let version = match value.get("version").and_then(|v| v.as_u64()) {
+            Some(version) if (1..=FORMAT_VERSION as u64).contains(&version) => version,
+            Some(version) => {
+                return Err(format!(
+                    "文件格式版本是 {}，本程序只支持版本 {}",
+                    version, FORMAT_VERSION
+                ))
+            }
+            None => return Err(String::from("缺少文件格式版本号 version")),
+        };
+
+        let mut document: Document =
+            serde_json::from_value(value).map_err(|e| format!("数据结构不正确（{}）", e))?;
+        if version == 1 {
+            migrate_v1(&mut document)?;
+        }
+
+        let mut courses = BTreeMap::new();
+        for course in document.courses {
+            course::check_new_course(&courses.values().cloned().collect::<Vec<Course>>(), &course)?;
+            courses.insert(course.code.clone(), course);
+        }
+        let course_list: Vec<Course> = courses.values().cloned().collect();
+
+        let mut students = HashMap::new();
+        for student in document.students {
+            let id = student.id;
+            course::check_student(&student, &course_list).map_err(|e| format!("学生 {}: {}", id, e))?;
+            if students.insert(id, student).is_some() {
+                return Err(format!("学生 ID {} 重复", id));
+            }
+        }
+
+        // next_id 必须大于所有已有的 ID，否则新学生会覆盖旧记录
+        let max_id = students.keys().max().copied().unwrap_or(0);
+        let after_max = student::id_after(max_id).ok_or("学生 ID 超出范围")?;
+        if document.next_id == u32::MAX {
+            return Err(String::from("学生 ID 超出范围"));
+        }
+        Ok(StudentManagement {
+            students,
+            next_id: document.next_id.max(after_max),
+            courses,
+        })
+    }
+}
+
+// 版本 1 -> 2：把按名称保存的成绩转换成课程登记表、选课和按课程代码保存的成绩
+fn migrate_v1(document: &mut Document) -> Result<(), String> {
+    let mut grades = Vec::new();
+    for student in &mut document.students {
+        for (subject, score) in std::mem::take(&mut student.grades) {
+            grades.push((student.id, subject, score));
+        }
+    }
+
+    let (courses, grades) = course::courses_from_subjects(&grades)?;
+    for (id, code, score) in grades {
+        if let Some(student) = document.students.iter_mut().find(|student| student.id == id) {
+            student.enroll(code.clone());
+            student.add_grade(code, score);
+        }
+    }
+    document.courses = courses;
+    Ok(())
+}
+
+// JSON 文件后端：数据缓存在内存中，每次修改都立刻原子地写回文件
+#[derive(Debug)]
+pub struct JsonFileRepository {
+    path: PathBuf,
+    cache: RefCell<Cache>,
+}
+
+// 内存中的数据和读取时文件的状态
+#[derive(Debug)]
+struct Cache {
+    data: StudentManagement,
+    stamp: Option<Stamp>,
+}
+
+// 文件的大小和修改时间，任何一个变了就说明文件被替换过；文件不存在时为 None
+type Stamp = (u64, Option<SystemTime>);
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    fs::metadata(path).ok().map(|metadata| (metadata.len(), metadata.modified().ok()))
+}
+
+impl JsonFileRepository {
+    // 打开数据文件，文件不存在时从空数据开始，第一次修改时创建
+    pub fn open(path: &Path) -> Result<JsonFileRepository, String> {
+        let stamp = stamp(path);
+        Ok(JsonFileRepository {
+            path: path.to_path_buf(),
+            cache: RefCell::new(Cache {
+                data: StudentManagement::load_or_new(path)?,
+                stamp,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 当前的数据；其他程序修改过文件时先重新读取
+    fn data(&self) -> Result<Ref<'_, StudentManagement>, String> {
+        let stamp = stamp(&self.path);
+        if self.cache.borrow().stamp != stamp {
+            let data = StudentManagement::load_or_new(&self.path)?;
+            *self.cache.borrow_mut() = Cache { data, stamp };
+        }
+        Ok(Ref::map(self.cache.borrow(), |cache| &cache.data))
+    }
+
+    // 锁住数据文件，在重新读取的最新数据上修改并保存；保存失败时文件和内存中的数据都保持不变
+    fn write<T>(&mut self, change: impl FnOnce(&mut StudentManagement) -> Result<T, String>) -> Result<T, String> {
+        let _lock = FileLock::exclusive(&self.path)?;
+        let mut data = StudentManagement::load_or_new(&self.path)?;
+        let result = change(&mut data)?;
+        data.save(&self.path)?;
+        let stamp = stamp(&self.path);
+        *self.cache.get_mut() = Cache { data, stamp };
+        Ok(result)
+    }
+}
+
+impl StudentRepository for JsonFileRepository {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        self.data()?.get(id)
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        self.write(|data| data.insert(name, age))
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        self.write(|data| data.update(student))
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        self.write(|data| data.modify(id, change))
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        // 没有删除任何记录时不需要重写文件
+        if self.data()?.get_student(id).is_none() {
+            return Ok(false);
+        }
+        self.write(|data| data.remove(id))
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        self.write(|data| data.restore(student))
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        // 整批修改只锁一次、保存一次文件，change 失败时不保存
+        self.write(|data| change(data))
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.data()?.list()
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        self.write(|data| data.insert_course(course))
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        if !self.data()?.courses.contains_key(&course::normalize_code(code)) {
+            return Ok(false);
+        }
+        self.write(|data| data.remove_course(code))
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        self.data()?.list_courses()
+    }
+}
+
+// 先写入同目录下的临时文件，成功后再重命名为目标文件；
+// 同一文件系统内的重命名是原子操作，读者看到的要么是旧文件要么是新文件
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建目录 {}: {}", parent.display(), e))?;
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("无效的文件路径: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!("{}.tmp", file_name.to_string_lossy()));
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("无法保存到 {}: {}", path.display(), e)
+    })
+}
+
+// 数据文件的默认位置：环境变量 STUDENT_DATA_FILE，否则是 data_dir() 下的 students.json
+pub fn default_path() -> PathBuf {
+    match env::var_os(DATA_PATH_VARIABLE) {
+        Some(path) => PathBuf::from(path),
+        None => data_dir().join("students.json"),
+    }
+}
+
+// 各种数据文件默认所在的目录：
+// - Linux: $XDG_DATA_HOME 或 ~/.local/share
+// - macOS: ~/Library/Application Support
+// - Windows: %APPDATA%
+// 下的 rust_learning/student_management，找不到用户目录时使用当前目录
+pub fn data_dir() -> PathBuf {
+    let data_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    };
+
+    match data_dir {
+        Some(dir) => dir.join("rust_learning").join("student_management"),
+        None => PathBuf::new(),
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+    use crate::repository::conformance;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("student_storage_{}_{}", name, std::process::id()))
+            .join("students.json")
+    }
+
+    fn sample() -> StudentManagement {
+        let clock = ManualClock::default();
+        let mut system = StudentManagement::new();
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        system.insert_course(math.clone()).unwrap();
+        let id = system.add_student(String::from("张三"), 20).unwrap();
+        let student = system.get_student_mut(id).unwrap();
+        student.enroll(math.code.clone());
+        student.record_grade(&math, 92.0, "", &clock).unwrap();
+        let removed = system.add_student(String::from("李四"), 21).unwrap();
+        system.remove_student(removed);
+        system
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = temp_path("round_trip");
+        let system = sample();
+        system.save(&path).unwrap();
+
+        let loaded = StudentManagement::load(&path).unwrap();
+        assert_eq!(loaded, system);
+        // 删除的学生 ID 不会在重新加载后被再次使用
+        assert_eq!(loaded.next_id(), 3);
+        // 临时文件已经被重命名
+        assert!(!path.with_file_name("students.json.tmp").exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_load_or_new_without_file() {
+        let path = temp_path("missing");
+        let system = StudentManagement::load_or_new(&path).unwrap();
+        assert_eq!(system, StudentManagement::new());
+    }
+
+    #[test]
+    fn test_corrupt_file_gives_recovery_message() {
+        let path = temp_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{ \"version\": 1, \"next_id\": 2, \"students\": [").unwrap();
+
+        let error = StudentManagement::load(&path).unwrap_err();
+        assert!(error.contains("不是有效的 JSON"), "{}", error);
+        assert!(error.contains("改名备份"), "{}", error);
+        // 损坏的文件保持原样
+        assert!(fs::read_to_string(&path).unwrap().ends_with('['));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_version_and_structure_checks() {
+        let newer = r#"{ "version": 3, "records": [] }"#;
+        assert!(StudentManagement::from_json(newer).unwrap_err().contains("版本是 3"));
+        assert!(StudentManagement::from_json(r#"{ "students": [] }"#).is_err());
+        assert!(StudentManagement::from_json(r#"{ "version": 1, "next_id": 1 }"#).is_err());
+
+        let duplicate = r#"{ "version": 1, "next_id": 3, "students": [
+            { "id": 1, "name": "张三", "age": 20, "grades": {} },
+            { "id": 1, "name": "李四", "age": 21, "grades": {} }
+        ] }"#;
+        assert!(StudentManagement::from_json(duplicate).unwrap_err().contains("重复"));
+    }
+
+    #[test]
+    fn test_next_id_is_repaired() {
+        let stale = r#"{ "version": 1, "next_id": 1, "students": [
+            { "id": 5, "name": "张三", "age": 20, "grades": { "数学": 90.0 } }
+        ] }"#;
+        let system = StudentManagement::from_json(stale).unwrap();
+        assert_eq!(system.next_id(), 6);
+        assert_eq!(system.get_student(5).unwrap().grades["数学"], 90.0);
+
+        // 最大的 ID 之后没有可以分配的 ID
+        let largest = r#"{ "version": 2, "next_id": 1, "courses": [], "students": [
+            { "id": 4294967295, "name": "a", "age": 3, "grades": {} }
+        ] }"#;
+        assert_eq!(StudentManagement::from_json(largest).unwrap_err(), "学生 ID 超出范围");
+        let exhausted = r#"{ "version": 2, "next_id": 4294967295, "courses": [], "students": [] }"#;
+        assert_eq!(StudentManagement::from_json(exhausted).unwrap_err(), "学生 ID 超出范围");
+    }
+
+    #[test]
+    fn test_migrate_version_1() {
+        let old = r#"{ "version": 1, "next_id": 3, "students": [
+            { "id": 1, "name": "张三", "age": 20, "grades": { "数学": 90.0, "英语": 120.0 } },
+            { "id": 2, "name": "李四", "age": 21, "grades": { "数学 ": 80.0, "math": 70.0 } }
+        ] }"#;
+        let system = StudentManagement::from_json(old).unwrap();
+
+        // "数学" 和 "数学 " 合并为一门课程，"math" 是另一门
+        let courses = system.list_courses().unwrap();
+        let codes: Vec<&str> = courses.iter().map(|c| c.code.as_str()).collect();
+        assert_eq!(codes, vec!["MATH", "数学", "英语"]);
+        // 满分取 100 和已有最高分中较大的
+        assert_eq!(courses[2].max_score, 120.0);
+
+        let student = system.get_student(2).unwrap();
+        assert!(student.is_enrolled("数学") && student.is_enrolled("MATH"));
+        assert_eq!(student.grades["数学"], 80.0);
+
+        // 再次保存后是版本 2
+        let path = temp_path("migrate");
+        system.save(&path).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("\"version\": 2"));
+        assert_eq!(StudentManagement::load(&path).unwrap(), system);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_inconsistent_courses_are_rejected() {
+        let ungraded = r#"{ "version": 2, "next_id": 2, "courses": [], "students": [
+            { "id": 1, "name": "张三", "age": 20, "enrollments": ["ART"], "grades": {} }
+        ] }"#;
+        assert!(StudentManagement::from_json(ungraded).unwrap_err().contains("ART"));
+    }
+
+    #[test]
+    fn test_json_backend_conformance() {
+        let path = temp_path("conformance");
+        conformance::run(&mut || {
+            let _ = fs::remove_file(&path);
+            Box::new(JsonFileRepository::open(&path).unwrap())
+        });
+        let _ = fs::remove_file(&path);
+        conformance::check_reopen(&mut || Box::new(JsonFileRepository::open(&path).unwrap()));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers() {
+        let path = temp_path("concurrent");
+        let _ = fs::remove_file(&path);
+        conformance::check_concurrent_writers(&|| Box::new(JsonFileRepository::open(&path).unwrap()), 4, 20);
+
+        // 另一个程序修改文件后，已经打开的仓库读取时看到新的数据
+        let reader = JsonFileRepository::open(&path).unwrap();
+        let mut writer = JsonFileRepository::open(&path).unwrap();
+        let id = writer.insert(String::from("李四"), 21).unwrap();
+        assert_eq!(reader.get(id).unwrap().unwrap().name, "李四");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_failed_save_keeps_memory_unchanged() {
+        // 数据文件的父路径是一个普通文件，保存一定会失败
+        let blocker = temp_path("blocked");
+        fs::create_dir_all(blocker.parent().unwrap()).unwrap();
+        fs::write(&blocker, "").unwrap();
+        let mut repository = JsonFileRepository::open(&blocker.join("students.json")).unwrap();
+
+        assert!(repository.insert(String::from("张三"), 20).is_err());
+        assert!(repository.list().unwrap().is_empty());
+
+        fs::remove_dir_all(blocker.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,462 @@
+// 学生和学生管理系统
+// 展示结构体、函数、方法和所有权的实际应用
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::course::{self, Course};
+use crate::gradebook::{self, Assessment};
+use crate::grading::{self, GradingConfig};
+use crate::history::{self, Clock, GradeChange};
+use crate::repository::StudentRepository;
+
+// 允许的年龄范围
+pub const AGE_RANGE: RangeInclusive<u8> = 1..=150;
+
+// 定义学生结构体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Student {
+    pub id: u32,
+    pub name: String,
+    pub age: u8,
+    // 所在班级，例如 "一班"；没有分班时为 None
+    #[serde(default)]
+    pub class: Option<String>,
+    // 选修的课程代码
+    #[serde(default)]
+    pub enrollments: BTreeSet<String>,
+    // 课程代码 -> 分数，按课程代码排序，显示和保存的顺序都是固定的
+    pub grades: BTreeMap<String, f32>,
+    // 课程代码 -> 按记录顺序排列的作业和考试成绩，只用于有评分类别的课程
+    #[serde(default)]
+    pub assessments: BTreeMap<String, Vec<Assessment>>,
+    // 课程代码 -> 按时间顺序排列的成绩修改记录
+    #[serde(default)]
+    pub history: BTreeMap<String, Vec<GradeChange>>,
+}
+
+// 为 Student 实现方法
+impl Student {
+    // 关联函数（静态方法）：创建新学生
+    pub fn new(id: u32, name: String, age: u8) -> Student {
+        Student {
+            id,
+            name,
+            age,
+            class: None,
+            enrollments: BTreeSet::new(),
+            grades: BTreeMap::new(),
+            assessments: BTreeMap::new(),
+            history: BTreeMap::new(),
+        }
+    }
+
+    // 选修课程，已经选修过时返回 false
+    pub fn enroll(&mut self, code: String) -> bool {
+        self.enrollments.insert(code)
+    }
+
+    pub fn is_enrolled(&self, code: &str) -> bool {
+        self.enrollments.contains(code)
+    }
+
+    // 直接写入课程成绩，不做检查，也不记录历史；用于从存储中加载数据。
+    // 保存时存储后端会检查是否选修了这门课程
+    pub fn add_grade(&mut self, code: String, score: f32) {
+        self.grades.insert(code, score);
+    }
+
+    // 写入课程成绩并记录修改历史，分数没有变化时不记录；reason 为空时使用默认原因
+    pub fn set_grade(&mut self, code: &str, score: f32, reason: &str, clock: &dyn Clock) {
+        let old = self.grades.insert(code.to_string(), score);
+        if old == Some(score) {
+            return;
+        }
+        let reason = match reason.trim() {
+            "" => history::default_reason(old, Some(score)),
+            reason => reason,
+        };
+        self.history.entry(code.to_string()).or_default().push(GradeChange {
+            time: clock.now(),
+            old,
+            new: Some(score),
+            reason: reason.to_string(),
+        });
+    }
+
+    // 课程的当前成绩
+    pub fn current_grade(&self, code: &str) -> Option<f32> {
+        self.grades.get(code).copied()
+    }
+
+    // 课程成绩的修改历史，按时间顺序排列
+    pub fn grade_history(&self, code: &str) -> &[GradeChange] {
+        self.history.get(code).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // 为选修的课程记录成绩，分数必须在 0 到课程满分之间，修改会记入历史
+    pub fn record_grade(&mut self, course: &Course, score: f32, reason: &str, clock: &dyn Clock) -> Result<(), String> {
+        if !self.is_enrolled(&course.code) {
+            return Err(format!(
+                "学生 {} 没有选修课程 {}（{}），请先选课",
+                self.id, course.code, course.name
+            ));
+        }
+        course.check_direct_grade()?;
+        course.check_score(score)?;
+        self.set_grade(&course.code, score, reason, clock);
+        Ok(())
+    }
+
+    // 记录一次作业或考试的成绩，同名的评估会被替换，然后按课程的评分规则重新计算课程成绩，
+    // 课程成绩的变化记入历史
+    pub fn record_assessment(&mut self, course: &Course, assessment: Assessment, clock: &dyn Clock) -> Result<(), String> {
+        if !self.is_enrolled(&course.code) {
+            return Err(format!(
+                "学生 {} 没有选修课程 {}（{}），请先选课",
+                self.id, course.code, course.name
+            ));
+        }
+        if course.category(&assessment.category).is_none() {
+            let names: Vec<&str> = course.categories.iter().map(|category| category.name.as_str()).collect();
+            return Err(if names.is_empty() {
+                format!("课程 {} 没有评分类别，请直接记录课程成绩", course.code)
+            } else {
+                format!("课程 {} 没有评分类别 {}（可选 {}）", course.code, assessment.category, names.join("、"))
+            });
+        }
+
+        let reason = format!("评估 {}", assessment.name);
+        let assessments = self.assessments.entry(course.code.clone()).or_default();
+        match assessments.iter_mut().find(|existing| existing.name == assessment.name) {
+            Some(existing) => *existing = assessment,
+            None => assessments.push(assessment),
+        }
+        if let Some(percent) = gradebook::course_percent(&course.categories, assessments) {
+            self.set_grade(&course.code, percent * course.max_score / 100.0, &reason, clock);
+        }
+        Ok(())
+    }
+
+    // 计算所有成绩的简单平均分，不考虑学分和满分；按学分加权的平均分见 grading::weighted_average
+    pub fn average_grade(&self) -> f32 {
+        if self.grades.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = self.grades.values().sum();
+        sum / (self.grades.len() as f32)
+    }
+
+    // 显示学生信息，课程名称和学分来自课程登记表，等级和 GPA 使用默认换算表
+    pub fn display(&self, courses: &[Course], grading: &GradingConfig) {
+        println!("学生ID: {}", self.id);
+        println!("姓名: {}", self.name);
+        println!("年龄: {}", self.age);
+        if let Some(class) = &self.class {
+            println!("班级: {}", class);
+        }
+        println!("选修课程:");
+
+        if self.enrollments.is_empty() {
+            println!("  尚未选修课程");
+            return;
+        }
+        for code in &self.enrollments {
+            let course = courses.iter().find(|course| &course.code == code);
+            let name = match course {
+                Some(course) if course.pass_fail => {
+                    format!("{} {}（{} 学分，通过制）", code, course.name, course.credits)
+                }
+                Some(course) => format!("{} {}（{} 学分）", code, course.name, course.credits),
+                None => code.clone(),
+            };
+            match (self.grades.get(code), course) {
+                (Some(score), Some(course)) => {
+                    println!("  {}: {:.1} {}", name, score, grading.letter(course, *score))
+                }
+                (Some(score), None) => println!("  {}: {:.1}", name, score),
+                (None, _) => println!("  {}: 尚无成绩", name),
+            }
+            for assessment in self.assessments.get(code).into_iter().flatten() {
+                let late = if assessment.days_late > 0 {
+                    format!("，迟交 {} 天", assessment.days_late)
+                } else {
+                    String::new()
+                };
+                println!(
+                    "    {}（{}）: {}/{}{}",
+                    assessment.name, assessment.category, assessment.score, assessment.max_score, late
+                );
+            }
+        }
+        if let Some(average) = grading::weighted_average(self, courses) {
+            println!("平均分（学分加权）: {:.2}", average);
+        }
+        if let Some(gpa) = grading.gpa(self, courses) {
+            let scale = grading.default();
+            println!("GPA（{} 制）: {:.2} / {:.1}", scale.name, gpa, scale.max_points());
+        }
+    }
+}
+
+// 学生 ID 之后的下一个 ID。下一个 ID 总是小于 u32::MAX（数据文件中的 next_id 为 u32::MAX 视为超出范围），
+// 所以最大的学生 ID 是 u32::MAX - 2，所有后端都按这个规则分配和放回 ID
+pub(crate) fn id_after(id: u32) -> Option<u32> {
+    id.checked_add(1).filter(|next| *next < u32::MAX)
+}
+
+// 学生管理系统结构体
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudentManagement {
+    pub(crate) students: HashMap<u32, Student>,
+    pub(crate) next_id: u32,
+    // 课程代码 -> 课程
+    pub(crate) courses: BTreeMap<String, Course>,
+}
+
+// 为 StudentManagement 实现方法
+impl StudentManagement {
+    // 创建新的学生管理系统
+    pub fn new() -> StudentManagement {
+        StudentManagement {
+            students: HashMap::new(),
+            next_id: 1,
+            courses: BTreeMap::new(),
+        }
+    }
+
+    // 下一个新学生将使用的 ID
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    // 添加学生，ID 用完时返回错误
+    pub fn add_student(&mut self, name: String, age: u8) -> Result<u32, String> {
+        let id = self.next_id;
+        // next_id 停在最大的 ID 上，不会绕回已经用过的 ID
+        self.next_id = id_after(id).ok_or("学生 ID 已经用完")?;
+        let student = Student::new(id, name, age);
+
+        self.students.insert(id, student);
+
+        Ok(id) // 返回新添加学生的ID
+    }
+
+    // 获取学生，返回可变引用
+    pub fn get_student_mut(&mut self, id: u32) -> Option<&mut Student> {
+        self.students.get_mut(&id)
+    }
+
+    // 获取学生，返回不可变引用
+    pub fn get_student(&self, id: u32) -> Option<&Student> {
+        self.students.get(&id)
+    }
+
+    // 删除学生
+    pub fn remove_student(&mut self, id: u32) -> bool {
+        self.students.remove(&id).is_some()
+    }
+
+    // 按 ID 排序的所有学生
+    pub fn students(&self) -> Vec<&Student> {
+        let mut students: Vec<&Student> = self.students.values().collect();
+        students.sort_by_key(|student| student.id);
+        students
+    }
+
+    // 显示所有学生的基本信息
+    pub fn list_all_students(&self, grading: &GradingConfig) {
+        let students: Vec<Student> = self.students().into_iter().cloned().collect();
+        let courses: Vec<Course> = self.courses.values().cloned().collect();
+        print_student_list(&students, &courses, grading);
+    }
+}
+
+// 内存后端：数据只保存在 HashMap 中
+impl StudentRepository for StudentManagement {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        Ok(self.get_student(id).cloned())
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        self.add_student(name, age)
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        course::check_student(&student, &self.list_courses()?)?;
+        match self.get_student_mut(student.id) {
+            Some(existing) => {
+                *existing = student;
+                Ok(())
+            }
+            None => Err(format!("未找到ID为 {} 的学生", student.id)),
+        }
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        Ok(self.remove_student(id))
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        if self.students.contains_key(&student.id) {
+            return Err(format!("ID {} 已经被学生使用", student.id));
+        }
+        course::check_student(&student, &self.list_courses()?)?;
+        let after = id_after(student.id).ok_or("学生 ID 超出范围")?;
+        self.next_id = self.next_id.max(after);
+        self.students.insert(student.id, student);
+        Ok(())
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        // 在副本上修改，全部成功后才替换原来的数据
+        let mut copy = self.clone();
+        change(&mut copy)?;
+        *self = copy;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        Ok(self.students().into_iter().cloned().collect())
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        course::check_new_course(&self.list_courses()?, &course)?;
+        self.courses.insert(course.code.clone(), course);
+        Ok(())
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        let code = course::normalize_code(code);
+        if !self.courses.contains_key(&code) {
+            return Ok(false);
+        }
+        course::check_unused(&code, &self.list()?)?;
+        Ok(self.courses.remove(&code).is_some())
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        Ok(self.courses.values().cloned().collect())
+    }
+}
+
+// 以表格形式显示学生的基本信息，平均分按学分加权，没有计分课程的成绩时显示 -
+pub fn print_student_list(students: &[Student], courses: &[Course], grading: &GradingConfig) {
+    if students.is_empty() {
+        println!("系统中尚无学生记录");
+        return;
+    }
+
+    let gpa_title = format!("GPA（{}）", grading.default().name);
+    println!("{:-^60}", " 学生列表 ");
+    println!("{:<5} | {:<15} | {:<5} | {:<10} | {:<10}", "ID", "姓名", "年龄", "平均分", gpa_title);
+    println!("{:-^60}", "");
+
+    for student in students {
+        println!(
+            "{:<5} | {:<15} | {:<5} | {:<10} | {:<10}",
+            student.id,
+            student.name,
+            student.age,
+            format_optional(grading::weighted_average(student, courses)),
+            format_optional(grading.gpa(student, courses))
+        );
+    }
+}
+
+// 保留两位小数，没有数值时显示 -
+fn format_optional(value: Option<f32>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => String::from("-"),
+    }
+}
+
+impl Default for StudentManagement {
+    fn default() -> StudentManagement {
+        StudentManagement::new()
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+
+    #[test]
+    fn test_add_and_remove_students() {
+        let mut system = StudentManagement::new();
+        let first = system.add_student(String::from("张三"), 20).unwrap();
+        let second = system.add_student(String::from("李四"), 21).unwrap();
+        assert_eq!((first, second), (1, 2));
+
+        assert!(system.remove_student(first));
+        assert!(!system.remove_student(first));
+        // 删除后 ID 不会被重新使用
+        assert_eq!(system.add_student(String::from("王五"), 19), Ok(3));
+        assert_eq!(system.students().iter().map(|s| s.id).collect::<Vec<u32>>(), vec![2, 3]);
+
+        // 放回最大的 ID 之后没有可以分配的 ID（撤销、重做和重放审计日志都会放回学生）
+        let largest = Student::new(u32::MAX, String::from("赵六"), 22);
+        assert_eq!(system.restore(largest).unwrap_err(), "学生 ID 超出范围");
+        assert_eq!(system.get_student(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_record_grade() {
+        let clock = ManualClock::default();
+        let course = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        let mut student = Student::new(1, String::from("张三"), 20);
+        assert!(student.record_grade(&course, 90.0, "", &clock).unwrap_err().contains("请先选课"));
+        assert!(student.enroll(course.code.clone()));
+        assert!(!student.enroll(course.code.clone()));
+        assert!(student.record_grade(&course, 101.0, "", &clock).is_err());
+        student.record_grade(&course, 90.0, "", &clock).unwrap();
+        assert_eq!(student.grades["MATH101"], 90.0);
+    }
+
+    #[test]
+    fn test_record_assessment() {
+        let clock = ManualClock::default();
+        let categories = vec![
+            gradebook::Category::parse("作业=40,drop=1").unwrap(),
+            gradebook::Category::parse("考试=60").unwrap(),
+        ];
+        let course = Course::new("MATH101", "数学", 4.0, 150.0).unwrap().with_categories(categories).unwrap();
+        let mut student = Student::new(1, String::from("张三"), 20);
+        student.enroll(course.code.clone());
+        assert!(student.record_grade(&course, 90.0, "", &clock).unwrap_err().contains("assess"));
+
+        let homework = |name: &str, score: f32| Assessment::new(name, "作业", score, 10.0, 0).unwrap();
+        student.record_assessment(&course, homework("作业1", 8.0), &clock).unwrap();
+        student.record_assessment(&course, homework("作业2", 4.0), &clock).unwrap();
+        // 去掉最低的作业2，作业平均 80 分，换算到满分 150 是 120
+        assert_eq!(student.grades["MATH101"], 120.0);
+
+        student.record_assessment(&course, Assessment::new("期末", "考试", 60.0, 100.0, 0).unwrap(), &clock).unwrap();
+        assert!((student.grades["MATH101"] - (80.0 * 0.4 + 60.0 * 0.6) * 1.5).abs() < 1e-3);
+        // 同名评估替换原来的成绩，作业1 的 80 分变成最低分被去掉
+        student.record_assessment(&course, homework("作业2", 10.0), &clock).unwrap();
+        assert_eq!(student.assessments["MATH101"].len(), 3);
+        assert!((student.grades["MATH101"] - (100.0 * 0.4 + 60.0 * 0.6) * 1.5).abs() < 1e-3);
+
+        assert!(course::check_student(&student, std::slice::from_ref(&course)).is_ok());
+        student.add_grade(course.code.clone(), 150.0);
+        assert!(course::check_student(&student, std::slice::from_ref(&course)).unwrap_err().contains("不一致"));
+        let quiz = Assessment::new("测验", "测验", 5.0, 10.0, 0).unwrap();
+        assert!(student.record_assessment(&course, quiz, &clock).unwrap_err().contains("作业、考试"));
+    }
+
+    #[test]
+    fn test_average_grade() {
+        let mut student = Student::new(1, String::from("张三"), 20);
+        assert_eq!(student.average_grade(), 0.0);
+        student.add_grade(String::from("数学"), 90.0);
+        student.add_grade(String::from("语文"), 80.0);
+        assert_eq!(student.average_grade(), 85.0);
+    }
+}
@@ -0,0 +1,695 @@
+// 全屏终端界面：可以滚动的学生表格、显示成绩的详情栏、带检查的编辑表单、边输入边搜索和快捷键
+//
+//     ↑/↓ 或 k/j 选择学生    PgUp/PgDn 翻页    Home/End 第一个/最后一个
+//     /  搜索（姓名、班级或 ID，输入时立即筛选）    Esc 清除搜索
+//     a  添加学生    e  修改学生    c  选课    g  记录成绩    d  删除学生
+//     u  撤销    r  重做    ?  帮助    q  退出
+//
+// 和交互式菜单一样，所有修改都通过 StudentRepository 完成并记入撤销历史，
+// 检查年龄、分数、选课的规则和提示信息与菜单相同。界面状态（App）与终端无关，可以直接测试。
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::Frame;
+
+use crate::cli::Context;
+use crate::course::Course;
+use crate::grading;
+use crate::import;
+use crate::repository::StudentRepository;
+use crate::student::Student;
+use crate::undo::Undoable;
+
+// PgUp/PgDn 一次移动的行数
+const PAGE: usize = 10;
+
+const HELP: &str = "↑/↓ 选择  / 搜索  a 添加  e 修改  c 选课  g 成绩  d 删除  u 撤销  r 重做  ? 帮助  q 退出";
+
+// 表单的种类，修改、选课和记录成绩针对一个学生
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormKind {
+    AddStudent,
+    EditStudent(u32),
+    Enroll(u32),
+    Grade(u32),
+}
+
+// 弹出的编辑表单
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    pub kind: FormKind,
+    // (标签, 输入的内容)
+    pub fields: Vec<(String, String)>,
+    // 正在输入的字段
+    pub focus: usize,
+    // 提交时检查不通过的原因，表单保持打开
+    pub error: Option<String>,
+}
+
+impl Form {
+    fn new(kind: FormKind, fields: &[(&str, String)]) -> Form {
+        Form {
+            kind,
+            fields: fields.iter().map(|(label, value)| (label.to_string(), value.clone())).collect(),
+            focus: 0,
+            error: None,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.kind {
+            FormKind::AddStudent => String::from(" 添加学生 "),
+            FormKind::EditStudent(id) => format!(" 修改学生 {} ", id),
+            FormKind::Enroll(id) => format!(" 学生 {} 选课 ", id),
+            FormKind::Grade(id) => format!(" 为学生 {} 记录成绩 ", id),
+        }
+    }
+
+    fn value(&self, index: usize) -> &str {
+        self.fields[index].1.trim()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    Browse,
+    // 正在输入搜索内容
+    Search,
+    Form(Form),
+    // 确认删除这个 ID 的学生
+    ConfirmDelete(u32),
+    Help,
+}
+
+// 界面状态
+pub struct App {
+    students: Vec<Student>,
+    courses: Vec<Course>,
+    pub search: String,
+    // 符合搜索条件的学生在 students 中的位置
+    visible: Vec<usize>,
+    // 选中的行在 visible 中的位置
+    selected: usize,
+    pub mode: Mode,
+    // 状态栏显示的上一次操作结果
+    pub message: Option<String>,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(repository: &dyn StudentRepository) -> Result<App, String> {
+        let mut app = App {
+            students: Vec::new(),
+            courses: Vec::new(),
+            search: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            mode: Mode::Browse,
+            message: None,
+            quit: false,
+        };
+        app.reload(repository)?;
+        Ok(app)
+    }
+
+    // 选中的学生
+    pub fn selected(&self) -> Option<&Student> {
+        self.visible.get(self.selected).map(|index| &self.students[*index])
+    }
+
+    // 符合搜索条件的学生，按 ID 排列
+    pub fn visible(&self) -> Vec<&Student> {
+        self.visible.iter().map(|index| &self.students[*index]).collect()
+    }
+
+    // 重新读取数据，尽量保持选中同一个学生
+    fn reload(&mut self, repository: &dyn StudentRepository) -> Result<(), String> {
+        let selected = self.selected().map(|student| student.id);
+        self.students = repository.list()?;
+        self.courses = repository.list_courses()?;
+        self.filter(selected);
+        Ok(())
+    }
+
+    // 按搜索内容筛选：姓名或班级包含搜索内容（不区分大小写），或者 ID 以它开头
+    fn filter(&mut self, keep: Option<u32>) {
+        let search = self.search.trim().to_lowercase();
+        self.visible = self
+            .students
+            .iter()
+            .enumerate()
+            .filter(|(_, student)| {
+                search.is_empty()
+                    || student.name.to_lowercase().contains(&search)
+                    || student.class.as_ref().is_some_and(|class| class.to_lowercase().contains(&search))
+                    || student.id.to_string().starts_with(&search)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let position = keep.and_then(|id| self.visible.iter().position(|index| self.students[*index].id == id));
+        self.selected = position.unwrap_or(self.selected).min(self.visible.len().saturating_sub(1));
+    }
+
+    fn select(&mut self, id: u32) {
+        if let Some(position) = self.visible.iter().position(|index| self.students[*index].id == id) {
+            self.selected = position;
+        }
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let last = self.visible.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + offset).clamp(0, last) as usize;
+    }
+
+    // 处理一次按键；修改数据的操作写入 repository 并记入撤销历史
+    pub fn handle_key(&mut self, key: KeyEvent, repository: &mut Undoable, context: &Context) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match &mut self.mode {
+            Mode::Browse => self.browse_key(key.code, repository),
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.mode = Mode::Browse;
+                    self.filter(self.selected().map(|student| student.id));
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.filter(None);
+                }
+                KeyCode::Char(c) => {
+                    self.search.push(c);
+                    self.selected = 0;
+                    self.filter(None);
+                }
+                KeyCode::Up | KeyCode::Down => {
+                    self.mode = Mode::Browse;
+                    self.browse_key(key.code, repository);
+                }
+                _ => {}
+            },
+            Mode::Form(form) => match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Browse;
+                    self.message = Some(String::from("已取消"));
+                }
+                KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1) % form.fields.len(),
+                KeyCode::BackTab | KeyCode::Up => form.focus = (form.focus + form.fields.len() - 1) % form.fields.len(),
+                KeyCode::Backspace => {
+                    form.fields[form.focus].1.pop();
+                }
+                KeyCode::Char(c) => form.fields[form.focus].1.push(c),
+                KeyCode::Enter => {
+                    let form = form.clone();
+                    match submit(&form, repository, context) {
+                        Ok((id, message)) => {
+                            self.mode = Mode::Browse;
+                            self.message = Some(message);
+                            self.refresh(repository);
+                            self.select(id);
+                        }
+                        Err(e) => {
+                            if let Mode::Form(form) = &mut self.mode {
+                                form.error = Some(e);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Mode::ConfirmDelete(id) => {
+                let id = *id;
+                self.mode = Mode::Browse;
+                self.message = Some(if let KeyCode::Char('y' | 'Y') = key.code {
+                    match repository.remove(id) {
+                        Ok(true) => format!("学生ID {} 已成功删除（可以按 u 撤销）", id),
+                        Ok(false) => format!("未找到ID为 {} 的学生", id),
+                        Err(e) => format!("删除失败: {}", e),
+                    }
+                } else {
+                    String::from("已取消删除操作")
+                });
+                self.refresh(repository);
+            }
+            Mode::Help => self.mode = Mode::Browse,
+        }
+    }
+
+    fn browse_key(&mut self, code: KeyCode, repository: &mut Undoable) {
+        let selected = self.selected().cloned();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc if self.search.is_empty() || code == KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc => {
+                self.search.clear();
+                self.filter(selected.map(|student| student.id));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-(PAGE as isize)),
+            KeyCode::PageDown => self.move_selection(PAGE as isize),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.move_selection(isize::MAX / 2),
+            KeyCode::Char('/') => self.mode = Mode::Search,
+            KeyCode::Char('?') => self.mode = Mode::Help,
+            KeyCode::Char('a') => {
+                let fields = [("姓名", String::new()), ("年龄", String::new()), ("班级（可以不填）", String::new())];
+                self.mode = Mode::Form(Form::new(FormKind::AddStudent, &fields));
+            }
+            KeyCode::Char('u') => {
+                self.message = Some(match repository.undo() {
+                    Ok(Some(description)) => format!("已撤销: {}", description),
+                    Ok(None) => String::from("没有可以撤销的操作"),
+                    Err(e) => format!("撤销失败: {}", e),
+                });
+                self.refresh(repository);
+            }
+            KeyCode::Char('r') => {
+                self.message = Some(match repository.redo() {
+                    Ok(Some(description)) => format!("已重做: {}", description),
+                    Ok(None) => String::from("没有可以重做的操作"),
+                    Err(e) => format!("重做失败: {}", e),
+                });
+                self.refresh(repository);
+            }
+            KeyCode::Char(c @ ('e' | 'c' | 'g' | 'd')) => {
+                let Some(student) = selected else {
+                    self.message = Some(String::from("系统中尚无学生记录"));
+                    return;
+                };
+                let id = student.id;
+                self.mode = match c {
+                    'e' => {
+                        let fields = [
+                            ("姓名", student.name.clone()),
+                            ("年龄", student.age.to_string()),
+                            ("班级（可以不填）", student.class.clone().unwrap_or_default()),
+                        ];
+                        Mode::Form(Form::new(FormKind::EditStudent(id), &fields))
+                    }
+                    'c' => Mode::Form(Form::new(FormKind::Enroll(id), &[("课程代码或名称", String::new())])),
+                    'g' => {
+                        let fields = [("课程代码或名称", String::new()), ("分数", String::new()), ("修改原因（可以不填）", String::new())];
+                        Mode::Form(Form::new(FormKind::Grade(id), &fields))
+                    }
+                    _ => Mode::ConfirmDelete(id),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    // 修改后重新读取数据，读取失败时显示在状态栏
+    fn refresh(&mut self, repository: &dyn StudentRepository) {
+        if let Err(e) = self.reload(repository) {
+            self.message = Some(format!("读取数据失败: {}", e));
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, context: &Context) {
+        let [search_area, main_area, status_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(5), Constraint::Length(1)]).areas(frame.area());
+        let [table_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main_area);
+
+        let search_style = if self.mode == Mode::Search {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let search_text = if self.search.is_empty() && self.mode != Mode::Search {
+            Span::styled("按 / 搜索姓名、班级或 ID", Style::default().fg(Color::DarkGray))
+        } else {
+            Span::raw(self.search.clone())
+        };
+        let title = format!(" 搜索（{}/{} 名学生） ", self.visible.len(), self.students.len());
+        frame.render_widget(Paragraph::new(search_text).block(Block::bordered().title(title).style(search_style)), search_area);
+
+        self.draw_table(frame, table_area, context);
+        self.draw_detail(frame, detail_area, context);
+
+        let status = match &self.message {
+            Some(message) => Line::from(vec![Span::styled(message.clone(), Style::default().fg(Color::Cyan)), Span::raw("  "), Span::raw(HELP)]),
+            None => Line::from(HELP),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        match &self.mode {
+            Mode::Form(form) => draw_form(frame, form),
+            Mode::ConfirmDelete(id) => {
+                let name = self.students.iter().find(|student| student.id == *id).map(|student| student.name.as_str());
+                let text = format!("确认删除ID为 {} 的学生记录（{}）? (y/n)", id, name.unwrap_or(""));
+                let area = popup(frame.area(), 50, 3);
+                frame.render_widget(Clear, area);
+                frame.render_widget(Paragraph::new(text).block(Block::bordered().title(" 删除学生 ")), area);
+            }
+            Mode::Help => {
+                let lines: Vec<Line> = [
+                    "↑/↓、k/j   选择学生",
+                    "PgUp/PgDn  翻页，Home/End 第一个/最后一个",
+                    "/          搜索姓名、班级或 ID，输入时立即筛选，Esc 清除",
+                    "a          添加学生",
+                    "e          修改选中学生的姓名、年龄和班级",
+                    "c          为选中的学生选课",
+                    "g          为选中的学生记录成绩",
+                    "d          删除选中的学生",
+                    "u / r      撤销 / 重做",
+                    "表单中     Tab 切换字段，Enter 提交，Esc 取消",
+                    "q          退出",
+                ]
+                .into_iter()
+                .map(Line::from)
+                .collect();
+                let area = popup(frame.area(), 64, lines.len() as u16 + 2);
+                frame.render_widget(Clear, area);
+                frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" 快捷键（按任意键关闭） ")), area);
+            }
+            Mode::Browse | Mode::Search => {}
+        }
+    }
+
+    fn draw_table(&self, frame: &mut Frame, area: Rect, context: &Context) {
+        let header = Row::new(["ID", "姓名", "年龄", "班级", "平均分", "GPA"]).style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.visible().into_iter().map(|student| {
+            Row::new(vec![
+                Cell::from(student.id.to_string()),
+                Cell::from(student.name.clone()),
+                Cell::from(student.age.to_string()),
+                Cell::from(student.class.clone().unwrap_or_else(|| String::from("-"))),
+                Cell::from(optional(grading::weighted_average(student, &self.courses))),
+                Cell::from(optional(context.grading.gpa(student, &self.courses))),
+            ])
+        });
+        let widths = [
+            Constraint::Length(5),
+            Constraint::Min(8),
+            Constraint::Length(4),
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Length(5),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(" 学生 "))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        let mut state = TableState::default().with_selected(self.selected().map(|_| self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect, context: &Context) {
+        let block = Block::bordered().title(" 详情 ");
+        let Some(student) = self.selected() else {
+            let text = if self.students.is_empty() { "系统中尚无学生记录，按 a 添加" } else { "没有符合搜索条件的学生" };
+            frame.render_widget(Paragraph::new(text).block(block), area);
+            return;
+        };
+        let mut lines = vec![
+            Line::from(format!("学生ID: {}", student.id)),
+            Line::from(format!("姓名: {}", student.name)),
+            Line::from(format!("年龄: {}", student.age)),
+            Line::from(format!("班级: {}", student.class.as_deref().unwrap_or("未分班"))),
+            Line::from(""),
+            Line::from(Span::styled("选修课程:", Style::default().add_modifier(Modifier::BOLD))),
+        ];
+        if student.enrollments.is_empty() {
+            lines.push(Line::from("  尚未选修课程"));
+        }
+        for code in &student.enrollments {
+            let course = self.courses.iter().find(|course| &course.code == code);
+            let name = course.map(|course| course.name.as_str()).unwrap_or("");
+            let grade = match (student.current_grade(code), course) {
+                (Some(score), Some(course)) => format!("{:.1} {}", score, context.grading.letter(course, score)),
+                (Some(score), None) => format!("{:.1}", score),
+                (None, _) => String::from("尚无成绩"),
+            };
+            lines.push(Line::from(format!("  {} {}: {}", code, name, grade)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("平均分（学分加权）: {}", optional(grading::weighted_average(student, &self.courses)))));
+        let scale = context.grading.default();
+        lines.push(Line::from(format!("GPA（{} 制）: {}", scale.name, optional(context.grading.gpa(student, &self.courses)))));
+        frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+    }
+}
+
+// 检查并提交表单，返回涉及的学生 ID 和显示在状态栏的结果
+fn submit(form: &Form, repository: &mut dyn StudentRepository, context: &Context) -> Result<(u32, String), String> {
+    match form.kind {
+        FormKind::AddStudent | FormKind::EditStudent(_) => {
+            let name = form.value(0).to_string();
+            if name.is_empty() {
+                return Err(String::from("姓名不能为空"));
+            }
+            let age = import::parse_age(form.value(1))?;
+            let class = Some(form.value(2).to_string()).filter(|class| !class.is_empty());
+            match form.kind {
+                FormKind::EditStudent(id) => {
+                    repository.modify(id, &mut |student| {
+                        student.name = name.clone();
+                        student.age = age;
+                        student.class = class.clone();
+                        Ok(())
+                    })?;
+                    Ok((id, format!("学生ID {} 的信息已修改", id)))
+                }
+                _ => {
+                    let id = repository.insert(name.clone(), age)?;
+                    if class.is_some() {
+                        repository.modify(id, &mut |student| {
+                            student.class = class.clone();
+                            Ok(())
+                        })?;
+                    }
+                    Ok((id, format!("学生 {} 已添加，ID为: {}", name, id)))
+                }
+            }
+        }
+        FormKind::Enroll(id) => {
+            let course = find_course(repository, form.value(0))?;
+            repository.modify(id, &mut |student| {
+                if !student.enroll(course.code.clone()) {
+                    return Err(format!("学生 {} 已经选修了课程 {}", student.name, course.name));
+                }
+                Ok(())
+            })?;
+            Ok((id, format!("学生ID {} 已选修课程 {} {}", id, course.code, course.name)))
+        }
+        FormKind::Grade(id) => {
+            let course = find_course(repository, form.value(0))?;
+            let score = import::parse_score(form.value(1))?;
+            repository.modify(id, &mut |student| {
+                if !student.is_enrolled(&course.code) {
+                    return Err(format!("学生 {} 没有选修课程 {}，请先选课", student.name, course.name));
+                }
+                student.record_grade(&course, score, form.value(2), context.clock.as_ref())
+            })?;
+            Ok((id, format!("已为学生ID {} 添加课程 {} 的成绩: {}", id, course.name, score)))
+        }
+    }
+}
+
+fn find_course(repository: &dyn StudentRepository, text: &str) -> Result<Course, String> {
+    if text.is_empty() {
+        return Err(String::from("请输入课程代码或名称"));
+    }
+    repository.find_course(text)?.ok_or_else(|| format!("课程 {} 没有登记", text))
+}
+
+fn optional(value: Option<f32>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => String::from("-"),
+    }
+}
+
+fn draw_form(frame: &mut Frame, form: &Form) {
+    let mut lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, (label, value))| {
+            if index == form.focus {
+                Line::from(vec![
+                    Span::styled(format!("> {}: ", label), Style::default().fg(Color::Yellow)),
+                    Span::raw(value.clone()),
+                    Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+                ])
+            } else {
+                Line::from(format!("  {}: {}", label, value))
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    match &form.error {
+        Some(error) => lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red)))),
+        None => lines.push(Line::from(Span::styled("Tab 切换字段，Enter 提交，Esc 取消", Style::default().fg(Color::DarkGray)))),
+    }
+    let area = popup(frame.area(), 60, lines.len() as u16 + 2);
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(form.title())).wrap(Wrap { trim: false }), area);
+}
+
+// 居中的弹出窗口，不超过屏幕大小
+fn popup(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+// 在终端中运行界面，退出时恢复终端；本次运行中的修改可以撤销和重做
+pub fn run(repository: Box<dyn StudentRepository>, context: &Context) -> Result<(), String> {
+    let mut undoable = Undoable::new(repository);
+    let mut app = App::new(&undoable)?;
+    let mut terminal = ratatui::try_init().map_err(|e| format!("无法打开终端界面: {}", e))?;
+    let result = (|| -> std::io::Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| app.draw(frame, context))?;
+            if let Event::Key(key) = event::read()? {
+                // Windows 上松开按键也会产生事件
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key, &mut undoable, context);
+                }
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result.map_err(|e| format!("终端界面出错: {}", e))
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grading::GradingConfig;
+    use crate::history::ManualClock;
+    use crate::student::StudentManagement;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn context() -> Context {
+        Context {
+            clock: Box::new(ManualClock::new(1_709_281_800)),
+            ..Context::new(GradingConfig::builtin())
+        }
+    }
+
+    fn press(app: &mut App, repository: &mut Undoable, keys: &str) {
+        let context = context();
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            app.handle_key(KeyEvent::from(code), repository, &context);
+        }
+    }
+
+    fn setup() -> (App, Undoable) {
+        let mut repository = StudentManagement::new();
+        repository.insert_course(Course::new("MATH101", "数学", 4.0, 100.0).unwrap()).unwrap();
+        for (name, age) in [("张三", 20), ("李四", 21), ("王五", 19)] {
+            repository.insert(String::from(name), age).unwrap();
+        }
+        let undoable = Undoable::new(Box::new(repository));
+        (App::new(&undoable).unwrap(), undoable)
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.visible().into_iter().map(|student| student.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_search_as_you_type() {
+        let (mut app, mut repository) = setup();
+        press(&mut app, &mut repository, "/李");
+        assert_eq!(app.mode, Mode::Search);
+        assert_eq!(names(&app), vec!["李四"]);
+        press(&mut app, &mut repository, "\x08");
+        assert_eq!(names(&app).len(), 3);
+        press(&mut app, &mut repository, "3\n");
+        assert_eq!(app.mode, Mode::Browse);
+        assert_eq!(app.selected().unwrap().name, "王五");
+        // 搜索时按 q 是输入，浏览时 Esc 先清除搜索
+        press(&mut app, &mut repository, "\x1b");
+        assert!(!app.quit && app.search.is_empty());
+        assert_eq!(app.selected().unwrap().name, "王五");
+        press(&mut app, &mut repository, "q");
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_forms_validate_and_save() {
+        let (mut app, mut repository) = setup();
+        press(&mut app, &mut repository, "a赵六\t200\n");
+        let Mode::Form(form) = &app.mode else { panic!("表单应该保持打开") };
+        assert!(form.error.as_ref().unwrap().contains("无效年龄"));
+        press(&mut app, &mut repository, "\x08\x08\x0818\t一班\n");
+        assert_eq!(app.mode, Mode::Browse);
+        assert_eq!(app.message.as_deref(), Some("学生 赵六 已添加，ID为: 4"));
+        assert_eq!(app.selected().unwrap().class.as_deref(), Some("一班"));
+
+        // 没有选课时不能记录成绩，选课后分数超出满分时提示
+        press(&mut app, &mut repository, "g数学\t90\n");
+        let Mode::Form(form) = &app.mode else { panic!("表单应该保持打开") };
+        assert!(form.error.as_ref().unwrap().contains("请先选课"));
+        press(&mut app, &mut repository, "\x1bc数学\n");
+        assert_eq!(app.message.as_deref(), Some("学生ID 4 已选修课程 MATH101 数学"));
+        press(&mut app, &mut repository, "g数学\t120\n");
+        let Mode::Form(form) = &app.mode else { panic!("表单应该保持打开") };
+        assert!(form.error.as_ref().unwrap().contains("0到100"));
+        press(&mut app, &mut repository, "\x08\x08\x0895\n");
+        assert_eq!(repository.get(4).unwrap().unwrap().current_grade("MATH101"), Some(95.0));
+
+        press(&mut app, &mut repository, "e\x08七\n");
+        assert_eq!(repository.get(4).unwrap().unwrap().name, "赵七");
+    }
+
+    #[test]
+    fn test_delete_and_undo() {
+        let (mut app, mut repository) = setup();
+        press(&mut app, &mut repository, "jdn");
+        assert_eq!(app.message.as_deref(), Some("已取消删除操作"));
+        press(&mut app, &mut repository, "dy");
+        assert_eq!(app.message.as_deref(), Some("学生ID 2 已成功删除（可以按 u 撤销）"));
+        assert_eq!(names(&app), vec!["张三", "王五"]);
+        press(&mut app, &mut repository, "u");
+        assert_eq!(app.message.as_deref(), Some("已撤销: 删除学生 李四（ID 2）"));
+        assert_eq!(names(&app).len(), 3);
+        press(&mut app, &mut repository, "r");
+        assert_eq!(names(&app).len(), 2);
+    }
+
+    // 屏幕上的文字；全角字符后面的占位格是空格，比较时去掉所有空格
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).filter(|s| *s != " ").collect()
+    }
+
+    #[test]
+    fn test_draw() {
+        let (mut app, mut repository) = setup();
+        press(&mut app, &mut repository, "j");
+        let context = context();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame, &context)).unwrap();
+        let text = screen(&terminal);
+        assert!(text.contains("搜索（3/3名学生）"));
+        assert!(text.contains("学生ID:2"));
+        assert!(text.contains("尚未选修课程"));
+
+        // 新的终端，避免 TestBackend 增量更新时全角字符后面残留旧内容
+        press(&mut app, &mut repository, "a");
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame, &context)).unwrap();
+        assert!(screen(&terminal).contains("添加学生"));
+    }
+}
@@ -0,0 +1,482 @@
+// 撤销和重做
+//
+// Undoable 包装任意一个 StudentRepository，每次修改都记成一个可以反向执行的操作：
+// 添加学生的反向操作是删除，删除学生的反向操作是按原来的 ID 放回（包括选课、成绩和历史），
+// 修改学生（选课、记录成绩等）的反向操作是换回修改前的记录（记录在这之后又被改过时拒绝撤销），一次 batch 中的修改作为一个操作整批撤销。
+// 操作保存在有上限的撤销栈中，超过上限时丢掉最早的操作；做了新的修改后重做栈清空。
+
+use std::collections::VecDeque;
+
+use crate::course::Course;
+use crate::repository::{Query, StudentRepository};
+use crate::student::Student;
+
+// 默认最多可以撤销的操作数
+pub const DEFAULT_LIMIT: usize = 50;
+
+// 一次可以撤销的修改，保存撤销和重做需要的完整记录
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Insert { student: Student },
+    Update { before: Student, after: Student },
+    Remove { student: Student },
+    AddCourse { course: Course },
+    RemoveCourse { course: Course },
+    // batch 中按顺序完成的多项修改
+    Batch { operations: Vec<Operation> },
+}
+
+impl Operation {
+    // 显示给用户的说明，例如 "删除学生 张三（ID 1）"
+    pub fn description(&self) -> String {
+        match self {
+            Operation::Insert { student } => format!("添加学生 {}（ID {}）", student.name, student.id),
+            Operation::Update { after, .. } => format!("修改学生 {}（ID {}）", after.name, after.id),
+            Operation::Remove { student } => format!("删除学生 {}（ID {}）", student.name, student.id),
+            Operation::AddCourse { course } => format!("添加课程 {} {}", course.code, course.name),
+            Operation::RemoveCourse { course } => format!("删除课程 {} {}", course.code, course.name),
+            Operation::Batch { operations } => format!("批量修改（{} 项）", operations.len()),
+        }
+    }
+
+    fn undo(&self, repository: &mut dyn StudentRepository) -> Result<(), String> {
+        match self {
+            Operation::Insert { student } => remove_existing(repository, student.id),
+            Operation::Update { before, after } => replace_unchanged(repository, after, before, "撤销"),
+            Operation::Remove { student } => repository.restore(student.clone()),
+            Operation::AddCourse { course } => remove_existing_course(repository, &course.code),
+            Operation::RemoveCourse { course } => repository.insert_course(course.clone()),
+            // 倒序撤销每一项，有一项失败时整批都不撤销
+            Operation::Batch { operations } => repository.batch(&mut |repository| {
+                operations.iter().rev().try_for_each(|operation| operation.undo(repository))
+            }),
+        }
+    }
+
+    fn redo(&self, repository: &mut dyn StudentRepository) -> Result<(), String> {
+        match self {
+            // 按原来的 ID 放回，之后的操作记录的 ID 仍然有效
+            Operation::Insert { student } => repository.restore(student.clone()),
+            Operation::Update { before, after } => replace_unchanged(repository, before, after, "重做"),
+            Operation::Remove { student } => remove_existing(repository, student.id),
+            Operation::AddCourse { course } => repository.insert_course(course.clone()),
+            Operation::RemoveCourse { course } => remove_existing_course(repository, &course.code),
+            Operation::Batch { operations } => repository.batch(&mut |repository| {
+                operations.iter().try_for_each(|operation| operation.redo(repository))
+            }),
+        }
+    }
+}
+
+// 学生的当前记录仍是 expected 时才换成 replacement；其他线程或程序在这之后改过这个学生时返回错误，
+// 不会覆盖别人的修改。检查和替换在同一次 modify 中完成
+fn replace_unchanged(
+    repository: &mut dyn StudentRepository,
+    expected: &Student,
+    replacement: &Student,
+    action: &str,
+) -> Result<(), String> {
+    repository.modify(expected.id, &mut |current| {
+        if current != expected {
+            return Err(format!("学生 {}（ID {}）之后又被修改过，不能{}", expected.name, expected.id, action));
+        }
+        *current = replacement.clone();
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn remove_existing(repository: &mut dyn StudentRepository, id: u32) -> Result<(), String> {
+    if repository.remove(id)? {
+        Ok(())
+    } else {
+        Err(format!("未找到ID为 {} 的学生", id))
+    }
+}
+
+fn remove_existing_course(repository: &mut dyn StudentRepository, code: &str) -> Result<(), String> {
+    if repository.remove_course(code)? {
+        Ok(())
+    } else {
+        Err(format!("未找到课程 {}", code))
+    }
+}
+
+// 记录撤销历史的仓库，读取直接交给被包装的仓库
+pub struct Undoable {
+    repository: Box<dyn StudentRepository>,
+    done: VecDeque<Operation>,
+    undone: Vec<Operation>,
+    limit: usize,
+}
+
+impl Undoable {
+    pub fn new(repository: Box<dyn StudentRepository>) -> Undoable {
+        Undoable::with_limit(repository, DEFAULT_LIMIT)
+    }
+
+    pub fn with_limit(repository: Box<dyn StudentRepository>, limit: usize) -> Undoable {
+        Undoable {
+            repository,
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            limit,
+        }
+    }
+
+    // 撤销最近一次修改，返回它的说明；没有可以撤销的修改时返回 None。
+    // 撤销失败时操作留在撤销栈中
+    pub fn undo(&mut self) -> Result<Option<String>, String> {
+        let Some(operation) = self.done.pop_back() else {
+            return Ok(None);
+        };
+        if let Err(e) = operation.undo(self.repository.as_mut()) {
+            self.done.push_back(operation);
+            return Err(e);
+        }
+        let description = operation.description();
+        self.undone.push(operation);
+        Ok(Some(description))
+    }
+
+    // 重做最近一次撤销的修改，返回它的说明；没有可以重做的修改时返回 None
+    pub fn redo(&mut self) -> Result<Option<String>, String> {
+        let Some(operation) = self.undone.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = operation.redo(self.repository.as_mut()) {
+            self.undone.push(operation);
+            return Err(e);
+        }
+        let description = operation.description();
+        self.push_done(operation);
+        Ok(Some(description))
+    }
+
+    // 取回被包装的仓库，撤销历史随之丢弃
+    pub fn into_inner(self) -> Box<dyn StudentRepository> {
+        self.repository
+    }
+
+    // 可以撤销的操作数
+    pub fn undo_count(&self) -> usize {
+        self.done.len()
+    }
+
+    // 可以重做的操作数
+    pub fn redo_count(&self) -> usize {
+        self.undone.len()
+    }
+
+    fn push_done(&mut self, operation: Operation) {
+        self.done.push_back(operation);
+        while self.done.len() > self.limit {
+            self.done.pop_front();
+        }
+    }
+
+    // 做了新的修改，之前撤销的操作不能再重做
+    fn record(&mut self, operation: Operation) {
+        self.undone.clear();
+        self.push_done(operation);
+    }
+
+    // 在被包装的仓库上执行修改并记录；一次 batch 中的多项修改合成一个操作，一起撤销和重做
+    fn track<T>(&mut self, change: impl FnOnce(&mut Recorder) -> Result<T, String>) -> Result<T, String> {
+        let mut recorder = Recorder {
+            repository: self.repository.as_mut(),
+            operations: Vec::new(),
+        };
+        let result = change(&mut recorder)?;
+        let mut operations = recorder.operations;
+        let operation = if operations.len() > 1 {
+            Some(Operation::Batch { operations })
+        } else {
+            operations.pop()
+        };
+        if let Some(operation) = operation {
+            self.record(operation);
+        }
+        Ok(result)
+    }
+}
+
+impl StudentRepository for Undoable {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        self.repository.get(id)
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        self.track(|recorder| recorder.insert(name, age))
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        self.track(|recorder| recorder.update(student))
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        self.track(|recorder| recorder.modify(id, change))
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        self.track(|recorder| recorder.remove(id))
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        self.track(|recorder| recorder.restore(student))
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        self.track(|recorder| recorder.batch(change))
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.repository.list()
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        self.repository.query(query)
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        self.track(|recorder| recorder.insert_course(course))
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        self.track(|recorder| recorder.remove_course(code))
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        self.repository.list_courses()
+    }
+}
+
+// 执行修改并记下对应的操作；batch 中的修改交给被包装仓库的 batch，成功后才记下其中的所有操作
+struct Recorder<'a> {
+    repository: &'a mut dyn StudentRepository,
+    operations: Vec<Operation>,
+}
+
+impl StudentRepository for Recorder<'_> {
+    fn get(&self, id: u32) -> Result<Option<Student>, String> {
+        self.repository.get(id)
+    }
+
+    fn insert(&mut self, name: String, age: u8) -> Result<u32, String> {
+        let id = self.repository.insert(name, age)?;
+        if let Some(student) = self.repository.get(id)? {
+            self.operations.push(Operation::Insert { student });
+        }
+        Ok(id)
+    }
+
+    fn update(&mut self, student: Student) -> Result<(), String> {
+        let before = self.repository.get(student.id)?;
+        self.repository.update(student.clone())?;
+        if let Some(before) = before {
+            self.operations.push(Operation::Update { before, after: student });
+        }
+        Ok(())
+    }
+
+    fn modify(&mut self, id: u32, change: &mut dyn FnMut(&mut Student) -> Result<(), String>) -> Result<Student, String> {
+        // 修改前的记录取自被包装的仓库交给 change 的那一份，而不是另外读取的可能已经过时的记录
+        let mut before = None;
+        let after = self.repository.modify(id, &mut |student| {
+            before = Some(student.clone());
+            change(student)
+        })?;
+        if let Some(before) = before {
+            self.operations.push(Operation::Update { before, after: after.clone() });
+        }
+        Ok(after)
+    }
+
+    fn remove(&mut self, id: u32) -> Result<bool, String> {
+        let Some(student) = self.repository.get(id)? else {
+            return self.repository.remove(id);
+        };
+        let removed = self.repository.remove(id)?;
+        if removed {
+            self.operations.push(Operation::Remove { student });
+        }
+        Ok(removed)
+    }
+
+    fn restore(&mut self, student: Student) -> Result<(), String> {
+        self.repository.restore(student.clone())?;
+        self.operations.push(Operation::Insert { student });
+        Ok(())
+    }
+
+    fn batch(&mut self, change: &mut dyn FnMut(&mut dyn StudentRepository) -> Result<(), String>) -> Result<(), String> {
+        let mut operations = Vec::new();
+        self.repository.batch(&mut |repository| {
+            let mut recorder = Recorder {
+                repository,
+                operations: Vec::new(),
+            };
+            change(&mut recorder)?;
+            operations = recorder.operations;
+            Ok(())
+        })?;
+        self.operations.extend(operations);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Student>, String> {
+        self.repository.list()
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Student>, String> {
+        self.repository.query(query)
+    }
+
+    fn insert_course(&mut self, course: Course) -> Result<(), String> {
+        self.repository.insert_course(course.clone())?;
+        // 登记时课程代码会被规范化，记录实际保存的课程
+        let course = self.repository.find_course(&course.code)?.unwrap_or(course);
+        self.operations.push(Operation::AddCourse { course });
+        Ok(())
+    }
+
+    fn remove_course(&mut self, code: &str) -> Result<bool, String> {
+        let Some(course) = self.repository.find_course(code)? else {
+            return self.repository.remove_course(code);
+        };
+        let removed = self.repository.remove_course(code)?;
+        if removed {
+            self.operations.push(Operation::RemoveCourse { course });
+        }
+        Ok(removed)
+    }
+
+    fn list_courses(&self) -> Result<Vec<Course>, String> {
+        self.repository.list_courses()
+    }
+}
+
+// 测试代码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ManualClock;
+    use crate::repository::conformance;
+    use crate::shared::SharedStudents;
+    use crate::student::StudentManagement;
+
+    #[test]
+    fn test_undo_remove_restores_grades() {
+        let clock = ManualClock::default();
+        let mut undoable = Undoable::new(Box::new(StudentManagement::new()));
+        let math = Course::new("MATH101", "数学", 4.0, 100.0).unwrap();
+        undoable.insert_course(math.clone()).unwrap();
+        let id = undoable.insert(String::from("张三"), 20).unwrap();
+        let mut student = undoable.get(id).unwrap().unwrap();
+        student.enroll(math.code.clone());
+        student.record_grade(&math, 92.0, "", &clock).unwrap();
+        undoable.update(student.clone()).unwrap();
+
+        assert!(undoable.remove(id).unwrap());
+        assert_eq!(undoable.undo().unwrap().unwrap(), "删除学生 张三（ID 1）");
+        assert_eq!(undoable.get(id).unwrap(), Some(student.clone()));
+
+        // 撤销成绩，再撤销添加学生
+        assert_eq!(undoable.undo().unwrap().unwrap(), "修改学生 张三（ID 1）");
+        assert_eq!(undoable.get(id).unwrap().unwrap().current_grade("MATH101"), None);
+        undoable.undo().unwrap();
+        assert_eq!(undoable.get(id).unwrap(), None);
+        undoable.undo().unwrap();
+        assert!(undoable.list_courses().unwrap().is_empty());
+        assert_eq!(undoable.undo().unwrap(), None);
+
+        // 全部重做后回到删除前后的状态
+        for _ in 0..3 {
+            undoable.redo().unwrap().unwrap();
+        }
+        assert_eq!(undoable.get(id).unwrap(), Some(student));
+        assert_eq!(undoable.redo().unwrap().unwrap(), "删除学生 张三（ID 1）");
+        assert_eq!(undoable.get(id).unwrap(), None);
+        assert_eq!(undoable.redo().unwrap(), None);
+
+        // 新学生不会重用撤销后又删除的 ID
+        assert_eq!(undoable.insert(String::from("李四"), 21).unwrap(), id + 1);
+    }
+
+    #[test]
+    fn test_undo_keeps_later_changes() {
+        let mut other = SharedStudents::new();
+        let mut undoable = Undoable::new(Box::new(other.clone()));
+        let id = undoable.insert(String::from("张三"), 20).unwrap();
+        undoable
+            .modify(id, &mut |student| {
+                student.age = 21;
+                Ok(())
+            })
+            .unwrap();
+
+        // 另一个句柄之后改过这个学生，撤销会覆盖它的修改，所以拒绝
+        other
+            .modify(id, &mut |student| {
+                student.name = String::from("张三丰");
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(undoable.undo().unwrap_err(), "学生 张三（ID 1）之后又被修改过，不能撤销");
+        assert_eq!(undoable.undo_count(), 2);
+        assert_eq!(undoable.get(id).unwrap().unwrap().name, "张三丰");
+
+        // 重做同样要求记录仍是撤销后的样子
+        other
+            .modify(id, &mut |student| {
+                student.name = String::from("张三");
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(undoable.undo().unwrap().unwrap(), "修改学生 张三（ID 1）");
+        assert_eq!(undoable.get(id).unwrap().unwrap().age, 20);
+        other
+            .modify(id, &mut |student| {
+                student.age = 22;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(undoable.redo().unwrap_err(), "学生 张三（ID 1）之后又被修改过，不能重做");
+        assert_eq!(undoable.redo_count(), 1);
+        assert_eq!(undoable.get(id).unwrap().unwrap().age, 22);
+    }
+
+    #[test]
+    fn test_new_change_clears_redo() {
+        let mut undoable = Undoable::new(Box::new(StudentManagement::new()));
+        undoable.insert(String::from("张三"), 20).unwrap();
+        undoable.undo().unwrap();
+        assert_eq!(undoable.redo_count(), 1);
+        undoable.insert(String::from("李四"), 21).unwrap();
+        assert_eq!(undoable.redo_count(), 0);
+        assert_eq!(undoable.redo().unwrap(), None);
+
+        // 失败的修改和没有删除任何记录的删除不会记录
+        assert!(undoable.update(Student::new(42, String::from("王五"), 19)).is_err());
+        assert!(!undoable.remove(42).unwrap());
+        assert_eq!(undoable.undo_count(), 1);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut undoable = Undoable::with_limit(Box::new(StudentManagement::new()), 3);
+        for name in ["甲", "乙", "丙", "丁", "戊"] {
+            undoable.insert(String::from(name), 20).unwrap();
+        }
+        assert_eq!(undoable.undo_count(), 3);
+        while undoable.undo().unwrap().is_some() {}
+        // 最早的两次添加已经超出上限，不能撤销
+        let names: Vec<String> = undoable.list().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["甲", "乙"]);
+    }
+
+    #[test]
+    fn test_undoable_conformance() {
+        conformance::run(&mut || Box::new(Undoable::new(Box::new(StudentManagement::new()))));
+    }
+}